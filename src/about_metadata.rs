@@ -25,6 +25,12 @@ pub struct AboutMetadata {
     pub website: Option<String>,
     /// The website label.
     pub website_label: Option<String>,
+    /// Details of the build, e.g. "2024-06-01 abc123" for its date and commit.
+    ///
+    /// ## Notes
+    ///
+    /// - Shown on its own line after the version, prefixed with `Build: `.
+    pub build_info: Option<String>,
 }
 
 impl AboutMetadata {
@@ -38,6 +44,20 @@ impl AboutMetadata {
                 .unwrap_or_default()
         ))
     }
+
+    /// The version as shown in the about dialog: [`version`](Self::version) followed by
+    /// [`short_version`](Self::short_version) in parentheses, then [`build_info`](Self::build_info)
+    /// on its own line prefixed with `Build: `, for example `"1.0.0 (1.0)\nBuild: 2024-06-01 abc123"`.
+    ///
+    /// Returns `None` when neither the version nor the build info are set.
+    pub fn detailed_version(&self) -> Option<String> {
+        let lines = [
+            self.full_version(),
+            self.build_info.as_ref().map(|b| format!("Build: {b}")),
+        ];
+        let lines = lines.into_iter().flatten().collect::<Vec<_>>();
+        (!lines.is_empty()).then(|| lines.join("\n"))
+    }
 }
 
 /// Creates [`AboutMetadata`] from [Cargo metadata][cargo]. The following fields are set by this function.
@@ -156,6 +176,16 @@ impl AboutMetadataBuilder {
         self
     }
 
+    /// Sets the details of the build, e.g. "2024-06-01 abc123" for its date and commit.
+    ///
+    /// ## Notes
+    ///
+    /// - Shown on its own line after the version, prefixed with `Build: `.
+    pub fn build_info<S: Into<String>>(mut self, build_info: Option<S>) -> Self {
+        self.0.build_info = build_info.map(|s| s.into());
+        self
+    }
+
     /// Construct the final [`AboutMetadata`]
     pub fn build(self) -> AboutMetadata {
         self.0
@@ -175,4 +205,13 @@ mod tests {
         assert!(m.comments.is_some());
         assert!(m.license.is_some());
     }
+
+    #[test]
+    fn test_build_info() {
+        let m = crate::AboutMetadataBuilder::new()
+            .version(Some("1.0.0"))
+            .build_info(Some("2024-06-01 abc123"))
+            .build();
+        assert_eq!(m.build_info, Some("2024-06-01 abc123".to_string()));
+    }
 }
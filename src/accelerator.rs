@@ -21,11 +21,20 @@
 //! # // statement above about ordering is no longer valid.
 //! # assert!("shift+KeyQ+alt".parse::<Accelerator>().is_err());
 //! ```
+//! and displayed, for example in a settings screen, with the text shown in the accelerator
+//! column of menu items: the modifiers in `Ctrl+Alt+Shift+Windows` order, then the key.
+//! ```no_run
+//! # use muda_win::accelerator::{Accelerator, Modifiers, Code};
+//! let accelerator = Accelerator::new(Some(Modifiers::SHIFT | Modifiers::CONTROL), Code::KeyS);
+//! assert_eq!(accelerator.to_string(), "Ctrl+Shift+S");
+//! ```
 //!
 
 pub use keyboard_types::{Code, Modifiers};
 use std::error::Error as StdError;
-use std::{borrow::Borrow, hash::Hash, str::FromStr};
+use std::{borrow::Borrow, hash::Hash, str::FromStr, sync::Mutex};
+
+use crate::MenuId;
 
 pub const CMD_OR_CTRL: Modifiers = Modifiers::CONTROL;
 
@@ -160,6 +169,44 @@ impl TryFrom<String> for Accelerator {
     }
 }
 
+/// Accelerators reserved with [`register_reserved`] and their owners.
+static RESERVED: Mutex<Vec<(Accelerator, MenuId)>> = Mutex::new(Vec::new());
+
+/// Reserves `accelerator` for `owner` in the whole process, so menus that coexist, like a menu bar
+/// and several context menus, can make sure they don't reuse each other's accelerators.
+///
+/// Reserving an accelerator again for the same owner succeeds. If it is already reserved for another
+/// owner, the reserved accelerator is returned as the error, see [`reserved_owner`] to find out by whom.
+///
+/// This is only a coordination layer, the accelerators of menus aren't checked against it.
+pub fn register_reserved(accelerator: Accelerator, owner: MenuId) -> Result<(), Accelerator> {
+    let mut reserved = RESERVED.lock().unwrap_or_else(|e| e.into_inner());
+    match reserved.iter().find(|(a, _)| *a == accelerator) {
+        Some((_, current)) if *current == owner => Ok(()),
+        Some((a, _)) => Err(*a),
+        None => {
+            reserved.push((accelerator, owner));
+            Ok(())
+        }
+    }
+}
+
+/// Releases `accelerator` reserved with [`register_reserved`], returns its owner if it was reserved.
+pub fn unregister_reserved(accelerator: &Accelerator) -> Option<MenuId> {
+    let mut reserved = RESERVED.lock().unwrap_or_else(|e| e.into_inner());
+    let position = reserved.iter().position(|(a, _)| a == accelerator)?;
+    Some(reserved.remove(position).1)
+}
+
+/// The owner `accelerator` is reserved for with [`register_reserved`], if any.
+pub fn reserved_owner(accelerator: &Accelerator) -> Option<MenuId> {
+    let reserved = RESERVED.lock().unwrap_or_else(|e| e.into_inner());
+    reserved
+        .iter()
+        .find(|(a, _)| a == accelerator)
+        .map(|(_, owner)| owner.clone())
+}
+
 fn parse_accelerator(accelerator: &str) -> Result<Accelerator, AcceleratorParseError> {
     let tokens = accelerator.split('+').collect::<Vec<&str>>();
 
@@ -200,7 +247,7 @@ fn parse_accelerator(accelerator: &str) -> Result<Accelerator, AcceleratorParseE
                     "CONTROL" | "CTRL" => {
                         mods |= Modifiers::CONTROL;
                     }
-                    "COMMAND" | "CMD" | "SUPER" => {
+                    "COMMAND" | "CMD" | "SUPER" | "WINDOWS" | "WIN" => {
                         mods |= Modifiers::META;
                     }
                     "SHIFT" => {
@@ -451,3 +498,49 @@ fn test_equality() {
             && h5.id() != h6.id()
     );
 }
+
+#[test]
+fn test_display_canonical_order() {
+    let h1 = parse_accelerator("Shift+Ctrl+S").unwrap();
+    let h2 = parse_accelerator("Ctrl+Shift+S").unwrap();
+    assert_eq!(h1.to_string(), h2.to_string());
+    assert_eq!(h1.to_string(), "Ctrl+Shift+S");
+
+    let h3 = parse_accelerator("Super+Shift+Alt+Control+KeyK").unwrap();
+    assert_eq!(h3.to_string(), "Ctrl+Alt+Shift+Windows+K");
+    assert_eq!(h3.to_string().parse::<Accelerator>().unwrap(), h3);
+}
+
+#[test]
+fn test_raw_accel() {
+    use windows_sys::Win32::UI::{
+        Input::KeyboardAndMouse::VK_S,
+        WindowsAndMessaging::{FCONTROL, FVIRTKEY},
+    };
+
+    let accelerator = parse_accelerator("Ctrl+S").unwrap();
+    assert_eq!(
+        accelerator.raw_accel().unwrap(),
+        (FVIRTKEY | FCONTROL, VK_S)
+    );
+
+    let accel = accelerator.to_accel(7).unwrap();
+    assert_eq!(
+        (accel.fVirt, accel.key, accel.cmd),
+        (FVIRTKEY | FCONTROL, VK_S, 7)
+    );
+}
+
+#[test]
+fn test_reserved_accelerators() {
+    let ctrl_s = parse_accelerator("Ctrl+S").unwrap();
+    register_reserved(ctrl_s, MenuId::new("editor")).unwrap();
+    register_reserved(ctrl_s, MenuId::new("editor")).unwrap();
+    assert_eq!(register_reserved(ctrl_s, MenuId::new("tray")), Err(ctrl_s));
+    assert_eq!(reserved_owner(&ctrl_s), Some(MenuId::new("editor")));
+
+    assert_eq!(unregister_reserved(&ctrl_s), Some(MenuId::new("editor")));
+    assert_eq!(unregister_reserved(&ctrl_s), None);
+    register_reserved(ctrl_s, MenuId::new("tray")).unwrap();
+    unregister_reserved(&ctrl_s);
+}
@@ -1,10 +1,12 @@
 mod check;
 mod icon;
 mod normal;
+mod standard;
 mod submenu;
 
 pub use crate::about_metadata::AboutMetadataBuilder;
 pub use check::*;
 pub use icon::*;
 pub use normal::*;
+pub use standard::*;
 pub use submenu::*;
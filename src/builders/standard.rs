@@ -0,0 +1,158 @@
+use crate::{
+    accelerator::{Accelerator, CMD_OR_CTRL},
+    items::PredefinedMenuItemType,
+    AboutMetadata, IsMenuItem, Menu, MenuId, MenuItem, PredefinedMenuItem, Submenu,
+};
+use keyboard_types::Code;
+
+/// The ids of the submenus and items of [`Menu::standard_app_menu`], to find them with
+/// [`Menu::find_item_by_id`] and to match their [`MenuEvent`](crate::MenuEvent)s.
+pub mod standard_ids {
+    /// The File submenu.
+    pub const FILE: &str = "file";
+    /// The New item of the File submenu.
+    pub const NEW: &str = "file.new";
+    /// The Open item of the File submenu.
+    pub const OPEN: &str = "file.open";
+    /// The Save item of the File submenu.
+    pub const SAVE: &str = "file.save";
+    /// The predefined Quit item of the File submenu.
+    pub const QUIT: &str = "file.quit";
+
+    /// The Edit submenu.
+    pub const EDIT: &str = "edit";
+    /// The Undo item of the Edit submenu.
+    pub const UNDO: &str = "edit.undo";
+    /// The Redo item of the Edit submenu.
+    pub const REDO: &str = "edit.redo";
+    /// The predefined Cut item of the Edit submenu.
+    pub const CUT: &str = "edit.cut";
+    /// The predefined Copy item of the Edit submenu.
+    pub const COPY: &str = "edit.copy";
+    /// The predefined Paste item of the Edit submenu.
+    pub const PASTE: &str = "edit.paste";
+    /// The predefined Select All item of the Edit submenu.
+    pub const SELECT_ALL: &str = "edit.select_all";
+
+    /// The Help submenu.
+    pub const HELP: &str = "help";
+    /// The About item of the Help submenu, predefined when it has
+    /// [`about_metadata`](super::StandardAppMenuBuilder::about_metadata).
+    pub const ABOUT: &str = "help.about";
+}
+
+/// A builder type for the conventional menu bar of [`Menu::standard_app_menu`].
+#[derive(Clone, Debug)]
+pub struct StandardAppMenuBuilder {
+    file: bool,
+    edit: bool,
+    help: bool,
+    about_metadata: Option<AboutMetadata>,
+}
+
+impl Default for StandardAppMenuBuilder {
+    fn default() -> Self {
+        Self {
+            file: true,
+            edit: true,
+            help: true,
+            about_metadata: None,
+        }
+    }
+}
+
+impl StandardAppMenuBuilder {
+    /// Create a builder with all the submenus included.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Include the File submenu, with New, Open, Save and Quit. `true` by default.
+    pub fn file(mut self, file: bool) -> Self {
+        self.file = file;
+        self
+    }
+
+    /// Include the Edit submenu, with Undo, Redo, Cut, Copy, Paste and Select All. `true` by default.
+    pub fn edit(mut self, edit: bool) -> Self {
+        self.edit = edit;
+        self
+    }
+
+    /// Include the Help submenu, with About. `true` by default.
+    pub fn help(mut self, help: bool) -> Self {
+        self.help = help;
+        self
+    }
+
+    /// Set the metadata shown by the About item, which only emits its
+    /// [`MenuEvent`](crate::MenuEvent) without it.
+    pub fn about_metadata(mut self, metadata: AboutMetadata) -> Self {
+        self.about_metadata = Some(metadata);
+        self
+    }
+
+    /// Build the menu.
+    pub fn build(self) -> crate::Result<Menu> {
+        use standard_ids::*;
+
+        let menu = Menu::new();
+        let predefined = |id: &str, item_type, text: Option<&str>| {
+            PredefinedMenuItem::new_with_id(item_type, text, Some(MenuId::new(id)))
+        };
+        let shortcut = |code| Some(Accelerator::new(Some(CMD_OR_CTRL), code));
+
+        if self.file {
+            menu.append(&Submenu::with_id_and_items(
+                FILE,
+                "&File",
+                true,
+                &[
+                    &MenuItem::with_id(NEW, "&New", true, shortcut(Code::KeyN)),
+                    &MenuItem::with_id(OPEN, "&Open...", true, shortcut(Code::KeyO)),
+                    &MenuItem::with_id(SAVE, "&Save", true, shortcut(Code::KeyS)),
+                    &PredefinedMenuItem::separator(),
+                    &predefined(QUIT, PredefinedMenuItemType::Quit, None),
+                ],
+            )?)?;
+        }
+
+        if self.edit {
+            menu.append(&Submenu::with_id_and_items(
+                EDIT,
+                "&Edit",
+                true,
+                &[
+                    // apps implement undo, the predefined items have no built-in action
+                    &MenuItem::with_id(UNDO, "&Undo", true, shortcut(Code::KeyZ)),
+                    &MenuItem::with_id(REDO, "&Redo", true, shortcut(Code::KeyY)),
+                    &PredefinedMenuItem::separator(),
+                    &predefined(CUT, PredefinedMenuItemType::Cut, None),
+                    &predefined(COPY, PredefinedMenuItemType::Copy, None),
+                    &predefined(PASTE, PredefinedMenuItemType::Paste, None),
+                    &PredefinedMenuItem::separator(),
+                    &predefined(SELECT_ALL, PredefinedMenuItemType::SelectAll, None),
+                ],
+            )?)?;
+        }
+
+        if self.help {
+            let about: Box<dyn IsMenuItem> = match self.about_metadata {
+                Some(metadata) => Box::new(predefined(
+                    ABOUT,
+                    PredefinedMenuItemType::About(Some(metadata)),
+                    None,
+                )),
+                None => Box::new(MenuItem::with_id(ABOUT, "&About", true, None)),
+            };
+            menu.append(&Submenu::with_id_and_items(
+                HELP,
+                "&Help",
+                true,
+                &[about.as_ref()],
+            )?)?;
+        }
+
+        Ok(menu)
+    }
+}
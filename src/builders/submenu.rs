@@ -1,4 +1,7 @@
-use crate::{IsMenuItem, MenuId, Submenu};
+use crate::{
+    accelerator::Accelerator, CheckMenuItem, IsMenuItem, MenuId, MenuItem, MenuItemKind,
+    PredefinedMenuItem, Submenu,
+};
 
 /// A builder type for [`Submenu`]
 #[derive(Clone, Default)]
@@ -64,3 +67,104 @@ impl<'a> SubmenuBuilder<'a> {
         }
     }
 }
+
+/// Adds items to the submenu created by [`Submenu::build`].
+///
+/// Errors, like an invalid accelerator, don't stop the chain of calls,
+/// the first one is returned by [`Submenu::build`] instead.
+#[derive(Default)]
+pub struct SubmenuItemsBuilder {
+    items: Vec<MenuItemKind>,
+    error: Option<crate::Error>,
+}
+
+impl std::fmt::Debug for SubmenuItemsBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SubmenuItemsBuilder")
+            .field("items", &self.items.len())
+            .field("error", &self.error)
+            .finish()
+    }
+}
+
+impl SubmenuItemsBuilder {
+    /// Add an enabled [`MenuItem`], `accelerator` is parsed like [`Accelerator::from_str`](std::str::FromStr::from_str).
+    pub fn item<I: Into<MenuId>, S: AsRef<str>>(
+        &mut self,
+        id: I,
+        text: S,
+        accelerator: Option<&str>,
+    ) -> &mut Self {
+        if let Some(accelerator) = self.accelerator(accelerator) {
+            self.add(&MenuItem::with_id(id, text, true, accelerator));
+        }
+        self
+    }
+
+    /// Add an enabled [`CheckMenuItem`], `accelerator` is parsed like [`Accelerator::from_str`](std::str::FromStr::from_str).
+    pub fn check<I: Into<MenuId>, S: AsRef<str>>(
+        &mut self,
+        id: I,
+        text: S,
+        checked: bool,
+        accelerator: Option<&str>,
+    ) -> &mut Self {
+        if let Some(accelerator) = self.accelerator(accelerator) {
+            self.add(&CheckMenuItem::with_id(
+                id,
+                text,
+                true,
+                checked,
+                accelerator,
+            ));
+        }
+        self
+    }
+
+    /// Add a separator.
+    pub fn separator(&mut self) -> &mut Self {
+        self.add(&PredefinedMenuItem::separator())
+    }
+
+    /// Add a nested submenu with the items added by `f`.
+    pub fn submenu<S: AsRef<str>, F: FnOnce(&mut SubmenuItemsBuilder)>(
+        &mut self,
+        text: S,
+        enabled: bool,
+        f: F,
+    ) -> &mut Self {
+        match Submenu::build(text, enabled, f) {
+            Ok(submenu) => self.add(&submenu),
+            Err(e) => self.fail(e),
+        }
+    }
+
+    /// Add any other item, for example an [`IconMenuItem`](crate::IconMenuItem).
+    pub fn add(&mut self, item: &dyn IsMenuItem) -> &mut Self {
+        self.items.push(item.kind());
+        self
+    }
+
+    /// Parses `accelerator`, returns `None` if it is invalid.
+    fn accelerator(&mut self, accelerator: Option<&str>) -> Option<Option<Accelerator>> {
+        match accelerator.map(str::parse::<Accelerator>).transpose() {
+            Ok(accelerator) => Some(accelerator),
+            Err(e) => {
+                self.fail(e.into());
+                None
+            }
+        }
+    }
+
+    fn fail(&mut self, error: crate::Error) -> &mut Self {
+        self.error.get_or_insert(error);
+        self
+    }
+
+    pub(crate) fn finish(self) -> crate::Result<Vec<MenuItemKind>> {
+        match self.error {
+            Some(e) => Err(e),
+            None => Ok(self.items),
+        }
+    }
+}
@@ -7,7 +7,21 @@ pub enum Error {
     NotAChildOfThisMenu,
     NotInitialized,
     AlreadyInitialized,
+    InvalidWindowClass,
+    NoSystemMenu,
+    WrongThread,
     AcceleratorParseError(AcceleratorParseError),
+    InvalidKeymapLine {
+        line: usize,
+        reason: String,
+    },
+    #[cfg(feature = "serde")]
+    InvalidJson(serde_json::Error),
+    #[cfg(feature = "raw-window-handle")]
+    WindowHandle(raw_window_handle::HandleError),
+    #[cfg(feature = "raw-window-handle")]
+    NotAWin32Window,
+    OsError(std::io::Error),
 }
 
 impl std::fmt::Display for Error {
@@ -21,7 +35,23 @@ impl std::fmt::Display for Error {
             Error::AlreadyInitialized => {
                 write!(f, "This menu has already been initialized for this hwnd")
             }
+            Error::InvalidWindowClass => write!(f, "The window class atom is not valid"),
+            Error::NoSystemMenu => write!(f, "This hwnd has no system menu with a Close item"),
+            Error::WrongThread => write!(
+                f,
+                "This menu or hwnd belongs to another thread or this thread can't own menus, menus must be created, changed and added to windows on the same thread"
+            ),
             Error::AcceleratorParseError(err) => write!(f, "{}", err),
+            Error::InvalidKeymapLine { line, reason } => {
+                write!(f, "Invalid keymap at line {}: {}", line, reason)
+            }
+            #[cfg(feature = "serde")]
+            Error::InvalidJson(err) => write!(f, "Invalid menu JSON: {}", err),
+            #[cfg(feature = "raw-window-handle")]
+            Error::WindowHandle(err) => write!(f, "{}", err),
+            #[cfg(feature = "raw-window-handle")]
+            Error::NotAWin32Window => write!(f, "This window handle is not a Win32 window handle"),
+            Error::OsError(err) => write!(f, "{}", err),
         }
     }
 }
@@ -30,6 +60,11 @@ impl std::error::Error for Error {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             Error::AcceleratorParseError(err) => Some(err),
+            #[cfg(feature = "serde")]
+            Error::InvalidJson(err) => Some(err),
+            #[cfg(feature = "raw-window-handle")]
+            Error::WindowHandle(err) => Some(err),
+            Error::OsError(err) => Some(err),
             _ => None,
         }
     }
@@ -1,5 +1,5 @@
 use crate::platform_impl::PlatformIcon;
-use std::{error::Error, fmt, io, mem};
+use std::{error::Error, fmt, io, mem, path::PathBuf};
 
 #[repr(C)]
 #[derive(Debug)]
@@ -13,7 +13,8 @@ pub(crate) struct Pixel {
 pub(crate) const PIXEL_SIZE: usize = mem::size_of::<Pixel>();
 
 #[derive(Debug)]
-/// An error produced when using [`Icon::from_rgba`] with invalid arguments.
+/// An error produced when using [`Icon::from_rgba`] with invalid arguments,
+/// or when [`Icon::from_path`] can't load a file.
 pub enum BadIcon {
     /// Produced when the length of the `rgba` argument isn't divisible by 4, thus `rgba` can't be
     /// safely interpreted as 32bpp RGBA pixels.
@@ -28,6 +29,10 @@ pub enum BadIcon {
     },
     /// Produced when underlying OS functionality failed to create the icon
     OsError(io::Error),
+    /// Produced when the file passed to [`Icon::from_path`] doesn't exist.
+    FileNotFound { path: PathBuf },
+    /// Produced when the file passed to [`Icon::from_path`] is neither a PNG nor an ICO image.
+    UnsupportedFormat { path: PathBuf },
 }
 
 impl fmt::Display for BadIcon {
@@ -47,6 +52,11 @@ impl fmt::Display for BadIcon {
                 width, height, pixel_count, width_x_height,
             ),
             BadIcon::OsError(e) => write!(f, "OS error when instantiating the icon: {:?}", e),
+            BadIcon::FileNotFound { path } => write!(f, "The icon file {:?} doesn't exist.", path),
+            BadIcon::UnsupportedFormat { path } => write!(f,
+                "The icon file {:?} is neither a PNG nor an ICO image.",
+                path,
+            ),
         }
     }
 }
@@ -129,13 +139,29 @@ impl Icon {
         })
     }
 
-    /// Create an icon from a file path.
+    /// Returns the pixels of this icon as 32bpp RGBA data, along with its width and height.
     ///
-    /// Specify `size` to load a specific icon size from the file, or `None` to load the default
-    /// icon size from the file.
+    /// The pixels are read back from the native icon, so this works for icons loaded from
+    /// files and resources too. The alpha channel is straight (not premultiplied), icons
+    /// without an alpha channel get an alpha of `0` or `255` from their transparency mask.
+    ///
+    /// Returns empty data with a size of `0x0` for monochrome icons.
+    pub fn to_rgba(&self) -> (Vec<u8>, u32, u32) {
+        self.inner.to_rgba().unwrap_or_default()
+    }
+
+    /// Create an icon from a PNG or ICO file.
+    ///
+    /// The format is detected from the magic bytes of the file, or from its extension if they
+    /// match neither format. Specify `size` to load a specific icon size from the file, or `None`
+    /// to load the size closest to the one menu icons are drawn at from an ICO file, or the size
+    /// of the image from a PNG file.
     ///
     /// In cases where the specified size does not exist in the file, Windows may perform scaling
     /// to get an icon of the desired size.
+    ///
+    /// Returns [`BadIcon::FileNotFound`] if `path` doesn't exist and
+    /// [`BadIcon::UnsupportedFormat`] if it is neither a PNG nor an ICO file.
     #[cfg(windows)]
     pub fn from_path<P: AsRef<std::path::Path>>(
         path: P,
@@ -152,11 +178,56 @@ impl Icon {
     ///
     /// In cases where the specified size does not exist in the file, Windows may perform scaling
     /// to get an icon of the desired size.
+    ///
+    /// The resource is looked up in the module of the current process, use
+    /// [`Icon::from_resource_in_module`] for icons of a library.
+    /// Returns [`BadIcon::OsError`] if there is no icon resource with this id.
     #[cfg(windows)]
     pub fn from_resource(ordinal: u16, size: Option<(u32, u32)>) -> Result<Self, BadIcon> {
         let win_icon = PlatformIcon::from_resource(ordinal, size)?;
         Ok(Icon { inner: win_icon })
     }
+
+    /// Create an icon from a resource embedded in `module`, for example a resource-only DLL,
+    /// or in this executable or library if `module` is `None`.
+    ///
+    /// `module` is an [`HMODULE`](windows_sys::Win32::Foundation::HMODULE), which must stay loaded
+    /// while this function runs. The icon doesn't refer to the module once it is loaded.
+    ///
+    /// Specify `size` to load a specific icon size from the resource, or `None` to load the default
+    /// icon size. In cases where the specified size does not exist in the resource, Windows may
+    /// perform scaling to get an icon of the desired size.
+    ///
+    /// Returns [`BadIcon::OsError`] if `module` has no icon resource with this id.
+    #[cfg(windows)]
+    pub fn from_resource_in_module(
+        module: Option<isize>,
+        resource_id: u16,
+        size: Option<(u32, u32)>,
+    ) -> Result<Self, BadIcon> {
+        let win_icon = match module {
+            Some(module) => PlatformIcon::from_resource_in_module(module as _, resource_id, size)?,
+            None => PlatformIcon::from_resource(resource_id, size)?,
+        };
+        Ok(Icon { inner: win_icon })
+    }
+
+    /// Create an icon from an existing [`HICON`](windows_sys::Win32::UI::WindowsAndMessaging::HICON),
+    /// for example one returned by the shell.
+    ///
+    /// The handle is duplicated with `CopyIcon`, so ownership is not transferred: the caller still
+    /// owns `hicon` and destroys it as usual, while the copy is destroyed when the icon is dropped.
+    ///
+    /// Returns [`BadIcon::OsError`] if `hicon` can't be copied.
+    ///
+    /// # Safety
+    ///
+    /// `hicon` must be a valid icon handle.
+    #[cfg(windows)]
+    pub unsafe fn from_hicon(hicon: isize) -> Result<Self, BadIcon> {
+        let win_icon = PlatformIcon::from_hicon(hicon as _)?;
+        Ok(Icon { inner: win_icon })
+    }
 }
 
 /// A native Icon to be used for the menu item
@@ -280,3 +351,115 @@ pub enum NativeIcon {
     /// Permissions for guests.
     UserGuest,
 }
+
+#[cfg(test)]
+mod test {
+    use crate::Icon;
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_reads_back_rgba() {
+        let rgba = vec![
+            255, 0, 0, 255, //
+            0, 255, 0, 255, //
+            0, 0, 255, 128, //
+            10, 20, 30, 0, //
+        ];
+        let icon = Icon::from_rgba(rgba.clone(), 2, 2).unwrap();
+        assert_eq!(icon.to_rgba(), (rgba, 2, 2));
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_loads_icon_from_module_resource() {
+        use windows_sys::Win32::System::LibraryLoader::GetModuleHandleW;
+
+        let user32 = "user32.dll\0".encode_utf16().collect::<Vec<u16>>();
+        let user32 = unsafe { GetModuleHandleW(user32.as_ptr()) } as isize;
+        assert_ne!(user32, 0);
+
+        // the application icon of user32.dll
+        let icon = Icon::from_resource_in_module(Some(user32), 100, Some((32, 32))).unwrap();
+        let (rgba, width, height) = icon.to_rgba();
+        assert_eq!((width, height), (32, 32));
+        assert!(!rgba.is_empty());
+
+        assert!(matches!(
+            Icon::from_resource_in_module(Some(user32), 65535, None),
+            Err(crate::BadIcon::OsError(_))
+        ));
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_copies_hicon() {
+        use windows_sys::Win32::UI::WindowsAndMessaging::{LoadIconW, IDI_APPLICATION};
+
+        // a shared icon, which must never be destroyed
+        let hicon = unsafe { LoadIconW(std::ptr::null_mut(), IDI_APPLICATION) };
+        assert!(!hicon.is_null());
+
+        let icon = unsafe { Icon::from_hicon(hicon as _) }.unwrap();
+        let (rgba, width, height) = icon.to_rgba();
+        assert!(width > 0 && height > 0);
+        drop(icon);
+
+        // the copy was destroyed, not the handle of the caller
+        let icon = unsafe { Icon::from_hicon(hicon as _) }.unwrap();
+        assert_eq!(icon.to_rgba(), (rgba, width, height));
+
+        // an owned handle can be destroyed while the icon is still used
+        let owned = Icon::from_rgba(vec![255; 4 * 4], 2, 2).unwrap();
+        let copy = unsafe { Icon::from_hicon(owned.inner.handle() as _) }.unwrap();
+        drop(owned);
+        assert_eq!(copy.to_rgba(), (vec![255; 4 * 4], 2, 2));
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_reports_missing_resources() {
+        // the test executable has no icon resources
+        assert!(matches!(
+            Icon::from_resource(1, None),
+            Err(crate::BadIcon::OsError(_))
+        ));
+        assert!(matches!(
+            Icon::from_resource(1, Some((16, 16))),
+            Err(crate::BadIcon::OsError(_))
+        ));
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_loads_png_files() {
+        // a single opaque red pixel
+        const PNG: &[u8] = &[
+            137, 80, 78, 71, 13, 10, 26, 10, 0, 0, 0, 13, 73, 72, 68, 82, 0, 0, 0, 1, 0, 0, 0, 1,
+            8, 6, 0, 0, 0, 31, 21, 196, 137, 0, 0, 0, 13, 73, 68, 65, 84, 120, 218, 99, 252, 207,
+            192, 240, 31, 0, 5, 5, 2, 0, 95, 200, 241, 210, 0, 0, 0, 0, 73, 69, 78, 68, 174, 66,
+            96, 130,
+        ];
+
+        let dir = std::env::temp_dir().join("muda-win-it-loads-png-files");
+        std::fs::create_dir_all(&dir).unwrap();
+        // detected from the magic bytes whatever the extension
+        let png = dir.join("red.img");
+        std::fs::write(&png, PNG).unwrap();
+        let text = dir.join("notes.ico.txt");
+        std::fs::write(&text, "not an icon").unwrap();
+
+        let icon = Icon::from_path(&png, None).unwrap();
+        assert_eq!(icon.to_rgba(), (vec![255, 0, 0, 255], 1, 1));
+
+        assert!(matches!(
+            Icon::from_path(&text, None),
+            Err(crate::BadIcon::UnsupportedFormat { path }) if path == text
+        ));
+        assert!(matches!(
+            Icon::from_path(dir.join("missing.png"), None),
+            Err(crate::BadIcon::FileNotFound { .. })
+        ));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -77,21 +77,62 @@ impl CheckMenuItem {
         }
     }
 
+    /// Create a new check menu item that belongs to the radio group `group`.
+    ///
+    /// Checking an item of a group, by activating it or with [`CheckMenuItem::set_checked`],
+    /// unchecks the other items of the group, so at most one of them is checked at a time.
+    /// Activating the checked item keeps it checked. Items show a round bullet instead of
+    /// a check mark, and they can be spread over several submenus as long as they share `group`.
+    ///
+    /// - `text` could optionally contain an `&` before a character to assign this character as the mnemonic
+    ///   for this check menu item. To display a `&` without assigning a mnemenonic, use `&&`.
+    pub fn new_radio<S: AsRef<str>, G: AsRef<str>>(
+        text: S,
+        enabled: bool,
+        checked: bool,
+        group: G,
+    ) -> Self {
+        let item = Self::new(text, enabled, checked, None);
+        crate::platform_impl::radio::join(&item.inner, group.as_ref());
+        item
+    }
+
+    /// The radio group of this item, if it was created with [`CheckMenuItem::new_radio`].
+    pub fn radio_group(&self) -> Option<String> {
+        self.inner.borrow().radio_group().map(ToString::to_string)
+    }
+
     /// Returns a unique identifier associated with this submenu.
     pub fn id(&self) -> &MenuId {
         &self.id
     }
 
+    /// Returns the command id Windows uses for this check menu item, the low-order word of the
+    /// `wparam` of the `WM_COMMAND` it sends when the item is activated.
+    ///
+    /// Unlike [`Self::id`], it is assigned by this crate, for correlating messages handled
+    /// by hand-written Win32 code with this item.
+    pub fn command_id(&self) -> u16 {
+        self.inner.borrow().command_id()
+    }
+
     /// Get the text for this check menu item.
     pub fn text(&self) -> String {
         self.inner.borrow().text()
     }
 
+    /// Get the label of this check menu item as Windows displays it,
+    /// see [`MenuItem::display_label`](crate::MenuItem::display_label).
+    pub fn display_label(&self) -> String {
+        self.inner.borrow().display_label()
+    }
+
     /// Set the text for this check menu item. `text` could optionally contain
     /// an `&` before a character to assign this character as the mnemonic
     /// for this check menu item. To display a `&` without assigning a mnemenonic, use `&&`.
     pub fn set_text<S: AsRef<str>>(&self, text: S) {
-        self.inner.borrow_mut().set_text(text.as_ref())
+        let change = self.inner.borrow_mut().set_text(text.as_ref());
+        change.notify();
     }
 
     /// Get whether this check menu item is enabled or not.
@@ -101,7 +142,23 @@ impl CheckMenuItem {
 
     /// Enable or disable this check menu item.
     pub fn set_enabled(&self, enabled: bool) {
-        self.inner.borrow_mut().set_enabled(enabled)
+        let change = self.inner.borrow_mut().set_enabled(enabled);
+        change.notify();
+    }
+
+    /// Whether this check menu item is visible, see [`set_visible`](Self::set_visible).
+    pub fn is_visible(&self) -> bool {
+        self.inner.borrow().is_visible()
+    }
+
+    /// Show or hide this check menu item without removing it, it keeps its position when shown again
+    /// even if other items around it were hidden or shown meanwhile.
+    /// Its accelerator doesn't activate it while it is hidden.
+    ///
+    /// A hidden check menu item is still returned by `items()`, but not by `visible_items()`.
+    pub fn set_visible(&self, visible: bool) {
+        let change = self.inner.borrow_mut().set_visible(visible);
+        change.notify();
     }
 
     /// Set this check menu item accelerator.
@@ -115,8 +172,30 @@ impl CheckMenuItem {
     }
 
     /// Check or Uncheck this check menu item.
+    ///
+    /// Checking an item of a radio group unchecks the other items of the group.
     pub fn set_checked(&self, checked: bool) {
-        self.inner.borrow_mut().set_checked(checked)
+        crate::platform_impl::radio::set_checked(&self.inner, checked);
+    }
+
+    /// Create a new check menu item with the same properties as this one (text, enabled state, checked state and accelerator)
+    /// but with a new generated id, so it can be added to a menu independently.
+    ///
+    /// Unlike [`Clone`], which returns another handle to the same item, changes to
+    /// the returned item don't affect this one. The copy of a radio item joins its group
+    /// unchecked, so this one stays checked.
+    pub fn clone_as_new(&self) -> CheckMenuItem {
+        let item = self.inner.borrow().clone_as_new();
+        let item = Self {
+            id: Rc::new(item.id().clone()),
+            inner: Rc::new(RefCell::new(item)),
+        };
+        if let Some(group) = self.radio_group() {
+            let change = item.inner.borrow_mut().set_checked(false);
+            change.notify();
+            crate::platform_impl::radio::join(&item.inner, &group);
+        }
+        item
     }
 
     /// Convert this menu item into its menu ID.
@@ -0,0 +1,94 @@
+use crate::{sealed::IsMenuItemBase, CheckMenuItem, IsMenuItem, MenuId, MenuItemKind, Submenu};
+
+/// A [`Submenu`] of independent toggles, for example a list of settings.
+///
+/// Each entry is a [`CheckMenuItem`] that toggles itself when clicked,
+/// this type keeps track of them so their states can be read and changed by id.
+#[derive(Clone)]
+pub struct CheckListSubmenu {
+    submenu: Submenu,
+    items: Vec<CheckMenuItem>,
+}
+
+impl IsMenuItemBase for CheckListSubmenu {}
+impl IsMenuItem for CheckListSubmenu {
+    fn kind(&self) -> MenuItemKind {
+        MenuItemKind::Submenu(self.submenu.clone())
+    }
+
+    fn id(&self) -> &MenuId {
+        self.submenu.id()
+    }
+
+    fn into_id(self) -> MenuId {
+        self.submenu.into_id()
+    }
+}
+
+impl CheckListSubmenu {
+    /// Create a new submenu with one enabled toggle for each `(label, id, checked)` entry.
+    ///
+    /// - `text` could optionally contain an `&` before a character to assign this character as the mnemonic
+    ///   for this submenu. To display a `&` without assigning a mnemenonic, use `&&`.
+    pub fn new<S: AsRef<str>, L: AsRef<str>, I: Clone + Into<MenuId>>(
+        text: S,
+        enabled: bool,
+        entries: &[(L, I, bool)],
+    ) -> crate::Result<Self> {
+        let submenu = Submenu::new(text, enabled);
+        let items = entries
+            .iter()
+            .map(|(label, id, checked)| {
+                CheckMenuItem::with_id(id.clone(), label, true, *checked, None)
+            })
+            .collect::<Vec<_>>();
+
+        for item in &items {
+            submenu.append(item)?;
+        }
+
+        Ok(Self { submenu, items })
+    }
+
+    /// The underlying submenu.
+    pub fn submenu(&self) -> &Submenu {
+        &self.submenu
+    }
+
+    /// The toggles of this list, in order.
+    pub fn items(&self) -> &[CheckMenuItem] {
+        &self.items
+    }
+
+    /// Check or uncheck the toggle with `id`.
+    ///
+    /// Returns `false` if this list has no toggle with this id.
+    pub fn set_checked<I: Into<MenuId>>(&self, id: I, checked: bool) -> bool {
+        let id = id.into();
+        match self.items.iter().find(|item| *item.id() == id) {
+            Some(item) => {
+                item.set_checked(checked);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Get whether the toggle with `id` is checked, `None` if this list has no toggle with this id.
+    pub fn is_checked<I: Into<MenuId>>(&self, id: I) -> Option<bool> {
+        let id = id.into();
+        self.items
+            .iter()
+            .find(|item| *item.id() == id)
+            .map(|item| item.is_checked())
+    }
+
+    /// The ids of the checked toggles, in order.
+    pub fn checked_ids(&self) -> Vec<MenuId> {
+        self.items
+            .iter()
+            .filter(|item| item.is_checked())
+            .map(|item| item.id().clone())
+            .collect()
+    }
+}
@@ -141,16 +141,32 @@ impl IconMenuItem {
         &self.id
     }
 
+    /// Returns the command id Windows uses for this icon menu item, the low-order word of the
+    /// `wparam` of the `WM_COMMAND` it sends when the item is activated.
+    ///
+    /// Unlike [`Self::id`], it is assigned by this crate, for correlating messages handled
+    /// by hand-written Win32 code with this item.
+    pub fn command_id(&self) -> u16 {
+        self.inner.borrow().command_id()
+    }
+
     /// Get the text for this check menu item.
     pub fn text(&self) -> String {
         self.inner.borrow().text()
     }
 
+    /// Get the label of this icon menu item as Windows displays it,
+    /// see [`MenuItem::display_label`](crate::MenuItem::display_label).
+    pub fn display_label(&self) -> String {
+        self.inner.borrow().display_label()
+    }
+
     /// Set the text for this check menu item. `text` could optionally contain
     /// an `&` before a character to assign this character as the mnemonic
     /// for this check menu item. To display a `&` without assigning a mnemenonic, use `&&`.
     pub fn set_text<S: AsRef<str>>(&self, text: S) {
-        self.inner.borrow_mut().set_text(text.as_ref())
+        let change = self.inner.borrow_mut().set_text(text.as_ref());
+        change.notify();
     }
 
     /// Get whether this check menu item is enabled or not.
@@ -160,7 +176,23 @@ impl IconMenuItem {
 
     /// Enable or disable this check menu item.
     pub fn set_enabled(&self, enabled: bool) {
-        self.inner.borrow_mut().set_enabled(enabled)
+        let change = self.inner.borrow_mut().set_enabled(enabled);
+        change.notify();
+    }
+
+    /// Whether this icon menu item is visible, see [`set_visible`](Self::set_visible).
+    pub fn is_visible(&self) -> bool {
+        self.inner.borrow().is_visible()
+    }
+
+    /// Show or hide this icon menu item without removing it, it keeps its position when shown again
+    /// even if other items around it were hidden or shown meanwhile.
+    /// Its accelerator doesn't activate it while it is hidden.
+    ///
+    /// A hidden icon menu item is still returned by `items()`, but not by `visible_items()`.
+    pub fn set_visible(&self, visible: bool) {
+        let change = self.inner.borrow_mut().set_visible(visible);
+        change.notify();
     }
 
     /// Set this icon menu item accelerator.
@@ -168,11 +200,30 @@ impl IconMenuItem {
         self.inner.borrow_mut().set_accelerator(accelerator)
     }
 
+    /// Get the icon of this menu item, as it was passed to [`IconMenuItem::new`]
+    /// or [`IconMenuItem::set_icon`].
+    pub fn icon(&self) -> Option<Icon> {
+        self.inner.borrow().icon()
+    }
+
     /// Change this menu item icon or remove it.
     pub fn set_icon(&self, icon: Option<Icon>) {
         self.inner.borrow_mut().set_icon(icon)
     }
 
+    /// Create a new icon menu item with the same properties as this one (text, enabled state, icon and accelerator)
+    /// but with a new generated id, so it can be added to a menu independently.
+    ///
+    /// Unlike [`Clone`], which returns another handle to the same item, changes to
+    /// the returned item don't affect this one.
+    pub fn clone_as_new(&self) -> IconMenuItem {
+        let item = self.inner.borrow().clone_as_new();
+        Self {
+            id: Rc::new(item.id().clone()),
+            inner: Rc::new(RefCell::new(item)),
+        }
+    }
+
     /// Convert this menu item into its menu ID.
     pub fn into_id(mut self) -> MenuId {
         // Note: `Rc::into_inner` is available from Rust 1.70
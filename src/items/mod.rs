@@ -1,10 +1,12 @@
 mod check;
+mod check_list;
 mod icon;
 mod normal;
 mod predefined;
 mod submenu;
 
 pub use check::*;
+pub use check_list::*;
 pub use icon::*;
 pub use normal::*;
 pub use predefined::*;
@@ -55,4 +57,384 @@ mod test {
         let item = PredefinedMenuItem::separator();
         assert_eq!(item.id().clone(), item.into_id());
     }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_returns_command_ids() {
+        use crate::ContextMenu;
+        use windows_sys::Win32::UI::WindowsAndMessaging::GetMenuItemID;
+
+        let item = MenuItem::new("Item", true, None);
+        let check = CheckMenuItem::new("Check", true, false, None);
+        let icon = IconMenuItem::new("Icon", true, None, None);
+        let copy = PredefinedMenuItem::copy(None);
+        let menu = crate::Menu::with_items(&[&item, &check, &icon, &copy]).unwrap();
+
+        let command_ids = [
+            item.command_id(),
+            check.command_id(),
+            icon.command_id(),
+            copy.command_id(),
+        ];
+        for (position, command_id) in command_ids.into_iter().enumerate() {
+            let id = unsafe { GetMenuItemID(menu.hpopupmenu() as _, position as _) };
+            assert_eq!(id, command_id as u32);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_returns_display_labels() {
+        use crate::{
+            accelerator::{Accelerator, Code, Modifiers},
+            ContextMenu,
+        };
+        use windows_sys::Win32::UI::WindowsAndMessaging::{GetMenuStringW, MF_BYPOSITION};
+
+        let ctrl_s = Accelerator::new(Some(Modifiers::CONTROL), Code::KeyS);
+        let save = MenuItem::new("&Save", true, Some(ctrl_s));
+        assert_eq!(save.text(), "&Save");
+        assert_eq!(save.display_label(), "&Save\tCtrl+S");
+
+        let submenu = Submenu::with_items("&File", true, &[&save]).unwrap();
+        assert_eq!(submenu.display_label(), "&File");
+        let menu = crate::Menu::with_items(&[&save]).unwrap();
+        assert_eq!(save.display_label(), "&Save\tCtrl+S");
+
+        let mut label = [0u16; 32];
+        let len = unsafe {
+            GetMenuStringW(
+                menu.hpopupmenu() as _,
+                0,
+                label.as_mut_ptr(),
+                label.len() as _,
+                MF_BYPOSITION,
+            )
+        };
+        assert_eq!(
+            String::from_utf16_lossy(&label[..len as usize]),
+            save.display_label()
+        );
+
+        save.set_accelerator(None).unwrap();
+        assert_eq!(save.display_label(), "&Save");
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_sets_literal_text() {
+        use crate::ContextMenu;
+        use windows_sys::Win32::UI::WindowsAndMessaging::{GetMenuStringW, MF_BYPOSITION};
+
+        let item = MenuItem::literal("Tom & Jerry", true, None);
+        assert_eq!(item.text(), "Tom && Jerry");
+
+        let menu = crate::Menu::with_items(&[&item]).unwrap();
+        item.set_text_literal("R&D && QA");
+        assert_eq!(item.text(), "R&&D &&&& QA");
+
+        let mut label = [0u16; 32];
+        let len = unsafe {
+            GetMenuStringW(
+                menu.hpopupmenu() as _,
+                0,
+                label.as_mut_ptr(),
+                label.len() as _,
+                MF_BYPOSITION,
+            )
+        };
+        assert_eq!(
+            String::from_utf16_lossy(&label[..len as usize]),
+            "R&&D &&&& QA"
+        );
+
+        // mnemonics still work with `set_text`
+        item.set_text("&Open");
+        assert_eq!(item.text(), "&Open");
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_returns_the_icon_source() {
+        use crate::Icon;
+
+        let rgba = vec![
+            255, 0, 0, 255, //
+            0, 255, 0, 255, //
+            0, 0, 255, 255, //
+            255, 255, 255, 255, //
+        ];
+        let icon = Icon::from_rgba(rgba.clone(), 2, 2).unwrap();
+        let item = IconMenuItem::new("Icon", true, Some(icon), None);
+        assert_eq!(item.icon().unwrap().to_rgba(), (rgba, 2, 2));
+
+        item.set_icon(None);
+        assert!(item.icon().is_none());
+        assert!(IconMenuItem::new("No icon", true, None, None)
+            .icon()
+            .is_none());
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_toggles_check_list_entries() {
+        use crate::CheckListSubmenu;
+
+        let list = CheckListSubmenu::new(
+            "Options",
+            true,
+            &[
+                ("Word Wrap", "wrap", false),
+                ("Line Numbers", "numbers", true),
+                ("Minimap", "minimap", false),
+            ],
+        )
+        .unwrap();
+        assert_eq!(list.submenu().items().len(), 3);
+        assert_eq!(list.checked_ids(), vec![MenuId::new("numbers")]);
+
+        assert!(list.set_checked("wrap", true));
+        assert!(list.set_checked("numbers", false));
+        assert!(!list.set_checked("missing", true));
+
+        assert_eq!(list.is_checked("wrap"), Some(true));
+        assert_eq!(list.checked_ids(), vec![MenuId::new("wrap")]);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_clones_items_with_new_ids() {
+        use crate::{
+            accelerator::{Accelerator, Code, Modifiers},
+            Menu,
+        };
+
+        let accelerator = Accelerator::new(Some(Modifiers::CONTROL), Code::KeyD);
+        let item = MenuItem::with_id("duplicate", "&Duplicate", false, Some(accelerator));
+        item.set_badge(Some("new"));
+        item.set_value(Some(("42 KB", Some([128, 128, 128, 255]))));
+
+        let copy = item.clone_as_new();
+        assert_eq!(copy.text(), item.text());
+        assert_ne!(copy.id(), item.id());
+        assert!(!copy.is_enabled());
+        assert_eq!(copy.accelerator(), Some(accelerator));
+        assert_eq!(copy.badge(), Some("new".to_string()));
+        assert_eq!(copy.value(), item.value());
+
+        copy.set_text("Changed");
+        assert_eq!(item.text(), "&Duplicate");
+
+        let check = CheckMenuItem::new("Check", true, true, None);
+        let check_copy = check.clone_as_new();
+        assert!(check_copy.is_checked());
+        assert_ne!(check_copy.id(), check.id());
+
+        let menu = Menu::with_items(&[&item, &copy, &check, &check_copy]).unwrap();
+        assert_eq!(menu.items().len(), 4);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_sets_and_clears_badge() {
+        let item = MenuItem::new("Updates", true, None);
+        assert_eq!(item.badge(), None);
+        assert!(!item.inner.borrow().is_owner_drawn());
+
+        item.set_badge(Some("3"));
+        assert_eq!(item.badge(), Some("3".to_string()));
+        assert!(item.inner.borrow().is_owner_drawn());
+
+        item.set_badge(None::<&str>);
+        assert_eq!(item.badge(), None);
+        assert!(!item.inner.borrow().is_owner_drawn());
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_paints_styled_items_with_their_colors() {
+        use crate::{Menu, MenuItemStyle, MenuTheme};
+
+        let item = MenuItem::new("Brand", true, None);
+        let style = MenuItemStyle {
+            text_color: Some([0xFF, 0xFF, 0xFF, 0xFF]),
+            background_color: Some([0x12, 0x34, 0x56, 0xFF]),
+            highlight_color: None,
+        };
+        item.set_style(Some(style));
+        assert_eq!(item.style(), Some(style));
+        assert!(item.inner.borrow().is_owner_drawn());
+
+        let menu = Menu::with_items(&[&item]).unwrap();
+        let (rgba, _, _) = menu.render_to_bitmap(MenuTheme::Light, 0).unwrap();
+        let background_pixels = rgba
+            .chunks_exact(4)
+            .filter(|p| p[..3] == [0x12, 0x34, 0x56])
+            .count();
+        assert!(background_pixels > rgba.len() / 4 / 2);
+
+        item.set_style(None);
+        assert_eq!(item.style(), None);
+        assert!(!item.inner.borrow().is_owner_drawn());
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_creates_submenu_with_id_and_items() {
+        let item = MenuItem::with_id("child", "Child", true, None);
+        let submenu = Submenu::with_id_and_items(
+            "parent",
+            "Parent",
+            true,
+            &[&item, &PredefinedMenuItem::separator()],
+        )
+        .unwrap();
+
+        assert_eq!(submenu.id(), "parent");
+        let items = submenu.items();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].id(), "child");
+        assert!(items[1].as_predefined_menuitem().is_some());
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_appends_ellipsis_to_items_opening_dialogs() {
+        let item = MenuItem::new("Save As", true, None);
+        let _menu = crate::Menu::with_items(&[&item]).unwrap();
+
+        item.set_opens_dialog(true);
+        assert!(item.opens_dialog());
+        assert_eq!(item.inner.borrow().display_text(), "Save As\u{2026}");
+        assert_eq!(item.text(), "Save As");
+
+        item.set_text("Export...");
+        assert_eq!(item.inner.borrow().display_text(), "Export...");
+        assert_eq!(item.text(), "Export...");
+
+        item.set_opens_dialog(false);
+        item.set_text("Save As");
+        assert_eq!(item.inner.borrow().display_text(), "Save As");
+        assert_eq!(item.text(), "Save As");
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_replaces_submenu_items() {
+        let submenu = Submenu::with_items(
+            "Recent",
+            true,
+            &[
+                &MenuItem::with_id("a", "A", true, None),
+                &MenuItem::with_id("b", "B", true, None),
+                &MenuItem::with_id("c", "C", true, None),
+            ],
+        )
+        .unwrap();
+
+        submenu
+            .set_items(&[
+                &MenuItem::with_id("d", "D", true, None),
+                &PredefinedMenuItem::separator(),
+            ])
+            .unwrap();
+
+        let items = submenu.items();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].id(), "d");
+        assert!(matches!(items[1], crate::MenuItemKind::Predefined(_)));
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_moves_used_items_to_the_front() {
+        let ids = |submenu: &Submenu| {
+            submenu
+                .items()
+                .iter()
+                .map(|i| i.id().as_ref().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let c = MenuItem::with_id("c", "&Charlie", true, None);
+        let submenu = Submenu::with_items(
+            "Recent",
+            true,
+            &[
+                &MenuItem::with_id("b", "Bravo", true, None),
+                &MenuItem::with_id("a", "Alpha", true, None),
+                &c,
+            ],
+        )
+        .unwrap();
+
+        submenu.set_sort(crate::Sort::MostRecentFirst);
+        assert_eq!(ids(&submenu), ["b", "a", "c"]);
+
+        c.mark_used();
+        assert_eq!(ids(&submenu), ["c", "b", "a"]);
+
+        submenu.set_sort(crate::Sort::Alphabetical);
+        assert_eq!(ids(&submenu), ["a", "b", "c"]);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_builds_nested_submenus() {
+        let file = Submenu::build("&File", true, |b| {
+            b.item("open", "&Open", Some("Ctrl+O"))
+                .submenu("Open &Recent", true, |b| {
+                    b.item("clear", "Clear", None)
+                        .submenu("&Pinned", false, |b| {
+                            b.check("pin", "Pin", true, None);
+                        });
+                })
+                .separator();
+        })
+        .unwrap();
+
+        let items = file.items();
+        assert_eq!(items.len(), 3);
+        assert_eq!(
+            items[0].as_menuitem_unchecked().accelerator(),
+            Some("Ctrl+O".parse().unwrap())
+        );
+        let recent = items[1].as_submenu_unchecked();
+        assert_eq!(recent.text(), "Open &Recent");
+        let pinned = recent.items()[1].as_submenu_unchecked().clone();
+        assert!(!pinned.is_enabled());
+        assert!(pinned.items()[0].as_check_menuitem_unchecked().is_checked());
+
+        let result = Submenu::build("&File", true, |b| {
+            b.submenu("Nested", true, |b| {
+                b.item("bad", "Bad", Some("Ctrl+Nope"));
+            })
+            .item("after", "After", None);
+        });
+        assert!(matches!(
+            result,
+            Err(crate::Error::AcceleratorParseError(_))
+        ));
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_reads_submenu_state_through_kind() {
+        use crate::IsMenuItem;
+
+        let item = MenuItem::new("Item", true, None);
+        let check = CheckMenuItem::new("Check", true, true, None);
+        let submenu = Submenu::with_items("Submenu", true, &[&item, &check]).unwrap();
+
+        submenu.set_enabled(false);
+        assert!(!submenu.is_enabled());
+        assert!(!submenu.kind().is_enabled());
+        assert_eq!(submenu.kind().is_checked(), None);
+
+        let children = submenu.items();
+        assert!(children.iter().all(|c| c.is_enabled()));
+        assert_eq!(children[0].is_checked(), None);
+        assert_eq!(children[1].is_checked(), Some(true));
+        assert!(PredefinedMenuItem::separator().kind().is_enabled());
+    }
 }
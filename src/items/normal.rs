@@ -1,6 +1,8 @@
 use std::{cell::RefCell, mem, rc::Rc};
 
-use crate::{accelerator::Accelerator, sealed::IsMenuItemBase, IsMenuItem, MenuId, MenuItemKind};
+use crate::{
+    accelerator::Accelerator, icon::Icon, sealed::IsMenuItemBase, IsMenuItem, MenuId, MenuItemKind,
+};
 
 /// A menu item inside a [`Menu`] or [`Submenu`] and contains only text.
 ///
@@ -62,21 +64,63 @@ impl MenuItem {
         }
     }
 
+    /// Create a new menu item whose label is `text` verbatim, without a mnemonic.
+    ///
+    /// Unlike [`MenuItem::new`], every `&` of `text` is shown, which is what user-provided text
+    /// like file names needs. See [`MenuItem::set_text_literal`].
+    pub fn literal<S: AsRef<str>>(
+        text: S,
+        enabled: bool,
+        accelerator: Option<Accelerator>,
+    ) -> Self {
+        Self::new(
+            crate::platform_impl::escape_mnemonics(text.as_ref()),
+            enabled,
+            accelerator,
+        )
+    }
+
     /// Returns a unique identifier associated with this menu item.
     pub fn id(&self) -> &MenuId {
         &self.id
     }
 
+    /// Returns the command id Windows uses for this menu item, the low-order word of the
+    /// `wparam` of the `WM_COMMAND` it sends when the item is activated.
+    ///
+    /// Unlike [`Self::id`], it is assigned by this crate, for correlating messages handled
+    /// by hand-written Win32 code with this item.
+    pub fn command_id(&self) -> u16 {
+        self.inner.borrow().command_id()
+    }
+
     /// Set the text for this menu item.
     pub fn text(&self) -> String {
         self.inner.borrow().text()
     }
 
+    /// Get the label of this menu item as Windows displays it, unlike [`text`](Self::text)
+    /// it includes the ellipsis appended to the text and the accelerator after a `\t`,
+    /// like `"&Save\tCtrl+S"`. This is also what UI automation tools read.
+    pub fn display_label(&self) -> String {
+        self.inner.borrow().display_label()
+    }
+
     /// Set the text for this menu item. `text` could optionally contain
     /// an `&` before a character to assign this character as the mnemonic
     /// for this menu item. To display a `&` without assigning a mnemenonic, use `&&`.
     pub fn set_text<S: AsRef<str>>(&self, text: S) {
-        self.inner.borrow_mut().set_text(text.as_ref())
+        let change = self.inner.borrow_mut().set_text(text.as_ref());
+        change.notify();
+    }
+
+    /// Set the text for this menu item, shown verbatim without a mnemonic.
+    ///
+    /// [`MenuItem::set_text`] treats a single `&` as a mnemonic marker, which hides it,
+    /// so arbitrary text like `"Tom & Jerry.mp4"` shows as `"Tom  Jerry.mp4"` with an underlined
+    /// space. This doubles every `&` instead, [`MenuItem::text`] then returns the doubled form.
+    pub fn set_text_literal<S: AsRef<str>>(&self, text: S) {
+        self.set_text(crate::platform_impl::escape_mnemonics(text.as_ref()))
     }
 
     /// Get whether this menu item is enabled or not.
@@ -86,14 +130,166 @@ impl MenuItem {
 
     /// Enable or disable this menu item.
     pub fn set_enabled(&self, enabled: bool) {
-        self.inner.borrow_mut().set_enabled(enabled)
+        let change = self.inner.borrow_mut().set_enabled(enabled);
+        change.notify();
+    }
+
+    /// Whether this menu item is visible, see [`set_visible`](Self::set_visible).
+    pub fn is_visible(&self) -> bool {
+        self.inner.borrow().is_visible()
     }
 
-    /// Set this menu item accelerator.
+    /// Show or hide this menu item without removing it, it keeps its position when shown again
+    /// even if other items around it were hidden or shown meanwhile.
+    /// Its accelerator doesn't activate it while it is hidden.
+    ///
+    /// A hidden menu item is still returned by `items()`, but not by `visible_items()`.
+    pub fn set_visible(&self, visible: bool) {
+        let change = self.inner.borrow_mut().set_visible(visible);
+        change.notify();
+    }
+
+    /// Show `icon` next to the text of this menu item, or remove it with `None`,
+    /// without having to replace this item with an [`IconMenuItem`](crate::IconMenuItem).
+    ///
+    /// The icon is drawn at the small icon size of the system, which follows its DPI.
+    pub fn set_icon(&self, icon: Option<Icon>) {
+        self.inner.borrow_mut().set_icon(icon)
+    }
+
+    /// Whether this menu item opens a dialog, see [`MenuItem::set_opens_dialog`].
+    pub fn opens_dialog(&self) -> bool {
+        self.inner.borrow().opens_dialog()
+    }
+
+    /// Mark this menu item as opening a dialog, which follows the platform convention
+    /// of ending its label with an ellipsis ("…").
+    ///
+    /// The ellipsis is only added to the label shown in the menu, [`MenuItem::text`] is unchanged.
+    /// Labels that already end with an ellipsis are left as is.
+    pub fn set_opens_dialog(&self, opens_dialog: bool) {
+        self.inner.borrow_mut().set_opens_dialog(opens_dialog)
+    }
+
+    /// Get this menu item accelerator, the current binding once it was changed with
+    /// [`MenuItem::set_accelerator`].
+    pub fn accelerator(&self) -> Option<Accelerator> {
+        self.inner.borrow().accelerator()
+    }
+
+    /// Set this menu item accelerator, or remove it with `None`, for example to rebind
+    /// shortcuts from a preferences dialog.
+    ///
+    /// The accelerator column of the item is updated and the accelerator table of the menus
+    /// it is in is rebuilt right away, so [`Menu::haccel`](crate::Menu::haccel) changes,
+    /// see [`Menu::on_haccel_changed`](crate::Menu::on_haccel_changed).
     pub fn set_accelerator(&self, accelerator: Option<Accelerator>) -> crate::Result<()> {
         self.inner.borrow_mut().set_accelerator(accelerator)
     }
 
+    /// Stores `data` as JSON in this item, replacing any previous payload, for example to
+    /// persist the state the item acts on. Unlike the other properties of the item, the payload
+    /// is opaque to the menu, it is kept by [`Menu::to_json`](crate::Menu::to_json) and
+    /// [`Menu::from_json`](crate::Menu::from_json) and copied with the item.
+    ///
+    /// Returns [`Error::InvalidJson`](crate::Error::InvalidJson) if `data` can't be serialized.
+    #[cfg(feature = "serde")]
+    pub fn set_data_json(&self, data: &impl serde::Serialize) -> crate::Result<()> {
+        let json = serde_json::to_string(data).map_err(crate::Error::InvalidJson)?;
+        self.inner.borrow_mut().data_json = Some(json);
+        Ok(())
+    }
+
+    /// Reads the payload stored with [`MenuItem::set_data_json`] as a `T`,
+    /// `None` if there is none or it isn't a valid `T`.
+    #[cfg(feature = "serde")]
+    pub fn data_json<T: serde::de::DeserializeOwned>(&self) -> Option<T> {
+        let json = self.inner.borrow().data_json.clone()?;
+        serde_json::from_str(&json).ok()
+    }
+
+    /// Records that this item was just used, for example when its file was opened,
+    /// so the submenus it is in that use [`Sort::MostRecentFirst`](crate::Sort::MostRecentFirst)
+    /// move it to their top.
+    pub fn mark_used(&self) {
+        crate::platform_impl::sort::mark_used(&self.inner)
+    }
+
+    /// Get the value shown on the right side of this menu item and its color, if any.
+    pub fn value(&self) -> Option<(String, Option<[u8; 4]>)> {
+        self.inner.borrow().value()
+    }
+
+    /// Show a value on the right side of this menu item, before the accelerator, for example
+    /// `Connected` in a `Status` item. The optional color is `[r, g, b, a]`, blended over the menu
+    /// background using its alpha, otherwise the value uses the color of the label.
+    /// Use `None` to remove the value.
+    ///
+    /// Items with a value are owner-drawn, see [`MenuItem::set_badge`] for the requirements.
+    pub fn set_value<S: Into<String>>(&self, value: Option<(S, Option<[u8; 4]>)>) {
+        self.inner
+            .borrow_mut()
+            .set_value(value.map(|(text, color)| (text.into(), color)))
+    }
+
+    /// Get the badge shown on this menu item, if any.
+    pub fn badge(&self) -> Option<String> {
+        self.inner.borrow().badge()
+    }
+
+    /// Show a small badge on the right side of this menu item, before the accelerator.
+    ///
+    /// An empty string shows a dot, otherwise the badge contains the given text.
+    /// Use `None` to remove the badge. This is purely cosmetic and doesn't affect activation.
+    ///
+    /// Items with a badge are owner-drawn, so the window the menu is shown for needs the menu
+    /// subclass, see [`Menu::init_for_hwnd`](crate::Menu::init_for_hwnd)
+    /// and [`ContextMenu::attach_menu_subclass_for_hwnd`](crate::ContextMenu::attach_menu_subclass_for_hwnd).
+    pub fn set_badge<S: Into<String>>(&self, badge: Option<S>) {
+        self.inner.borrow_mut().set_badge(badge.map(|b| b.into()))
+    }
+
+    /// Get the indent level of this menu item, see [`MenuItem::set_indent_level`].
+    pub fn indent_level(&self) -> u8 {
+        self.inner.borrow().indent_level()
+    }
+
+    /// Indent this menu item by `level` steps, for example to show a tree of bookmarks in a flat menu.
+    /// Each level moves the item to the right by the width of a check mark. Use `0` to remove the indentation.
+    ///
+    /// Indented items are owner-drawn, see [`MenuItem::set_badge`] for the requirements.
+    pub fn set_indent_level(&self, level: u8) {
+        self.inner.borrow_mut().set_indent_level(level)
+    }
+
+    /// Get the colors of this menu item, see [`MenuItem::set_style`].
+    pub fn style(&self) -> Option<MenuItemStyle> {
+        self.inner.borrow().style()
+    }
+
+    /// Paint this menu item with custom colors instead of the ones of the menu theme, for example
+    /// to match the colors of a brand. The highlight color is used while the item is hovered
+    /// or selected with the keyboard, and a disabled item uses its text color at half opacity.
+    /// Use `None` to go back to the colors of the menu theme.
+    ///
+    /// Styled items are owner-drawn, see [`MenuItem::set_badge`] for the requirements.
+    pub fn set_style(&self, style: Option<MenuItemStyle>) {
+        self.inner.borrow_mut().set_style(style)
+    }
+
+    /// Create a new menu item with the same properties as this one (text, enabled state, accelerator, badge, indent level, style and dialog ellipsis)
+    /// but with a new generated id, so it can be added to a menu independently.
+    ///
+    /// Unlike [`Clone`], which returns another handle to the same item, changes to
+    /// the returned item don't affect this one.
+    pub fn clone_as_new(&self) -> MenuItem {
+        let item = self.inner.borrow().clone_as_new();
+        Self {
+            id: Rc::new(item.id().clone()),
+            inner: Rc::new(RefCell::new(item)),
+        }
+    }
+
     /// Convert this menu item into its menu ID.
     pub fn into_id(mut self) -> MenuId {
         // Note: `Rc::into_inner` is available from Rust 1.70
@@ -104,3 +300,18 @@ impl MenuItem {
         }
     }
 }
+
+/// Colors of a menu item set with [`MenuItem::set_style`].
+///
+/// Colors are `[r, g, b, a]`, blended over the menu background using their alpha,
+/// `None` keeps the color of the menu theme.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MenuItemStyle {
+    /// Color of the label, the accelerator and the value.
+    pub text_color: Option<[u8; 4]>,
+    /// Color of the item background.
+    pub background_color: Option<[u8; 4]>,
+    /// Color of the item background while it is highlighted.
+    pub highlight_color: Option<[u8; 4]>,
+}
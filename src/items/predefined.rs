@@ -39,6 +39,35 @@ impl PredefinedMenuItem {
         PredefinedMenuItem::new::<&str>(PredefinedMenuItemType::Separator, None)
     }
 
+    /// Separator menu item with a custom appearance.
+    ///
+    /// Styled separators are owner-drawn, so the window the menu is shown for needs the menu
+    /// subclass, see [`Menu::init_for_hwnd`](crate::Menu::init_for_hwnd)
+    /// and [`ContextMenu::attach_menu_subclass_for_hwnd`](crate::ContextMenu::attach_menu_subclass_for_hwnd).
+    /// The default style keeps the native separator.
+    pub fn separator_styled(style: SeparatorStyle) -> PredefinedMenuItem {
+        let item = PredefinedMenuItem::separator();
+        item.inner.borrow_mut().set_separator_style(style);
+        item
+    }
+
+    /// Separator menu item with a caption, for example to name the group of items below it.
+    ///
+    /// The caption is drawn left-aligned with the labels of the other items and followed by the
+    /// separator line. Like other separators, it can't be selected and doesn't emit [`MenuEvent`]s,
+    /// it is also disabled. Use [`PredefinedMenuItem::set_text`] to change the caption.
+    ///
+    /// Labeled separators are owner-drawn, see [`PredefinedMenuItem::separator_styled`]
+    /// for the requirements.
+    ///
+    /// [`MenuEvent`]: crate::MenuEvent
+    pub fn labeled_separator<S: AsRef<str>>(text: S) -> PredefinedMenuItem {
+        let item = PredefinedMenuItem::new(PredefinedMenuItemType::Separator, Some(text));
+        let change = item.inner.borrow_mut().set_enabled(false);
+        change.notify();
+        item
+    }
+
     /// Copy menu item
     pub fn copy(text: Option<&str>) -> PredefinedMenuItem {
         PredefinedMenuItem::new(PredefinedMenuItemType::Copy, text)
@@ -59,6 +88,11 @@ impl PredefinedMenuItem {
         PredefinedMenuItem::new(PredefinedMenuItemType::SelectAll, text)
     }
 
+    /// Undo menu item
+    pub fn undo(text: Option<&str>) -> PredefinedMenuItem {
+        PredefinedMenuItem::new(PredefinedMenuItemType::Undo, text)
+    }
+
     /// Redo menu item
     pub fn redo(text: Option<&str>) -> PredefinedMenuItem {
         PredefinedMenuItem::new(PredefinedMenuItemType::Redo, text)
@@ -99,10 +133,21 @@ impl PredefinedMenuItem {
         PredefinedMenuItem::new(PredefinedMenuItemType::About(metadata), text)
     }
 
-    fn new<S: AsRef<str>>(item: PredefinedMenuItemType, text: Option<S>) -> Self {
+    pub(crate) fn new<S: AsRef<str>>(item: PredefinedMenuItemType, text: Option<S>) -> Self {
+        Self::new_with_id(item, text, None)
+    }
+
+    /// A predefined item with a stable `id` instead of a generated one,
+    /// for the items of [`Menu::standard_app_menu`](crate::Menu::standard_app_menu).
+    pub(crate) fn new_with_id<S: AsRef<str>>(
+        item: PredefinedMenuItemType,
+        text: Option<S>,
+        id: Option<MenuId>,
+    ) -> Self {
         let item = crate::platform_impl::MenuChild::new_predefined(
             item,
             text.map(|t| t.as_ref().to_string()),
+            id,
         );
         Self {
             id: Rc::new(item.id().clone()),
@@ -115,14 +160,62 @@ impl PredefinedMenuItem {
         &self.id
     }
 
+    /// Returns the command id Windows uses for this predefined menu item, the low-order word of the
+    /// `wparam` of the `WM_COMMAND` it sends when the item is activated.
+    ///
+    /// Unlike [`Self::id`], it is assigned by this crate, for correlating messages handled
+    /// by hand-written Win32 code with this item.
+    pub fn command_id(&self) -> u16 {
+        self.inner.borrow().command_id()
+    }
+
     /// Get the text for this predefined menu item.
     pub fn text(&self) -> String {
         self.inner.borrow().text()
     }
 
+    /// Get the label of this predefined menu item as Windows displays it,
+    /// see [`MenuItem::display_label`](crate::MenuItem::display_label).
+    pub fn display_label(&self) -> String {
+        self.inner.borrow().display_label()
+    }
+
+    /// Whether this predefined menu item is visible, see [`set_visible`](Self::set_visible).
+    pub fn is_visible(&self) -> bool {
+        self.inner.borrow().is_visible()
+    }
+
+    /// Show or hide this predefined menu item without removing it, it keeps its position when shown again
+    /// even if other items around it were hidden or shown meanwhile.
+    /// Its accelerator doesn't activate it while it is hidden.
+    ///
+    /// A hidden predefined menu item is still returned by `items()`, but not by `visible_items()`.
+    pub fn set_visible(&self, visible: bool) {
+        let change = self.inner.borrow_mut().set_visible(visible);
+        change.notify();
+    }
+
     /// Set the text for this predefined menu item.
     pub fn set_text<S: AsRef<str>>(&self, text: S) {
-        self.inner.borrow_mut().set_text(text.as_ref())
+        let change = self.inner.borrow_mut().set_text(text.as_ref());
+        change.notify();
+    }
+
+    /// Call `f` after the built-in action of this item completed, for example to log that the
+    /// window was minimized. For the about item, `f` is called once the about dialog is dismissed,
+    /// for the other items right after their action. The quit item posts `WM_QUIT`, so `f` is
+    /// called before the message loop ends.
+    ///
+    /// The about dialog is reported through the menu subclass, see
+    /// [`Menu::init_for_hwnd`](crate::Menu::init_for_hwnd)
+    /// and [`ContextMenu::attach_menu_subclass_for_hwnd`](crate::ContextMenu::attach_menu_subclass_for_hwnd).
+    pub fn set_after_action<F: Fn() + 'static>(&self, f: F) {
+        self.inner.borrow_mut().set_after_action(Rc::new(f))
+    }
+
+    /// Get the style of this separator, `None` if it is not a separator or uses the native look.
+    pub fn separator_style(&self) -> Option<SeparatorStyle> {
+        self.inner.borrow().separator_style()
     }
 
     /// Convert this menu item into its menu ID.
@@ -136,6 +229,29 @@ impl PredefinedMenuItem {
     }
 }
 
+/// Appearance of a separator created with [`PredefinedMenuItem::separator_styled`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SeparatorStyle {
+    /// Thickness of the line, in physical pixels.
+    pub thickness: u32,
+    /// Space left empty at both ends of the line, in physical pixels.
+    pub inset: u32,
+    /// Color of the line as `[r, g, b, a]`, blended over the menu background using its alpha.
+    /// `None` uses the system color of disabled text.
+    pub color: Option<[u8; 4]>,
+}
+
+impl Default for SeparatorStyle {
+    fn default() -> Self {
+        Self {
+            thickness: 1,
+            inset: 0,
+            color: None,
+        }
+    }
+}
+
 #[test]
 fn test_about_metadata() {
     assert_eq!(
@@ -166,6 +282,31 @@ fn test_about_metadata() {
     );
 }
 
+#[test]
+fn test_about_metadata_detailed_version() {
+    assert_eq!(AboutMetadata::default().detailed_version(), None);
+
+    assert_eq!(
+        AboutMetadata {
+            build_info: Some("2024-06-01 abc123".into()),
+            ..Default::default()
+        }
+        .detailed_version(),
+        Some("Build: 2024-06-01 abc123".into())
+    );
+
+    assert_eq!(
+        AboutMetadata {
+            version: Some("1.0.0".into()),
+            short_version: Some("1.0".into()),
+            build_info: Some("2024-06-01 abc123".into()),
+            ..Default::default()
+        }
+        .detailed_version(),
+        Some("1.0.0 (1.0)\nBuild: 2024-06-01 abc123".into())
+    );
+}
+
 #[derive(Debug, Clone)]
 #[non_exhaustive]
 #[allow(clippy::large_enum_variant, dead_code)]
@@ -198,6 +339,58 @@ impl Default for PredefinedMenuItemType {
 }
 
 impl PredefinedMenuItemType {
+    /// The `snake_case` name of this type, matching the [`PredefinedMenuItem`] constructors.
+    #[cfg(feature = "serde")]
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            PredefinedMenuItemType::Separator => "separator",
+            PredefinedMenuItemType::Copy => "copy",
+            PredefinedMenuItemType::Cut => "cut",
+            PredefinedMenuItemType::Paste => "paste",
+            PredefinedMenuItemType::SelectAll => "select_all",
+            PredefinedMenuItemType::Undo => "undo",
+            PredefinedMenuItemType::Redo => "redo",
+            PredefinedMenuItemType::Minimize => "minimize",
+            PredefinedMenuItemType::Maximize => "maximize",
+            PredefinedMenuItemType::Fullscreen => "fullscreen",
+            PredefinedMenuItemType::Hide => "hide",
+            PredefinedMenuItemType::HideOthers => "hide_others",
+            PredefinedMenuItemType::ShowAll => "show_all",
+            PredefinedMenuItemType::CloseWindow => "close_window",
+            PredefinedMenuItemType::Quit => "quit",
+            PredefinedMenuItemType::About(_) => "about",
+            PredefinedMenuItemType::Services => "services",
+            PredefinedMenuItemType::BringAllToFront => "bring_all_to_front",
+            PredefinedMenuItemType::None => "none",
+        }
+    }
+
+    /// The type with this [`name`](Self::name), the about metadata is not restored.
+    #[cfg(feature = "serde")]
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "separator" => PredefinedMenuItemType::Separator,
+            "copy" => PredefinedMenuItemType::Copy,
+            "cut" => PredefinedMenuItemType::Cut,
+            "paste" => PredefinedMenuItemType::Paste,
+            "select_all" => PredefinedMenuItemType::SelectAll,
+            "undo" => PredefinedMenuItemType::Undo,
+            "redo" => PredefinedMenuItemType::Redo,
+            "minimize" => PredefinedMenuItemType::Minimize,
+            "maximize" => PredefinedMenuItemType::Maximize,
+            "fullscreen" => PredefinedMenuItemType::Fullscreen,
+            "hide" => PredefinedMenuItemType::Hide,
+            "hide_others" => PredefinedMenuItemType::HideOthers,
+            "show_all" => PredefinedMenuItemType::ShowAll,
+            "close_window" => PredefinedMenuItemType::CloseWindow,
+            "quit" => PredefinedMenuItemType::Quit,
+            "about" => PredefinedMenuItemType::About(None),
+            "services" => PredefinedMenuItemType::Services,
+            "bring_all_to_front" => PredefinedMenuItemType::BringAllToFront,
+            _ => return None,
+        })
+    }
+
     pub(crate) fn text(&self) -> &str {
         match self {
             PredefinedMenuItemType::Separator => "",
@@ -1,8 +1,10 @@
 use std::{cell::RefCell, mem, rc::Rc};
 
 use crate::{
-    dpi::Position, sealed::IsMenuItemBase, util::AddOp, ContextMenu, IsMenuItem, MenuId,
-    MenuItemKind,
+    dpi::{Position, Size},
+    sealed::IsMenuItemBase,
+    util::AddOp,
+    ContextMenu, ContextMenuOptions, IsMenuItem, MenuId, MenuItemKind, SubmenuItemsBuilder,
 };
 
 /// A menu that can be added to a [`Menu`] or another [`Submenu`].
@@ -65,6 +67,7 @@ impl Submenu {
         enabled: bool,
         items: &[&dyn IsMenuItem],
     ) -> crate::Result<Self> {
+        crate::platform_impl::check_ui_thread()?;
         let menu = Self::new(text, enabled);
         menu.append_items(items)?;
         Ok(menu)
@@ -77,11 +80,40 @@ impl Submenu {
         enabled: bool,
         items: &[&dyn IsMenuItem],
     ) -> crate::Result<Self> {
+        crate::platform_impl::check_ui_thread()?;
         let menu = Self::with_id(id, text, enabled);
         menu.append_items(items)?;
         Ok(menu)
     }
 
+    /// Creates a new submenu with the items added by `f`, which can add nested submenus the same way.
+    ///
+    /// ```no_run
+    /// # use muda_win::Submenu;
+    /// let file = Submenu::build("&File", true, |b| {
+    ///     b.item("open", "&Open", Some("Ctrl+O"))
+    ///         .submenu("Open &Recent", true, |b| {
+    ///             b.item("clear-recent", "Clear", None);
+    ///         })
+    ///         .separator()
+    ///         .check("autosave", "&Autosave", true, None);
+    /// })?;
+    /// # Ok::<(), muda_win::Error>(())
+    /// ```
+    ///
+    /// Returns the first error of the builder, for example an invalid accelerator.
+    pub fn build<S: AsRef<str>, F: FnOnce(&mut SubmenuItemsBuilder)>(
+        text: S,
+        enabled: bool,
+        f: F,
+    ) -> crate::Result<Self> {
+        let mut builder = SubmenuItemsBuilder::default();
+        f(&mut builder);
+        let items = builder.finish()?;
+        let items = items.iter().map(|i| i.as_ref()).collect::<Vec<_>>();
+        Self::with_items(text, enabled, &items)
+    }
+
     /// Returns a unique identifier associated with this submenu.
     pub fn id(&self) -> &MenuId {
         &self.id
@@ -89,7 +121,7 @@ impl Submenu {
 
     /// Add a menu item to the end of this menu.
     pub fn append(&self, item: &dyn IsMenuItem) -> crate::Result<()> {
-        self.inner.borrow_mut().add_menu_item(item, AddOp::Append)
+        self.add_menu_item(item, AddOp::Append)
     }
 
     /// Add menu items to the end of this submenu. It calls [`Submenu::append`] in a loop.
@@ -103,9 +135,7 @@ impl Submenu {
 
     /// Add a menu item to the beginning of this submenu.
     pub fn prepend(&self, item: &dyn IsMenuItem) -> crate::Result<()> {
-        self.inner
-            .borrow_mut()
-            .add_menu_item(item, AddOp::Insert(0))
+        self.add_menu_item(item, AddOp::Insert(0))
     }
 
     /// Add menu items to the beginning of this submenu.
@@ -117,9 +147,15 @@ impl Submenu {
 
     /// Insert a menu item at the specified `postion` in the submenu.
     pub fn insert(&self, item: &dyn IsMenuItem, position: usize) -> crate::Result<()> {
-        self.inner
-            .borrow_mut()
-            .add_menu_item(item, AddOp::Insert(position))
+        self.add_menu_item(item, AddOp::Insert(position))
+    }
+
+    /// Adds `item` and moves it to its place if this submenu is sorted.
+    fn add_menu_item(&self, item: &dyn IsMenuItem, op: AddOp) -> crate::Result<()> {
+        let mut inner = self.inner.borrow_mut();
+        inner.add_menu_item(item, op)?;
+        crate::platform_impl::sort::apply(&mut inner);
+        Ok(())
     }
 
     /// Insert menu items at the specified `postion` in the submenu.
@@ -148,21 +184,189 @@ impl Submenu {
         }
     }
 
+    /// Removes all the items of this submenu at once and returns them, for example to rebuild it.
+    pub fn clear(&self) -> crate::Result<Vec<MenuItemKind>> {
+        self.inner.borrow_mut().clear()
+    }
+
+    /// Replaces all the items of this submenu with `items`, for example to populate it lazily.
+    ///
+    /// The replacement happens in one go, so an open submenu never shows a partially updated list,
+    /// and nothing is removed if one of the accelerators of `items` is unsupported.
+    pub fn set_items(&self, items: &[&dyn IsMenuItem]) -> crate::Result<()> {
+        self.inner.borrow_mut().set_items(items)
+    }
+
     /// Returns a list of menu items that has been added to this submenu.
+    ///
+    /// Hidden items are included, see [`Submenu::visible_items`].
     pub fn items(&self) -> Vec<MenuItemKind> {
         self.inner.borrow().items()
     }
 
+    /// Returns the items of this submenu that aren't hidden, see [`MenuItem::set_visible`](crate::MenuItem::set_visible).
+    pub fn visible_items(&self) -> Vec<MenuItemKind> {
+        self.items()
+            .into_iter()
+            .filter(|item| item.child().is_visible())
+            .collect()
+    }
+
+    /// Returns the first item with `id`, searching nested submenus too, depth-first.
+    pub fn find_item_by_id(&self, id: &MenuId) -> Option<MenuItemKind> {
+        self.inner.borrow().find_item_by_id(id)
+    }
+
+    /// Whether the items of this submenu show their accelerators, see [`Submenu::set_show_accelerators`].
+    pub fn shows_accelerators(&self) -> bool {
+        self.inner.borrow().shows_accelerators()
+    }
+
+    /// Show or hide the accelerator column of all the items in this submenu.
+    ///
+    /// Hidden accelerators are only removed from the labels, they still trigger their items.
+    /// This doesn't affect the items of nested submenus.
+    pub fn set_show_accelerators(&self, show: bool) {
+        self.inner.borrow_mut().set_show_accelerators(show)
+    }
+
+    /// How the items of this submenu are ordered, see [`Submenu::set_sort`].
+    pub fn sort(&self) -> Sort {
+        self.inner.borrow().sort()
+    }
+
+    /// Keep the items of this submenu ordered by `sort`, for example a list of recent files.
+    ///
+    /// The items are re-ordered right away, when items are added to this submenu,
+    /// and with [`Sort::MostRecentFirst`] whenever one of its items is marked with [`MenuItem::mark_used`].
+    /// Separators stay in place and the items between them are sorted separately.
+    ///
+    /// [`MenuItem::mark_used`]: crate::MenuItem::mark_used
+    pub fn set_sort(&self, sort: Sort) {
+        crate::platform_impl::sort::set_sort(&self.inner, sort)
+    }
+
+    /// Whether the items of this submenu are removed when it closes, see [`Submenu::set_clear_on_close`].
+    pub fn clears_on_close(&self) -> bool {
+        self.inner.borrow().clears_on_close()
+    }
+
+    /// Remove all the items of this submenu whenever it closes (`WM_UNINITMENUPOPUP`), so a submenu
+    /// that is filled with fresh items before each open doesn't keep the items of the previous one.
+    ///
+    /// The items are removed once the messages posted to the window before it closed are handled,
+    /// so the command of an item clicked in it still emits its event. The submenu stays empty
+    /// until items are added again, for example with [`Submenu::set_items`] before it is shown
+    /// next. Items added after it opened are removed too when it closes.
+    ///
+    /// Closing is detected by the menu subclass, see [`Menu::init_for_hwnd`](crate::Menu::init_for_hwnd)
+    /// and [`ContextMenu::attach_menu_subclass_for_hwnd`].
+    pub fn set_clear_on_close(&self, clear: bool) {
+        self.inner.borrow_mut().set_clear_on_close(clear)
+    }
+
+    /// Whether clicking the title of this submenu emits a [`MenuEvent`](crate::MenuEvent),
+    /// see [`Submenu::set_title_clickable`].
+    pub fn is_title_clickable(&self) -> bool {
+        self.inner.borrow().is_title_clickable()
+    }
+
+    /// Make clicking the title of this submenu emit a [`MenuEvent`](crate::MenuEvent) with the id
+    /// of this submenu and close the menu, like a regular item, for example to open a default view,
+    /// while hovering the title still opens the submenu.
+    ///
+    /// Win32 has no such items, so the click is detected while the menu is shown, with these caveats:
+    /// - The submenu opens when the mouse button is pressed and closes with the rest of the menu
+    ///   when it is released, so it may flash briefly.
+    /// - Only mouse clicks are detected, the keyboard opens the submenu as usual, so the action
+    ///   should also be reachable from an item, for example the first one of the submenu.
+    /// - The event is posted to the window of the menu and emitted by the menu subclass, see
+    ///   [`Menu::init_for_hwnd`](crate::Menu::init_for_hwnd) and
+    ///   [`ContextMenu::attach_menu_subclass_for_hwnd`], after the `show_*` method that opened a
+    ///   context menu returned as if it was cancelled.
+    pub fn set_title_clickable(&self, clickable: bool) {
+        self.inner.borrow_mut().set_title_clickable(clickable)
+    }
+
+    /// Get the side the menus of this submenu cascade to, see [`Submenu::set_cascade_side`].
+    pub fn cascade_side(&self) -> CascadeSide {
+        self.inner.borrow().cascade_side()
+    }
+
+    /// Choose the side the menus of this submenu open on when it is shown as a context menu,
+    /// for example [`CascadeSide::Left`] for a menu anchored near the right edge of the screen.
+    ///
+    /// With [`CascadeSide::Left`], the popup opens to the left of the position it is shown at
+    /// and Windows cascades its nested submenus to the left too. Windows still moves a menu to
+    /// the other side when it doesn't fit on the monitor.
+    ///
+    /// The side only applies when this submenu is itself shown as a context menu: Windows
+    /// has no way to choose the side of a single nested popup, so the side of a submenu in a
+    /// menu bar or in another menu is ignored and it cascades like its parent. Submenus shown
+    /// below a control with [`ContextMenu::show_below_control`] are positioned by the system.
+    pub fn set_cascade_side(&self, side: CascadeSide) {
+        self.inner.borrow_mut().set_cascade_side(side)
+    }
+
+    /// Fill this submenu on demand with `f`, right before it opens for the first time, for example
+    /// for a list of hundreds of fonts that users rarely open. The items of the submenu are removed
+    /// before `f` runs, so it can append the items like when building the submenu eagerly.
+    ///
+    /// `f` runs again on every open with [`Submenu::set_rebuild_on_open`], otherwise the built
+    /// items are kept. Setting another builder runs it on the next open.
+    ///
+    /// Nested submenus are built when they open in a window with the menu subclass, see
+    /// [`Menu::init_for_hwnd`](crate::Menu::init_for_hwnd) and
+    /// [`ContextMenu::attach_menu_subclass_for_hwnd`], and a submenu shown as a context menu
+    /// is built before it is shown. Accelerators of the items are only registered once they
+    /// are built.
+    pub fn set_lazy_builder<F: Fn(&Submenu) + 'static>(&self, f: F) {
+        self.inner.borrow_mut().set_lazy_builder(Rc::new(f))
+    }
+
+    /// Whether the lazy builder runs on every open, see [`Submenu::set_rebuild_on_open`].
+    pub fn rebuilds_on_open(&self) -> bool {
+        self.inner.borrow().rebuilds_on_open()
+    }
+
+    /// Run the builder set with [`Submenu::set_lazy_builder`] every time this submenu opens,
+    /// instead of only the first time, for lists that change between opens.
+    pub fn set_rebuild_on_open(&self, rebuild: bool) {
+        self.inner.borrow_mut().set_rebuild_on_open(rebuild)
+    }
+
+    /// Whether this submenu is compact, see [`Submenu::set_compact`].
+    pub fn is_compact(&self) -> bool {
+        self.inner.borrow().is_compact_submenu()
+    }
+
+    /// Draw the items of this submenu without the space reserved on their left side for check marks
+    /// and icons, so their text starts near the edge, for example in a long list of fonts.
+    ///
+    /// Check items and items with an icon keep that space, so they are drawn as usual, as are items
+    /// that are also in a submenu that isn't compact. Compact items are owner-drawn,
+    /// see [`MenuItem::set_badge`](crate::MenuItem::set_badge) for the requirements.
+    pub fn set_compact(&self, compact: bool) {
+        self.inner.borrow_mut().set_compact(compact)
+    }
+
     /// Get the text for this submenu.
     pub fn text(&self) -> String {
         self.inner.borrow().text()
     }
 
+    /// Get the label of this submenu as Windows displays it, which is also what UI automation
+    /// tools read. Submenus have no accelerator, so it is their [`text`](Self::text), like `"&File"`.
+    pub fn display_label(&self) -> String {
+        self.inner.borrow().display_label()
+    }
+
     /// Set the text for this submenu. `text` could optionally contain
     /// an `&` before a character to assign this character as the mnemonic
     /// for this submenu. To display a `&` without assigning a mnemenonic, use `&&`.
     pub fn set_text<S: AsRef<str>>(&self, text: S) {
-        self.inner.borrow_mut().set_text(text.as_ref())
+        let change = self.inner.borrow_mut().set_text(text.as_ref());
+        change.notify();
     }
 
     /// Get whether this submenu is enabled or not.
@@ -172,7 +376,22 @@ impl Submenu {
 
     /// Enable or disable this submenu.
     pub fn set_enabled(&self, enabled: bool) {
-        self.inner.borrow_mut().set_enabled(enabled)
+        let change = self.inner.borrow_mut().set_enabled(enabled);
+        change.notify();
+    }
+
+    /// Whether this submenu is visible, see [`set_visible`](Self::set_visible).
+    pub fn is_visible(&self) -> bool {
+        self.inner.borrow().is_visible()
+    }
+
+    /// Show or hide this submenu without removing it, it keeps its position when shown again
+    /// even if other items around it were hidden or shown meanwhile.
+    ///
+    /// A hidden submenu is still returned by `items()`, but not by `visible_items()`.
+    pub fn set_visible(&self, visible: bool) {
+        let change = self.inner.borrow_mut().set_visible(visible);
+        change.notify();
     }
 
     /// Convert this submenu into its menu ID.
@@ -186,17 +405,70 @@ impl Submenu {
     }
 }
 
+/// The order of the items of a [`Submenu`], see [`Submenu::set_sort`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Sort {
+    /// The items keep the order they were added in.
+    #[default]
+    None,
+    /// Items marked with [`MenuItem::mark_used`] come first, the most recently used one at the top,
+    /// followed by the items that were never used in the order they were added in.
+    ///
+    /// [`MenuItem::mark_used`]: crate::MenuItem::mark_used
+    MostRecentFirst,
+    /// Items are ordered by their text, ignoring case and mnemonic markers (`&`).
+    Alphabetical,
+}
+
 impl ContextMenu for Submenu {
     fn hpopupmenu(&self) -> isize {
         self.inner.borrow().hpopupmenu()
     }
 
     unsafe fn show_context_menu_for_hwnd(&self, hwnd: isize, position: Option<Position>) -> bool {
+        crate::platform_impl::lazy::build(&self.inner);
         self.inner
             .borrow_mut()
             .show_context_menu_for_hwnd(hwnd, position)
     }
 
+    unsafe fn show_context_menu_at_screen_pos(&self, hwnd: isize, position: Position) -> bool {
+        crate::platform_impl::lazy::build(&self.inner);
+        self.inner
+            .borrow_mut()
+            .show_context_menu_at_screen_pos_for_hwnd(hwnd, position)
+    }
+
+    unsafe fn show_context_menu_for_hwnd_blocking(
+        &self,
+        hwnd: isize,
+        position: Option<Position>,
+    ) -> Option<MenuId> {
+        crate::platform_impl::lazy::build(&self.inner);
+        self.inner
+            .borrow_mut()
+            .show_context_menu_for_hwnd_blocking(hwnd, position)
+    }
+
+    unsafe fn show_context_menu_with_options_for_hwnd(
+        &self,
+        hwnd: isize,
+        options: ContextMenuOptions,
+    ) -> bool {
+        crate::platform_impl::lazy::build(&self.inner);
+        self.inner
+            .borrow_mut()
+            .show_context_menu_with_options_for_hwnd(hwnd, &options)
+    }
+
+    unsafe fn show_below_control(&self, hwnd: isize, position: Position, size: Size) -> bool {
+        crate::platform_impl::lazy::build(&self.inner);
+        self.inner
+            .borrow_mut()
+            .show_below_control_for_hwnd(hwnd, position, size)
+    }
+
     unsafe fn attach_menu_subclass_for_hwnd(&self, hwnd: isize) {
         self.inner.borrow().attach_menu_subclass_for_hwnd(hwnd)
     }
@@ -205,3 +477,18 @@ impl ContextMenu for Submenu {
         self.inner.borrow().detach_menu_subclass_from_hwnd(hwnd)
     }
 }
+
+/// The side the menus of a [`Submenu`] shown as a context menu open on,
+/// see [`Submenu::set_cascade_side`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CascadeSide {
+    /// The default placement of Windows, to the right unless there is no room or the
+    /// handedness setting of Windows makes menus drop to the left.
+    #[default]
+    Auto,
+    /// To the left of the position and of the parent menus.
+    Left,
+    /// To the right of the position and of the parent menus, whatever the handedness setting.
+    Right,
+}
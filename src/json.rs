@@ -0,0 +1,198 @@
+//! A serializable description of a menu, see [`Menu::to_descriptor`](crate::Menu::to_descriptor)
+//! and its JSON form for external tooling, see [`Menu::to_json`](crate::Menu::to_json).
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    accelerator::Accelerator, items::PredefinedMenuItemType, platform_impl::MenuChild,
+    CheckMenuItem, IconMenuItem, IsMenuItem, Menu, MenuItem, MenuItemKind, MenuItemType,
+    PredefinedMenuItem, Submenu,
+};
+
+/// The logical structure of a [`Menu`], without its native handles,
+/// see [`Menu::to_descriptor`] and [`Menu::from_descriptor`].
+///
+/// It serializes to the format documented in [`Menu::to_json`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MenuDescriptor {
+    /// The id of the menu.
+    pub id: String,
+    /// The items of the menu.
+    pub items: Vec<ItemDescriptor>,
+}
+
+/// The kind of item an [`ItemDescriptor`] describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemDescriptorKind {
+    /// A [`MenuItem`].
+    Item,
+    /// A [`CheckMenuItem`].
+    Check,
+    /// An [`IconMenuItem`], its icon is not described.
+    Icon,
+    /// A [`Submenu`] with the items in [`ItemDescriptor::children`].
+    Submenu,
+    /// A [`PredefinedMenuItem`] named by [`ItemDescriptor::predefined`].
+    Predefined,
+}
+
+/// An item of a [`MenuDescriptor`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItemDescriptor {
+    /// The kind of the item.
+    pub kind: ItemDescriptorKind,
+    /// The id of the item, `None` for predefined items since their ids are generated.
+    ///
+    /// Items built from a descriptor without an id get a generated one, like predefined items.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// For predefined items, the name of their constructor, for example `"separator"` or `"copy"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub predefined: Option<String>,
+    /// The label, including mnemonic markers (`&`).
+    #[serde(default)]
+    pub text: String,
+    /// Whether the item is enabled, `true` when omitted.
+    #[serde(default = "enabled_default")]
+    pub enabled: bool,
+    /// For check items, whether the item is checked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checked: Option<bool>,
+    /// The accelerator in the format of [`Menu::export_accelerators`], if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accelerator: Option<String>,
+    /// For submenus, their items.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<ItemDescriptor>,
+    /// The payload of [`MenuItem::set_data_json`], if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
+fn enabled_default() -> bool {
+    true
+}
+
+/// Describes `menu` and its nested submenus.
+pub(crate) fn to_descriptor(menu: &Menu) -> MenuDescriptor {
+    MenuDescriptor {
+        id: menu.id().as_ref().to_string(),
+        items: menu.items().iter().map(to_node).collect(),
+    }
+}
+
+/// Builds a new menu from the description made by [`to_descriptor`].
+pub(crate) fn from_descriptor(descriptor: &MenuDescriptor) -> crate::Result<Menu> {
+    let menu = Menu::with_id(descriptor.id.as_str());
+    for item in &descriptor.items {
+        menu.append(from_node(item)?.as_ref())?;
+    }
+    Ok(menu)
+}
+
+/// Describes `menu` and its nested submenus as pretty-printed JSON.
+pub(crate) fn to_json(menu: &Menu) -> String {
+    serde_json::to_string_pretty(&to_descriptor(menu)).expect("menu descriptors always serialize")
+}
+
+/// Builds a new menu from JSON in the format of [`to_json`].
+pub(crate) fn from_json(json: &str) -> crate::Result<Menu> {
+    let descriptor: MenuDescriptor =
+        serde_json::from_str(json).map_err(crate::Error::InvalidJson)?;
+    from_descriptor(&descriptor)
+}
+
+fn to_node(item: &MenuItemKind) -> ItemDescriptor {
+    let (kind, child) = match item {
+        MenuItemKind::MenuItem(i) => (ItemDescriptorKind::Item, &i.inner),
+        MenuItemKind::Check(i) => (ItemDescriptorKind::Check, &i.inner),
+        MenuItemKind::Icon(i) => (ItemDescriptorKind::Icon, &i.inner),
+        MenuItemKind::Submenu(i) => (ItemDescriptorKind::Submenu, &i.inner),
+        MenuItemKind::Predefined(i) => (ItemDescriptorKind::Predefined, &i.inner),
+    };
+    let child = child.borrow();
+
+    ItemDescriptor {
+        kind,
+        // predefined items get a generated id
+        id: (kind != ItemDescriptorKind::Predefined).then(|| child.id().as_ref().to_string()),
+        predefined: child.predefined_item_type().map(|t| t.name().to_string()),
+        text: child.text(),
+        enabled: child.is_enabled(),
+        checked: (kind == ItemDescriptorKind::Check).then(|| child.is_checked()),
+        accelerator: accelerator_of(&child).map(|a| crate::keymap::format(&a)),
+        children: match item {
+            MenuItemKind::Submenu(submenu) => submenu.items().iter().map(to_node).collect(),
+            _ => Vec::new(),
+        },
+        // the payload was serialized by `set_data_json`
+        data: child
+            .data_json
+            .as_deref()
+            .and_then(|json| serde_json::from_str(json).ok()),
+    }
+}
+
+fn accelerator_of(child: &MenuChild) -> Option<Accelerator> {
+    match child.item_type() {
+        MenuItemType::MenuItem | MenuItemType::Check | MenuItemType::Icon => child.accelerator(),
+        MenuItemType::Submenu | MenuItemType::Predefined => None,
+    }
+}
+
+fn from_node(node: &ItemDescriptor) -> crate::Result<Box<dyn IsMenuItem>> {
+    let accelerator = node
+        .accelerator
+        .as_deref()
+        .map(str::parse::<Accelerator>)
+        .transpose()?;
+    // items without an id get a generated one, like the items of `MenuItem::new`
+    let id = || node.id.clone().map(crate::MenuId::new);
+
+    let item: Box<dyn IsMenuItem> = match node.kind {
+        ItemDescriptorKind::Item => Box::new(match id() {
+            Some(id) => MenuItem::with_id(id, &node.text, node.enabled, accelerator),
+            None => MenuItem::new(&node.text, node.enabled, accelerator),
+        }),
+        ItemDescriptorKind::Check => {
+            let checked = node.checked.unwrap_or_default();
+            Box::new(match id() {
+                Some(id) => {
+                    CheckMenuItem::with_id(id, &node.text, node.enabled, checked, accelerator)
+                }
+                None => CheckMenuItem::new(&node.text, node.enabled, checked, accelerator),
+            })
+        }
+        ItemDescriptorKind::Icon => Box::new(match id() {
+            Some(id) => IconMenuItem::with_id(id, &node.text, node.enabled, None, accelerator),
+            None => IconMenuItem::new(&node.text, node.enabled, None, accelerator),
+        }),
+        ItemDescriptorKind::Submenu => {
+            let submenu = match id() {
+                Some(id) => Submenu::with_id(id, &node.text, node.enabled),
+                None => Submenu::new(&node.text, node.enabled),
+            };
+            for child in &node.children {
+                submenu.append(from_node(child)?.as_ref())?;
+            }
+            Box::new(submenu)
+        }
+        ItemDescriptorKind::Predefined => {
+            let name = node.predefined.as_deref().unwrap_or_default();
+            let item_type = PredefinedMenuItemType::from_name(name).ok_or_else(|| {
+                crate::Error::InvalidJson(serde::de::Error::custom(format!(
+                    "unknown predefined item `{name}`"
+                )))
+            })?;
+            let item = PredefinedMenuItem::new(item_type, Some(&node.text));
+            let change = item.inner.borrow_mut().set_enabled(node.enabled);
+            change.notify();
+            Box::new(item)
+        }
+    };
+    if let Some(data) = &node.data {
+        item.kind().child_mut().data_json = Some(data.to_string());
+    }
+    Ok(item)
+}
@@ -0,0 +1,185 @@
+//! Plain-text keymaps of `id = accelerator` lines,
+//! see [`Menu::export_accelerators`](crate::Menu::export_accelerators).
+
+use keyboard_types::{Code, Modifiers};
+
+use crate::{accelerator::Accelerator, platform_impl::strip_mnemonic, Menu, MenuId, MenuItemKind};
+
+/// The conventional accelerators of common commands, by lowercase command name,
+/// see [`Menu::apply_standard_accelerators`].
+const STANDARD_ACCELERATORS: &[(&str, Modifiers, Code)] = &[
+    ("new", Modifiers::CONTROL, Code::KeyN),
+    ("open", Modifiers::CONTROL, Code::KeyO),
+    ("save", Modifiers::CONTROL, Code::KeyS),
+    (
+        "save as",
+        Modifiers::CONTROL.union(Modifiers::SHIFT),
+        Code::KeyS,
+    ),
+    ("print", Modifiers::CONTROL, Code::KeyP),
+    ("close", Modifiers::CONTROL, Code::KeyW),
+    ("undo", Modifiers::CONTROL, Code::KeyZ),
+    ("redo", Modifiers::CONTROL, Code::KeyY),
+    ("cut", Modifiers::CONTROL, Code::KeyX),
+    ("copy", Modifiers::CONTROL, Code::KeyC),
+    ("paste", Modifiers::CONTROL, Code::KeyV),
+    ("delete", Modifiers::empty(), Code::Delete),
+    ("select all", Modifiers::CONTROL, Code::KeyA),
+    ("find", Modifiers::CONTROL, Code::KeyF),
+    ("find next", Modifiers::empty(), Code::F3),
+    ("replace", Modifiers::CONTROL, Code::KeyH),
+    ("refresh", Modifiers::empty(), Code::F5),
+    ("help", Modifiers::empty(), Code::F1),
+];
+
+/// Writes one `id = accelerator` line for every item of `menu` that has an accelerator.
+pub(crate) fn export(menu: &Menu) -> String {
+    menu.shortcut_hints()
+        .into_iter()
+        .map(|(id, _, accelerator)| format!("{} = {}\n", id.as_ref(), format(&accelerator)))
+        .collect()
+}
+
+/// Parses `keymap` and applies it to the items of `menu`, nothing is applied if any line is invalid.
+pub(crate) fn import(menu: &Menu, keymap: &str) -> crate::Result<()> {
+    let mut bindings = Vec::new();
+    for (i, line) in keymap.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let invalid = |reason: String| crate::Error::InvalidKeymapLine {
+            line: i + 1,
+            reason,
+        };
+
+        let (id, accelerator) = line
+            .split_once('=')
+            .ok_or_else(|| invalid("expected `id = accelerator`".to_string()))?;
+        let id = MenuId::new(id.trim());
+        let accelerator = match accelerator.trim() {
+            "" => None,
+            accelerator => {
+                let accelerator = accelerator
+                    .parse::<Accelerator>()
+                    .map_err(|e| invalid(e.to_string()))?;
+                // fail before applying anything if the accelerator can't be registered
+                accelerator
+                    .to_accel(0)
+                    .map_err(|e| invalid(e.to_string()))?;
+                Some(accelerator)
+            }
+        };
+
+        let mut items = Vec::new();
+        find_items(&menu.items(), &id, &mut items);
+        if items.is_empty() {
+            return Err(invalid(format!("no item with id `{}`", id.as_ref())));
+        }
+
+        bindings.push((items, accelerator));
+    }
+
+    for (items, accelerator) in bindings {
+        for item in items {
+            match item {
+                MenuItemKind::MenuItem(i) => i.set_accelerator(accelerator)?,
+                MenuItemKind::Check(i) => i.set_accelerator(accelerator)?,
+                MenuItemKind::Icon(i) => i.set_accelerator(accelerator)?,
+                MenuItemKind::Submenu(_) | MenuItemKind::Predefined(_) => {}
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Gives the items of `menu` without an accelerator the conventional one of their command,
+/// found by id first and then by label.
+pub(crate) fn apply_standard(menu: &Menu) -> crate::Result<()> {
+    let mut taken = menu
+        .shortcut_hints()
+        .into_iter()
+        .map(|(_, _, accelerator)| accelerator)
+        .collect::<Vec<_>>();
+    apply_standard_to(&menu.items(), &mut taken)
+}
+
+fn apply_standard_to(items: &[MenuItemKind], taken: &mut Vec<Accelerator>) -> crate::Result<()> {
+    for item in items {
+        if let MenuItemKind::Submenu(submenu) = item {
+            apply_standard_to(&submenu.items(), taken)?;
+            continue;
+        }
+        if matches!(item, MenuItemKind::Predefined(_)) || item.child().accelerator().is_some() {
+            continue;
+        }
+
+        let Some(accelerator) = standard_accelerator(item.id().as_ref())
+            .or_else(|| standard_accelerator(&item.child().text()))
+            .filter(|a| !taken.contains(a))
+        else {
+            continue;
+        };
+        match item {
+            MenuItemKind::MenuItem(i) => i.set_accelerator(Some(accelerator))?,
+            MenuItemKind::Check(i) => i.set_accelerator(Some(accelerator))?,
+            MenuItemKind::Icon(i) => i.set_accelerator(Some(accelerator))?,
+            MenuItemKind::Submenu(_) | MenuItemKind::Predefined(_) => {}
+        }
+        taken.push(accelerator);
+    }
+    Ok(())
+}
+
+/// The conventional accelerator of the command `name`, which can be an id like `save_as`
+/// or a label like `Save &As...`.
+fn standard_accelerator(name: &str) -> Option<Accelerator> {
+    let name = strip_mnemonic(name)
+        .trim_end_matches("...")
+        .trim_end_matches('\u{2026}')
+        .replace(['_', '-'], " ")
+        .trim()
+        .to_lowercase();
+    STANDARD_ACCELERATORS
+        .iter()
+        .find(|(command, ..)| *command == name)
+        .map(|&(_, mods, key)| Accelerator::new(Some(mods), key))
+}
+
+/// Collects the items with `id` that can have an accelerator, searching nested submenus too.
+fn find_items(items: &[MenuItemKind], id: &MenuId, found: &mut Vec<MenuItemKind>) {
+    for item in items {
+        match item {
+            MenuItemKind::Submenu(submenu) => find_items(&submenu.items(), id, found),
+            MenuItemKind::Predefined(_) => {}
+            item if item.id() == id => found.push(item.clone()),
+            _ => {}
+        }
+    }
+}
+
+/// The display form of `accelerator` when it can be parsed back, for example `Ctrl+S`,
+/// otherwise the key is written with its [`Code`](keyboard_types::Code) name, like `Ctrl+PageUp`.
+pub(crate) fn format(accelerator: &Accelerator) -> String {
+    let display = accelerator.to_string();
+    if display.parse::<Accelerator>().ok().as_ref() == Some(accelerator) {
+        return display;
+    }
+
+    let mut text = String::new();
+    let mods = accelerator.modifiers();
+    for (modifier, name) in [
+        (Modifiers::CONTROL, "Ctrl+"),
+        (Modifiers::ALT, "Alt+"),
+        (Modifiers::SHIFT, "Shift+"),
+        (Modifiers::SUPER, "Windows+"),
+    ] {
+        if mods.contains(modifier) {
+            text.push_str(name);
+        }
+    }
+    text.push_str(&accelerator.key().to_string());
+    text
+}
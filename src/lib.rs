@@ -108,7 +108,8 @@
 //! [tao]: https://docs.rs/tao
 
 use crossbeam_channel::{unbounded, Receiver, Sender};
-use std::sync::{LazyLock, OnceLock};
+use std::sync::{Arc, LazyLock, RwLock};
+use util::Counter;
 
 pub mod about_metadata;
 pub mod accelerator;
@@ -116,9 +117,14 @@ mod builders;
 mod error;
 mod icon;
 mod items;
+#[cfg(feature = "serde")]
+mod json;
+mod keymap;
+mod macros;
 mod menu;
 mod menu_id;
 mod platform_impl;
+mod rebuild;
 mod util;
 
 pub use about_metadata::AboutMetadata;
@@ -127,8 +133,12 @@ pub use dpi;
 pub use error::*;
 pub use icon::{BadIcon, Icon, NativeIcon};
 pub use items::*;
+#[cfg(feature = "serde")]
+pub use json::{ItemDescriptor, ItemDescriptorKind, MenuDescriptor};
 pub use menu::*;
 pub use menu_id::MenuId;
+#[cfg(feature = "raw-window-handle")]
+pub use raw_window_handle;
 
 /// An enumeration of all available menu types, useful to match against
 /// the items returned from [`Menu::items`] or [`Submenu::items`]
@@ -153,6 +163,16 @@ impl MenuItemKind {
         }
     }
 
+    /// Returns whether this item is enabled, for every kind of item, including the title of a [`Submenu`].
+    pub fn is_enabled(&self) -> bool {
+        self.child().is_enabled()
+    }
+
+    /// Returns whether this item is checked, or `None` if it isn't a [`CheckMenuItem`].
+    pub fn is_checked(&self) -> Option<bool> {
+        self.as_check_menuitem().map(|i| i.is_checked())
+    }
+
     /// Casts this item to a [`MenuItem`], and returns `None` if it wasn't.
     pub fn as_menuitem(&self) -> Option<&MenuItem> {
         match self {
@@ -279,6 +299,9 @@ impl Default for MenuItemType {
 pub trait ContextMenu {
     /// Get the popup [`HMENU`] for this menu.
     ///
+    /// This is the handle to pass to `TrackPopupMenu` and similar APIs that show the menu as a popup,
+    /// for a [`Menu`] it is distinct from the menu bar handle, see [`Menu::hmenu_bar`].
+    ///
     /// The returned [`HMENU`] is valid as long as the `ContextMenu` is.
     ///
     /// [`HMENU`]: windows_sys::Win32::UI::WindowsAndMessaging::HMENU
@@ -299,6 +322,100 @@ pub trait ContextMenu {
         position: Option<dpi::Position>,
     ) -> bool;
 
+    /// Same as [`ContextMenu::show_context_menu_for_hwnd`] but for a window of `winit`, `tao`
+    /// or any other windowing library implementing [`HasWindowHandle`](raw_window_handle::HasWindowHandle).
+    ///
+    /// Returns [`Error::NotAWin32Window`] if the window handle isn't a Win32 one.
+    #[cfg(feature = "raw-window-handle")]
+    fn show_context_menu_for_window(
+        &self,
+        window: &dyn raw_window_handle::HasWindowHandle,
+        position: Option<dpi::Position>,
+    ) -> Result<bool> {
+        let hwnd = util::hwnd_of(window)?;
+        // the handle is valid while `window` is borrowed
+        Ok(unsafe { self.show_context_menu_for_hwnd(hwnd, position) })
+    }
+
+    /// Same as [`ContextMenu::show_context_menu_for_hwnd`] but `position` is in screen coordinates,
+    /// like the ones of `GetCursorPos` or of a tray icon event, instead of relative to the window.
+    ///
+    /// A logical `position` is scaled with the DPI of `hwnd`, like for [`ContextMenu::show_context_menu_for_hwnd`].
+    ///
+    /// # Safety
+    ///
+    /// The `hwnd` must be a valid window HWND.
+    unsafe fn show_context_menu_at_screen_pos(&self, hwnd: isize, position: dpi::Position) -> bool;
+
+    /// Same as [`ContextMenu::show_context_menu_for_hwnd`] but returns the id of the selected item,
+    /// or `None` if menu tracking was cancelled.
+    ///
+    /// The [`MenuEvent`] of the item is still sent, but callers don't need to wait for it
+    /// to know what was selected.
+    ///
+    /// # Safety
+    ///
+    /// The `hwnd` must be a valid window HWND.
+    unsafe fn show_context_menu_for_hwnd_blocking(
+        &self,
+        hwnd: isize,
+        position: Option<dpi::Position>,
+    ) -> Option<MenuId>;
+
+    /// Same as [`ContextMenu::show_context_menu_for_hwnd_blocking`] but consumes the menu,
+    /// for throwaway menus built right before they are shown.
+    ///
+    /// The native handles of the menu and of its items are destroyed as soon as the menu
+    /// is closed, unless clones of the menu or of its items are kept elsewhere, their
+    /// handles are then destroyed with the last clone as usual.
+    ///
+    /// # Safety
+    ///
+    /// The `hwnd` must be a valid window HWND.
+    unsafe fn show_context_menu_for_hwnd_once(
+        self,
+        hwnd: isize,
+        position: Option<dpi::Position>,
+    ) -> Option<MenuId>
+    where
+        Self: Sized,
+    {
+        let selected = self.show_context_menu_for_hwnd_blocking(hwnd, position);
+        drop(self);
+        selected
+    }
+
+    /// Same as [`ContextMenu::show_context_menu_for_hwnd`] but with more [`ContextMenuOptions`],
+    /// like the item highlighted when the menu opens.
+    ///
+    /// # Safety
+    ///
+    /// The `hwnd` must be a valid window HWND.
+    unsafe fn show_context_menu_with_options_for_hwnd(
+        &self,
+        hwnd: isize,
+        options: ContextMenuOptions,
+    ) -> bool;
+
+    /// Shows this menu as a dropdown of a control inside a win32 window, like a dropdown button.
+    ///
+    /// - `position` and `size` describe the control, relative to the window top-left corner.
+    ///
+    /// The menu is aligned with the left edge of the control and shown directly below it,
+    /// or above it if there isn't enough room below on the monitor. The menu never covers the control.
+    ///
+    /// Returns `true` if menu tracking ended because an item was selected, and `false` if menu tracking was cancelled for any reason.
+    ///
+    /// # Safety
+    ///
+    /// The `hwnd` must be a valid window HWND.
+    unsafe fn show_below_control(
+        &self,
+        hwnd: isize,
+        position: dpi::Position,
+        size: dpi::Size,
+    ) -> bool;
+
     /// Attach the menu subclass handler to the given hwnd
     /// so you can recieve events from that window using [MenuEvent::receiver]
     ///
@@ -309,6 +426,18 @@ pub trait ContextMenu {
     /// The `hwnd` must be a valid window HWND.
     unsafe fn attach_menu_subclass_for_hwnd(&self, hwnd: isize);
 
+    /// Same as [`ContextMenu::attach_menu_subclass_for_hwnd`] but for a window implementing
+    /// [`HasWindowHandle`](raw_window_handle::HasWindowHandle), see [`ContextMenu::show_context_menu_for_window`].
+    #[cfg(feature = "raw-window-handle")]
+    fn attach_menu_subclass_for_window(
+        &self,
+        window: &dyn raw_window_handle::HasWindowHandle,
+    ) -> Result<()> {
+        let hwnd = util::hwnd_of(window)?;
+        unsafe { self.attach_menu_subclass_for_hwnd(hwnd) };
+        Ok(())
+    }
+
     /// Remove the menu subclass handler from the given hwnd
     ///
     /// The view must be a pointer to a valid `NSView`.
@@ -317,6 +446,74 @@ pub trait ContextMenu {
     ///
     /// The `hwnd` must be a valid window HWND.
     unsafe fn detach_menu_subclass_from_hwnd(&self, hwnd: isize);
+
+    /// Estimates the size this menu would have if shown as a context menu inside `hwnd`,
+    /// useful to position it without showing it first, for example to keep it on screen.
+    ///
+    /// Owner-drawn items are measured by `hwnd`, so it should have the menu subclass
+    /// attached, see [`ContextMenu::attach_menu_subclass_for_hwnd`].
+    ///
+    /// Returns `None` if the menu couldn't be measured.
+    ///
+    /// # Safety
+    ///
+    /// The `hwnd` must be a valid window HWND.
+    unsafe fn measure(&self, hwnd: isize) -> Option<dpi::Size> {
+        platform_impl::measure_menu(hwnd as _, self.hpopupmenu() as _).map(Into::into)
+    }
+
+    /// Closes this menu if it is currently shown as a context menu, for example because
+    /// the document it acts on was closed.
+    ///
+    /// The `show_*` method that opened the menu returns as if the menu was cancelled.
+    /// This is a no-op if the menu isn't shown.
+    fn dismiss(&self) {
+        platform_impl::dismiss_context_menu(self.hpopupmenu())
+    }
+}
+
+/// Options for [`ContextMenu::show_context_menu_with_options_for_hwnd`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Default)]
+pub struct ContextMenuOptions {
+    /// The position relative to the window top-left corner, if `None`, the cursor position is used.
+    pub position: Option<dpi::Position>,
+    /// The index of the item highlighted when the menu opens, so pressing Enter activates it.
+    ///
+    /// Only the items directly in the menu can be selected, not the items of its submenus.
+    /// The selection is ignored if there is no item at this index or if it is a separator.
+    pub select: Option<usize>,
+    /// Whether the menu opens again at the same position after a check item was toggled,
+    /// so several check items can be toggled in a row. Defaults to `false`.
+    ///
+    /// Only check items keep the menu open, any other item closes it as usual,
+    /// and pressing Escape or clicking outside the menu still dismisses it.
+    pub keep_open_on_check: bool,
+}
+
+impl ContextMenuOptions {
+    /// Options showing the menu at the cursor position, without a highlighted item.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets [`ContextMenuOptions::position`].
+    pub fn with_position<P: Into<dpi::Position>>(mut self, position: P) -> Self {
+        self.position = Some(position.into());
+        self
+    }
+
+    /// Sets [`ContextMenuOptions::select`].
+    pub fn with_select(mut self, index: usize) -> Self {
+        self.select = Some(index);
+        self
+    }
+
+    /// Sets [`ContextMenuOptions::keep_open_on_check`].
+    pub fn with_keep_open_on_check(mut self, keep_open: bool) -> Self {
+        self.keep_open_on_check = keep_open;
+        self
+    }
 }
 
 /// Describes a menu event emitted when a menu item is activated
@@ -331,8 +528,18 @@ pub struct MenuEvent {
 pub type MenuEventReceiver = Receiver<MenuEvent>;
 pub type MenuEventHandler = Box<dyn Fn(MenuEvent) + Send + Sync + 'static>;
 
+pub type MenuEventObserver = Arc<dyn Fn(&MenuEvent) + Send + Sync + 'static>;
+type SharedMenuEventHandler = Arc<dyn Fn(MenuEvent) + Send + Sync + 'static>;
+
+/// An identifier for an observer added using [`MenuEvent::add_observer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ObserverId(u32);
+
 static MENU_CHANNEL: LazyLock<(Sender<MenuEvent>, MenuEventReceiver)> = LazyLock::new(unbounded);
-static MENU_EVENT_HANDLER: OnceLock<Option<MenuEventHandler>> = OnceLock::new();
+// an `Arc` so the handler can be called without holding the lock, letting it replace itself
+static MENU_EVENT_HANDLER: RwLock<Option<SharedMenuEventHandler>> = RwLock::new(None);
+static MENU_EVENT_OBSERVERS: RwLock<Vec<(ObserverId, MenuEventObserver)>> = RwLock::new(Vec::new());
+static OBSERVER_COUNTER: Counter = Counter::new();
 
 impl MenuEvent {
     /// Returns the id of the menu item which triggered this event
@@ -352,33 +559,233 @@ impl MenuEvent {
 
     /// Set a handler to be called for new events. Useful for implementing custom event sender.
     ///
+    /// Calling this function again replaces the previous handler, and calling it with `None`
+    /// sends new events to the channel associated with [`MenuEvent::receiver`] again.
+    ///
+    /// Returns the previous handler, if any, so it can be restored later.
+    ///
     /// ## Note
     ///
     /// Calling this function with a `Some` value,
     /// will not send new events to the channel associated with [`MenuEvent::receiver`]
     pub fn set_event_handler<F: Fn(MenuEvent) + Send + Sync + 'static>(
         f: Option<F>,
-    ) -> Option<Option<MenuEventHandler>> {
-        if let Some(f) = f {
-            // Wrap the closure in a Box to store on the heap
-            let boxed_handler = Box::new(f);
-            let _ = MENU_EVENT_HANDLER.set(Some(boxed_handler));
-        } else {
-            let _ = MENU_EVENT_HANDLER.set(None);
-        }
+    ) -> Option<MenuEventHandler> {
+        let handler = f.map(|f| Arc::new(f) as SharedMenuEventHandler);
+        let previous = std::mem::replace(
+            &mut *MENU_EVENT_HANDLER
+                .write()
+                .unwrap_or_else(|e| e.into_inner()),
+            handler,
+        );
+        previous.map(|handler| Box::new(move |event| handler(event)) as MenuEventHandler)
+    }
 
-        // Dereference and return the Box
-        match MENU_EVENT_HANDLER.get() {
-            Some(Some(handler)) => Some(Some(Box::new(handler))),
-            _ => None,
-        }
+    /// Add an observer that is called for every new event, in addition to
+    /// the channel associated with [`MenuEvent::receiver`] or the handler
+    /// set by [`MenuEvent::set_event_handler`].
+    ///
+    /// Useful for logging or testing without disturbing how events are normally delivered.
+    pub fn add_observer<F: Fn(&MenuEvent) + Send + Sync + 'static>(f: F) -> ObserverId {
+        let id = ObserverId(OBSERVER_COUNTER.next());
+        MENU_EVENT_OBSERVERS
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .push((id, Arc::new(f)));
+        id
+    }
+
+    /// Remove an observer previously added using [`MenuEvent::add_observer`].
+    ///
+    /// Returns `false` if no observer with this id was found.
+    pub fn remove_observer(id: ObserverId) -> bool {
+        let mut observers = MENU_EVENT_OBSERVERS
+            .write()
+            .unwrap_or_else(|e| e.into_inner());
+        let len = observers.len();
+        observers.retain(|(i, _)| *i != id);
+        observers.len() != len
     }
 
     pub(crate) fn send(event: MenuEvent) {
-        if let Some(handler) = MENU_EVENT_HANDLER.get_or_init(|| None) {
+        // clone the observers so they can add or remove observers while being called
+        let observers = MENU_EVENT_OBSERVERS
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(_, o)| o.clone())
+            .collect::<Vec<_>>();
+        for observer in observers {
+            observer(&event);
+        }
+
+        let handler = MENU_EVENT_HANDLER
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        if let Some(handler) = handler {
             handler(event);
         } else {
             let _ = MENU_CHANNEL.0.send(event);
         }
     }
 }
+
+/// Describes a submenu event emitted when a submenu opens or closes.
+///
+/// Submenu events are only emitted for windows with the menu subclass, see
+/// [`Menu::init_for_hwnd`] and [`ContextMenu::attach_menu_subclass_for_hwnd`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SubmenuEvent {
+    /// Id of the submenu which opened or closed
+    pub id: MenuId,
+    /// Whether the submenu opened or closed
+    pub kind: SubmenuEventKind,
+}
+
+/// The kind of a [`SubmenuEvent`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SubmenuEventKind {
+    /// The submenu is about to be shown (`WM_INITMENUPOPUP`), its items aren't drawn yet.
+    Opened,
+    /// The submenu was closed (`WM_UNINITMENUPOPUP`).
+    Closed,
+}
+
+/// A reciever that could be used to listen to submenu events.
+pub type SubmenuEventReceiver = Receiver<SubmenuEvent>;
+pub type SubmenuEventHandler = Box<dyn Fn(SubmenuEvent) + Send + Sync + 'static>;
+
+type SharedSubmenuEventHandler = Arc<dyn Fn(SubmenuEvent) + Send + Sync + 'static>;
+
+static SUBMENU_CHANNEL: LazyLock<(Sender<SubmenuEvent>, SubmenuEventReceiver)> =
+    LazyLock::new(unbounded);
+static SUBMENU_EVENT_HANDLER: RwLock<Option<SharedSubmenuEventHandler>> = RwLock::new(None);
+
+impl SubmenuEvent {
+    /// Returns the id of the submenu which triggered this event
+    pub fn id(&self) -> &MenuId {
+        &self.id
+    }
+
+    /// Gets a reference to the event channel's [`SubmenuEventReceiver`]
+    /// which can be used to listen for submenu events.
+    ///
+    /// ## Note
+    ///
+    /// This will not receive any events if [`SubmenuEvent::set_event_handler`] has been called with a `Some` value.
+    pub fn receiver<'a>() -> &'a SubmenuEventReceiver {
+        &SUBMENU_CHANNEL.1
+    }
+
+    /// Set a handler to be called for new events, like [`MenuEvent::set_event_handler`].
+    ///
+    /// Unlike the channel, the handler is called right away, on the thread of the menu, so a
+    /// handler receiving [`SubmenuEventKind::Opened`] can fill the submenu before it is shown,
+    /// for example with [`Submenu::set_items`]. Menus are not [`Send`], so the handler has to
+    /// reach the submenu through a `thread_local!`.
+    ///
+    /// ## Reentrancy
+    ///
+    /// The handler is called from the window procedure while the menu is tracked:
+    ///
+    /// - The items of the submenu that opened, and of any other menu, can be added, removed
+    ///   and changed, except for a [`Submenu`] shown with [`ContextMenu::show_context_menu_for_hwnd`]
+    ///   (and the other `show_*` methods), which is borrowed until it closes, so changing
+    ///   its own items panics. Its nested submenus can be changed.
+    /// - Showing another menu or a modal dialog from the handler is not supported.
+    /// - Changes made on [`SubmenuEventKind::Closed`] apply the next time the submenu opens.
+    pub fn set_event_handler<F: Fn(SubmenuEvent) + Send + Sync + 'static>(
+        f: Option<F>,
+    ) -> Option<SubmenuEventHandler> {
+        let handler = f.map(|f| Arc::new(f) as SharedSubmenuEventHandler);
+        let previous = std::mem::replace(
+            &mut *SUBMENU_EVENT_HANDLER
+                .write()
+                .unwrap_or_else(|e| e.into_inner()),
+            handler,
+        );
+        previous.map(|handler| Box::new(move |event| handler(event)) as SubmenuEventHandler)
+    }
+
+    pub(crate) fn send(event: SubmenuEvent) {
+        let handler = SUBMENU_EVENT_HANDLER
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        if let Some(handler) = handler {
+            handler(event);
+        } else {
+            let _ = SUBMENU_CHANNEL.0.send(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{MenuEvent, MenuId};
+
+    #[test]
+    fn observers_see_every_event() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let seen_c = seen.clone();
+        let observer = MenuEvent::add_observer(move |e| {
+            if e.id == "observed" {
+                seen_c.lock().unwrap().push(e.id.clone());
+            }
+        });
+
+        MenuEvent::send(MenuEvent {
+            id: MenuId::new("observed"),
+        });
+        assert_eq!(*seen.lock().unwrap(), vec![MenuId::new("observed")]);
+
+        assert!(MenuEvent::remove_observer(observer));
+        assert!(!MenuEvent::remove_observer(observer));
+
+        MenuEvent::send(MenuEvent {
+            id: MenuId::new("observed"),
+        });
+        assert_eq!(seen.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn event_handler_can_be_replaced_and_removed() {
+        let handled = Arc::new(Mutex::new(Vec::new()));
+        let handler = |name: &'static str| {
+            let handled = handled.clone();
+            move |e: MenuEvent| {
+                if e.id == "replaced" {
+                    handled.lock().unwrap().push(name);
+                }
+            }
+        };
+        let send = || {
+            MenuEvent::send(MenuEvent {
+                id: MenuId::new("replaced"),
+            })
+        };
+
+        MenuEvent::set_event_handler(Some(handler("first")));
+        send();
+        let first = MenuEvent::set_event_handler(Some(handler("second"))).unwrap();
+        send();
+        assert_eq!(*handled.lock().unwrap(), vec!["first", "second"]);
+
+        // the returned handler is the one that was replaced
+        first(MenuEvent {
+            id: MenuId::new("replaced"),
+        });
+        assert_eq!(handled.lock().unwrap().last(), Some(&"first"));
+
+        assert!(MenuEvent::set_event_handler(None::<fn(MenuEvent)>).is_some());
+        send();
+        assert_eq!(handled.lock().unwrap().len(), 3);
+        assert!(MenuEvent::receiver().try_iter().any(|e| e.id == "replaced"));
+        assert!(MenuEvent::set_event_handler(None::<fn(MenuEvent)>).is_none());
+    }
+}
@@ -0,0 +1,132 @@
+/// Builds a [`Menu`](crate::Menu) from an inline description of its items.
+///
+/// Items are separated by commas and can be:
+///
+/// - `item "Text" [Ctrl+S] => id`: a [`MenuItem`](crate::MenuItem), the accelerator is optional.
+/// - `check "Text" (checked) [Ctrl+S] => id`: a [`CheckMenuItem`](crate::CheckMenuItem),
+///   `checked` is a `bool` expression and the accelerator is optional.
+/// - `separator`: a [`PredefinedMenuItem::separator`](crate::PredefinedMenuItem::separator).
+/// - `submenu "Text" { ... }`: a [`Submenu`](crate::Submenu) containing the items inside the braces.
+///
+/// `id` is any expression that converts into a [`MenuId`](crate::MenuId), and accelerators
+/// are written the same way as they are parsed by [`Accelerator`](crate::accelerator::Accelerator).
+///
+/// Evaluates to a `crate::Result<Menu>` that fails if an accelerator can't be parsed.
+///
+/// ## Example
+///
+/// ```no_run
+/// let menu = muda_win::menu! {
+///     submenu "&File" {
+///         item "&New" [Ctrl+N] => "new",
+///         item "&Save" [Ctrl+S] => "save",
+///         separator,
+///         check "Auto Save" (true) => "auto-save",
+///     },
+///     item "&Help" [F1] => "help",
+/// }?;
+/// # Ok::<(), muda_win::Error>(())
+/// ```
+#[macro_export]
+macro_rules! menu {
+    ($($items:tt)*) => {
+        (|| -> $crate::Result<$crate::Menu> {
+            let menu = $crate::Menu::new();
+            $crate::__menu_items!(menu; $($items)*);
+            Ok(menu)
+        })()
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __menu_items {
+    ($parent:ident;) => {};
+    ($parent:ident; separator $(, $($rest:tt)*)?) => {
+        $parent.append(&$crate::PredefinedMenuItem::separator())?;
+        $crate::__menu_items!($parent; $($($rest)*)?);
+    };
+    ($parent:ident; item $text:literal $([$($accel:tt)+])? => $id:expr $(, $($rest:tt)*)?) => {
+        $parent.append(&$crate::MenuItem::with_id(
+            $id,
+            $text,
+            true,
+            $crate::__menu_accelerator!($($($accel)+)?),
+        ))?;
+        $crate::__menu_items!($parent; $($($rest)*)?);
+    };
+    ($parent:ident; check $text:literal ($checked:expr) $([$($accel:tt)+])? => $id:expr $(, $($rest:tt)*)?) => {
+        $parent.append(&$crate::CheckMenuItem::with_id(
+            $id,
+            $text,
+            true,
+            $checked,
+            $crate::__menu_accelerator!($($($accel)+)?),
+        ))?;
+        $crate::__menu_items!($parent; $($($rest)*)?);
+    };
+    ($parent:ident; submenu $text:literal { $($items:tt)* } $(, $($rest:tt)*)?) => {
+        {
+            let submenu = $crate::Submenu::new($text, true);
+            $crate::__menu_items!(submenu; $($items)*);
+            $parent.append(&submenu)?;
+        }
+        $crate::__menu_items!($parent; $($($rest)*)?);
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __menu_accelerator {
+    () => {
+        None
+    };
+    ($($accel:tt)+) => {
+        Some(::std::stringify!($($accel)+).parse::<$crate::accelerator::Accelerator>()?)
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        accelerator::{Accelerator, Code, Modifiers},
+        MenuItemKind,
+    };
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_builds_menu_from_macro() {
+        let menu = menu! {
+            submenu "File" {
+                item "Save" [Ctrl+S] => "save",
+                separator,
+                check "Auto Save" (true) => "auto-save",
+            },
+            item "Help" => "help",
+        }
+        .unwrap();
+
+        let items = menu.items();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[1].id(), "help");
+
+        let file = items[0].as_submenu().unwrap();
+        assert_eq!(file.text(), "File");
+
+        let file_items = file.items();
+        assert_eq!(file_items.len(), 3);
+
+        let save = file_items[0].as_menuitem().unwrap();
+        assert_eq!(save.id(), "save");
+        assert_eq!(
+            save.accelerator(),
+            Some(Accelerator::new(Some(Modifiers::CONTROL), Code::KeyS))
+        );
+
+        assert!(matches!(file_items[1], MenuItemKind::Predefined(_)));
+
+        let auto_save = file_items[2].as_check_menuitem().unwrap();
+        assert_eq!(auto_save.id(), "auto-save");
+        assert!(auto_save.is_checked());
+    }
+}
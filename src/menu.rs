@@ -1,13 +1,28 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
 
-use crate::{dpi::Position, util::AddOp, ContextMenu, IsMenuItem, MenuId, MenuItemKind};
+use crate::{
+    accelerator::Accelerator,
+    dpi::{Position, Size},
+    util::AddOp,
+    ContextMenu, ContextMenuOptions, IsMenuItem, MenuEvent, MenuId, MenuItemKind, Submenu,
+};
 
 /// A root menu that can be added to a Window on Windows and Linux
 /// and used as the app global menu on macOS.
+///
+/// Menus and their items must be created on a thread that can have a message queue, which becomes
+/// their owner: the fallible constructors like [`Menu::with_items`] return
+/// [`Error::WrongThread`](crate::Error::WrongThread) on other threads and the others panic in debug builds.
+/// Appending, inserting or removing items from another thread returns the same error.
 #[derive(Clone)]
 pub struct Menu {
     id: Rc<MenuId>,
     inner: Rc<RefCell<crate::platform_impl::Menu>>,
+    /// Shared with `inner`, which is mutably borrowed while its context menu is shown.
+    tracking: Rc<Cell<u32>>,
 }
 
 impl Default for Menu {
@@ -16,12 +31,20 @@ impl Default for Menu {
     }
 }
 
+/// Builds a new menu with the collected items, see [`Menu::try_from_iter`].
+impl FromIterator<Box<dyn IsMenuItem>> for crate::Result<Menu> {
+    fn from_iter<I: IntoIterator<Item = Box<dyn IsMenuItem>>>(items: I) -> Self {
+        Menu::try_from_iter(items)
+    }
+}
+
 impl Menu {
     /// Creates a new menu.
     pub fn new() -> Self {
         let menu = crate::platform_impl::Menu::new(None);
         Self {
             id: Rc::new(menu.id().clone()),
+            tracking: menu.tracking(),
             inner: Rc::new(RefCell::new(menu)),
         }
     }
@@ -29,24 +52,96 @@ impl Menu {
     /// Creates a new menu with the specified id.
     pub fn with_id<I: Into<MenuId>>(id: I) -> Self {
         let id = id.into();
+        let menu = crate::platform_impl::Menu::new(Some(id.clone()));
         Self {
-            id: Rc::new(id.clone()),
-            inner: Rc::new(RefCell::new(crate::platform_impl::Menu::new(Some(id)))),
+            id: Rc::new(id),
+            tracking: menu.tracking(),
+            inner: Rc::new(RefCell::new(menu)),
+        }
+    }
+
+    /// Creates a conventional menu bar to start from: File (New, Open, Save and Quit),
+    /// Edit (Undo, Redo, Cut, Copy, Paste and Select All) and Help (About), with the usual
+    /// accelerators. Use [`StandardAppMenuBuilder`](crate::StandardAppMenuBuilder) to leave out
+    /// submenus or to show an about dialog.
+    ///
+    /// The submenus and items have the stable ids of [`standard_ids`](crate::standard_ids),
+    /// so apps can find the submenus to append their own items and match the events of New, Open,
+    /// Save, Undo, Redo and About, which are normal items with no built-in action.
+    pub fn standard_app_menu() -> Menu {
+        crate::StandardAppMenuBuilder::new()
+            .build()
+            .expect("the standard menu has valid accelerators")
+    }
+
+    /// Creates a new menu with copies of the items of `hmenu`, an [`HMENU`] built with the Win32 API,
+    /// for example by legacy code, and of its submenus.
+    ///
+    /// The items are copied, `hmenu` is not adopted: the caller still owns it and can destroy it,
+    /// and changes made to it afterwards are not reflected in this menu. The new menu can then be
+    /// added to windows with [`Menu::init_for_hwnd`] or shown with [`ContextMenu`] like any other.
+    ///
+    /// The id of each item is its command id as a string, for example `"101"`, so the
+    /// [`MenuEvent`](crate::MenuEvent)s of the new menu can be matched with the command ids of
+    /// the legacy code. Text after a tab is parsed as the accelerator of the item, items with
+    /// `MFT_RADIOCHECK` or check mark bitmaps become [`CheckMenuItem`](crate::CheckMenuItem)s,
+    /// consecutive radio items in a radio group, and separators stay separators. Items added
+    /// to `hmenu` with the Win32 API afterwards don't emit events, create the menu again instead.
+    ///
+    /// Returns [`Error::OsError`](crate::Error::OsError) if `hmenu` can't be read.
+    ///
+    /// # Safety
+    ///
+    /// `hmenu` must be a valid menu handle.
+    ///
+    /// [`HMENU`]: windows_sys::Win32::UI::WindowsAndMessaging::HMENU
+    pub unsafe fn copy_from_hmenu(hmenu: isize) -> crate::Result<Self> {
+        let menu = Self::new();
+        for item in crate::platform_impl::import::items(hmenu as _)? {
+            menu.append(item.as_ref())?;
         }
+        Ok(menu)
+    }
+
+    /// Creates a new menu that uses `theme` for its menu bar when it is added to a window
+    /// with [`Menu::init_for_hwnd`], just like [`Menu::init_for_hwnd_with_theme`] would.
+    ///
+    /// The theme can still be changed per window using [`Menu::set_theme_for_hwnd`].
+    pub fn with_theme(theme: MenuTheme) -> Self {
+        let menu = Self::new();
+        menu.inner.borrow_mut().set_default_theme(theme);
+        menu
     }
 
     /// Creates a new menu with given `items`. It calls [`Menu::new`] and [`Menu::append_items`] internally.
     pub fn with_items(items: &[&dyn IsMenuItem]) -> crate::Result<Self> {
+        crate::platform_impl::check_ui_thread()?;
         let menu = Self::new();
         menu.append_items(items)?;
         Ok(menu)
     }
 
+    /// Creates a new menu with the `items` of an iterator, appended in order,
+    /// returns the first error of [`Menu::append`].
+    ///
+    /// Collecting boxed items into a [`Result<Menu>`](crate::Result) does the same.
+    pub fn try_from_iter<I: IntoIterator<Item = Box<dyn IsMenuItem>>>(
+        items: I,
+    ) -> crate::Result<Self> {
+        crate::platform_impl::check_ui_thread()?;
+        let menu = Self::new();
+        for item in items {
+            menu.append(item.as_ref())?;
+        }
+        Ok(menu)
+    }
+
     /// Creates a new menu with the specified id and given `items`. It calls [`Menu::new`] and [`Menu::append_items`] internally.
     pub fn with_id_and_items<I: Into<MenuId>>(
         id: I,
         items: &[&dyn IsMenuItem],
     ) -> crate::Result<Self> {
+        crate::platform_impl::check_ui_thread()?;
         let menu = Self::with_id(id);
         menu.append_items(items)?;
         Ok(menu)
@@ -64,6 +159,7 @@ impl Menu {
 
     /// Add menu items to the end of this menu. It calls [`Menu::append`] in a loop internally.
     pub fn append_items(&self, items: &[&dyn IsMenuItem]) -> crate::Result<()> {
+        let _update = self.begin_update();
         for item in items {
             self.append(*item)?
         }
@@ -84,14 +180,36 @@ impl Menu {
     }
 
     /// Insert a menu item at the specified `postion` in the menu.
+    ///
+    /// Separators count in `position` like any item, see [`Menu::insert_skipping_separators`]
+    /// to count only the other items.
     pub fn insert(&self, item: &dyn IsMenuItem, position: usize) -> crate::Result<()> {
         self.inner
             .borrow_mut()
             .add_menu_item(item, AddOp::Insert(position))
     }
 
+    /// Insert a menu item so that it becomes the item at `logical_index` of
+    /// [`Menu::non_separator_items`], before the item currently there, or at the end.
+    ///
+    /// Unlike the raw positions of [`Menu::insert`] and [`Menu::items`], `logical_index` only
+    /// counts the items that aren't separators, so inserting at `1` in a menu with a separator
+    /// between its first two items inserts after that separator, before the second item.
+    pub fn insert_skipping_separators(
+        &self,
+        item: &dyn IsMenuItem,
+        logical_index: usize,
+    ) -> crate::Result<()> {
+        let position = self
+            .inner
+            .borrow()
+            .position_skipping_separators(logical_index);
+        self.insert(item, position)
+    }
+
     /// Insert menu items at the specified `postion` in the menu.
     pub fn insert_items(&self, items: &[&dyn IsMenuItem], position: usize) -> crate::Result<()> {
+        let _update = self.begin_update();
         for (i, item) in items.iter().enumerate() {
             self.insert(*item, position + i)?
         }
@@ -99,6 +217,30 @@ impl Menu {
         Ok(())
     }
 
+    /// Starts a batch of changes to this menu, until the returned guard is dropped.
+    ///
+    /// Meanwhile adding and removing items doesn't redraw the menu bars of the windows
+    /// of this menu nor rebuild its accelerator table, both happen once when the guard
+    /// is dropped. Accelerators of the items added in the meantime are not translated
+    /// until then. Guards can be nested, only dropping the outermost one ends the batch.
+    ///
+    /// [`Menu::append_items`] and [`Menu::insert_items`] use one internally.
+    ///
+    /// ```no_run
+    /// # use muda_win::{Menu, MenuItem};
+    /// let menu = Menu::new();
+    /// {
+    ///     let _update = menu.begin_update();
+    ///     for i in 0..100 {
+    ///         menu.append(&MenuItem::new(format!("Item {i}"), true, None)).unwrap();
+    ///     }
+    /// }
+    /// ```
+    pub fn begin_update(&self) -> MenuUpdateGuard<'_> {
+        self.inner.borrow_mut().begin_update();
+        MenuUpdateGuard { menu: self }
+    }
+
     /// Remove a menu item from this menu.
     pub fn remove(&self, item: &dyn IsMenuItem) -> crate::Result<()> {
         self.inner.borrow_mut().remove(item)
@@ -116,13 +258,76 @@ impl Menu {
         }
     }
 
+    /// Removes all the items of this menu at once and returns them, for example to rebuild it.
+    ///
+    /// The menu bar is redrawn once, and the menu stays valid so new items can be appended right away.
+    /// The accelerator table isn't rebuilt, so [`Menu::haccel`] stays valid.
+    pub fn clear(&self) -> crate::Result<Vec<MenuItemKind>> {
+        self.inner.borrow_mut().clear()
+    }
+
+    /// Updates this menu in place to match the structure and state of `desired`.
+    ///
+    /// Items are matched by id (predefined items by their text), so only the differences
+    /// are applied: missing items are inserted, extra items are removed, misplaced items are moved,
+    /// and the text, enabled state, checked state and accelerator of matching items are updated.
+    /// Submenus with matching ids are updated recursively.
+    ///
+    /// This avoids the flicker of clearing and rebuilding the whole menu, the windows of this menu
+    /// are only redrawn once all the changes are applied.
+    ///
+    /// Items that are missing from this menu are added as copies with the same ids, so later
+    /// changes to the items of `desired` only show up in this menu on the next rebuild.
+    pub fn rebuild_from(&self, desired: &Menu) -> crate::Result<()> {
+        self.sync_from(desired).map(|_| ())
+    }
+
+    /// Same as [`Menu::rebuild_from`] but returns the number of applied operations.
+    fn sync_from(&self, desired: &Menu) -> crate::Result<usize> {
+        let _update = self.begin_update();
+        crate::rebuild::sync_items(self, &desired.items())
+    }
+
     /// Returns a list of menu items that has been added to this menu.
+    ///
+    /// Hidden items are included, see [`Menu::visible_items`].
     pub fn items(&self) -> Vec<MenuItemKind> {
         self.inner.borrow().items()
     }
 
+    /// Returns the items of this menu that aren't hidden, see [`MenuItem::set_visible`](crate::MenuItem::set_visible).
+    pub fn visible_items(&self) -> Vec<MenuItemKind> {
+        self.items()
+            .into_iter()
+            .filter(|item| item.child().is_visible())
+            .collect()
+    }
+
+    /// Returns the items of this menu that aren't separators, whose positions are the logical
+    /// indices of [`Menu::insert_skipping_separators`].
+    pub fn non_separator_items(&self) -> Vec<MenuItemKind> {
+        self.inner.borrow().non_separator_items()
+    }
+
+    /// Get the menu bar [`HMENU`] of this menu, the handle [`Menu::init_for_hwnd`] passes to `SetMenu`.
+    ///
+    /// Windows shows the same items differently in a menu bar and in a popup menu, so a menu keeps
+    /// two handles with the same items: use this one for APIs that work on the menu bar of a window,
+    /// like `SetMenu` or `DrawMenuBar`, and [`ContextMenu::hpopupmenu`] for APIs that show a popup,
+    /// like `TrackPopupMenu`.
+    ///
+    /// The returned [`HMENU`] is valid as long as the `Menu` is.
+    ///
+    /// [`HMENU`]: windows_sys::Win32::UI::WindowsAndMessaging::HMENU
+    pub fn hmenu_bar(&self) -> isize {
+        self.inner.borrow().hmenu_bar()
+    }
+
     /// Adds this menu to a win32 window.
     ///
+    /// Returns [`Error::WrongThread`](crate::Error::WrongThread) if `hwnd` or this menu were
+    /// created by another thread, since a window can only be subclassed from the thread that created it.
+    ///
     /// # Safety
     ///
     /// The `hwnd` must be a valid window HWND.
@@ -153,6 +358,21 @@ impl Menu {
         self.inner.borrow_mut().init_for_hwnd(hwnd)
     }
 
+    /// Same as [`Menu::init_for_hwnd`] but for a window of `winit`, `tao` or any other
+    /// windowing library implementing [`HasWindowHandle`](raw_window_handle::HasWindowHandle).
+    ///
+    /// Returns [`Error::NotAWin32Window`](crate::Error::NotAWin32Window) if the window
+    /// handle isn't a Win32 one.
+    #[cfg(feature = "raw-window-handle")]
+    pub fn init_for_window(
+        &self,
+        window: &impl raw_window_handle::HasWindowHandle,
+    ) -> crate::Result<()> {
+        let hwnd = crate::util::hwnd_of(window)?;
+        // the handle is valid while `window` is borrowed
+        unsafe { self.init_for_hwnd(hwnd) }
+    }
+
     /// Adds this menu to a win32 window using the specified theme.
     ///
     /// See [Menu::init_for_hwnd] for more info.
@@ -172,6 +392,34 @@ impl Menu {
             .init_for_hwnd_with_theme(hwnd, theme)
     }
 
+    /// Sets this menu as the default menu of a window class, so every top-level window
+    /// of that class created afterwards on this thread gets this menu, just as if
+    /// [`Menu::init_for_hwnd`] was called on it.
+    ///
+    /// `class_atom` is the atom returned from
+    /// [`RegisterClassExW`](windows_sys::Win32::UI::WindowsAndMessaging::RegisterClassExW).
+    ///
+    /// ## Notes
+    ///
+    /// - Win32 can only associate a menu resource with a window class (via `lpszMenuName`),
+    ///   so this installs a thread-local `WH_CBT` hook that sets the menu on windows of that class
+    ///   while they are being created. Only windows created on the calling thread are affected.
+    /// - Child windows and windows created with their own menu are left untouched.
+    /// - Windows that already exist are not affected, use [`Menu::init_for_hwnd`] for those.
+    /// - Each class can only have one default menu, calling this again for the same class replaces it.
+    ///
+    /// Returns [`Error::InvalidWindowClass`](crate::Error::InvalidWindowClass) if `class_atom` is `0`.
+    pub fn set_as_class_default(&self, class_atom: u16) -> crate::Result<()> {
+        crate::platform_impl::Menu::set_as_class_default(&self.inner, class_atom)
+    }
+
+    /// Stops setting this menu on new windows of the window class, see [`Menu::set_as_class_default`].
+    ///
+    /// Returns `false` if this menu isn't the default menu of that class.
+    pub fn remove_as_class_default(&self, class_atom: u16) -> bool {
+        crate::platform_impl::Menu::remove_as_class_default(&self.inner, class_atom)
+    }
+
     /// Set a theme for the menu bar on this window.
     ///
     /// Note that the theme only affects the menu bar itself and not submenus or context menu.
@@ -183,6 +431,93 @@ impl Menu {
         self.inner.borrow().set_theme_for_hwnd(hwnd, theme)
     }
 
+    /// Returns the id of the item currently highlighted in the menu open on a win32 window,
+    /// as reported by `WM_MENUSELECT`, including submenus.
+    ///
+    /// Returns `None` when no menu is open, when nothing (or a separator) is highlighted,
+    /// or when this menu wasn't initialized for `hwnd`.
+    pub fn highlighted_item_for_hwnd(&self, hwnd: isize) -> Option<MenuId> {
+        self.inner.borrow().highlighted_item_for_hwnd(hwnd)
+    }
+
+    /// Grays or re-enables the Close item in the system menu of a win32 window,
+    /// for example to prevent closing the window while there are unsaved changes.
+    ///
+    /// Windows keeps the close (X) button of the caption in sync with that item,
+    /// so it is disabled as well and the frame is redrawn to reflect it.
+    ///
+    /// Returns [`Error::NoSystemMenu`](crate::Error::NoSystemMenu) if the window has no
+    /// system menu or its system menu has no Close item.
+    ///
+    /// # Safety
+    ///
+    /// The `hwnd` must be a valid window HWND.
+    pub unsafe fn disable_system_close_for_hwnd(
+        &self,
+        hwnd: isize,
+        disabled: bool,
+    ) -> crate::Result<()> {
+        self.inner
+            .borrow()
+            .disable_system_close_for_hwnd(hwnd, disabled)
+    }
+
+    /// Translates the accelerators of this menu for a win32 window and its child windows
+    /// without calling [`TranslateAcceleratorW`](windows_sys::Win32::UI::WindowsAndMessaging::TranslateAcceleratorW)
+    /// in the message loop, for loops owned by a library like `winit` or `tao`.
+    ///
+    /// A thread-local `WH_GETMESSAGE` hook translates the keyboard messages of the window
+    /// as they are removed from the queue, so the window must belong to the calling thread.
+    /// Translated messages are replaced by `WM_NULL` and the [`MenuEvent`] of the item is sent.
+    /// It stops when this menu is dropped or removed from the window, or with
+    /// [`Menu::disable_accelerators_for_hwnd`].
+    ///
+    /// Returns [`Error::NotInitialized`](crate::Error::NotInitialized) if this menu wasn't
+    /// initialized for `hwnd` with [`Menu::init_for_hwnd`].
+    ///
+    /// # Safety
+    ///
+    /// The `hwnd` must be a valid window HWND.
+    pub unsafe fn enable_accelerators_for_hwnd(&self, hwnd: isize) -> crate::Result<()> {
+        crate::platform_impl::Menu::enable_accelerators_for_hwnd(&self.inner, hwnd)
+    }
+
+    /// Same as [`Menu::enable_accelerators_for_hwnd`] but for a window implementing
+    /// [`HasWindowHandle`](raw_window_handle::HasWindowHandle), see [`Menu::init_for_window`].
+    #[cfg(feature = "raw-window-handle")]
+    pub fn enable_accelerators_for_window(
+        &self,
+        window: &impl raw_window_handle::HasWindowHandle,
+    ) -> crate::Result<()> {
+        let hwnd = crate::util::hwnd_of(window)?;
+        unsafe { self.enable_accelerators_for_hwnd(hwnd) }
+    }
+
+    /// Same as [`Menu::enable_accelerators_for_hwnd`] but for a `winit` window.
+    ///
+    /// ```no_run
+    /// # fn example(window: &winit::window::Window) {
+    /// let menu = muda_win::Menu::new();
+    /// menu.init_for_window(window).unwrap();
+    /// menu.enable_accelerators_for_winit(window).unwrap();
+    /// # }
+    /// ```
+    #[cfg(feature = "winit")]
+    pub fn enable_accelerators_for_winit(
+        &self,
+        window: &winit::window::Window,
+    ) -> crate::Result<()> {
+        self.enable_accelerators_for_window(window)
+    }
+
+    /// Stops translating the accelerators of this menu for a win32 window,
+    /// see [`Menu::enable_accelerators_for_hwnd`].
+    ///
+    /// Returns `false` if they weren't translated.
+    pub fn disable_accelerators_for_hwnd(&self, hwnd: isize) -> bool {
+        self.inner.borrow().disable_accelerators_for_hwnd(hwnd)
+    }
+
     /// Returns The [`HACCEL`](windows_sys::Win32::UI::WindowsAndMessaging::HACCEL) associated with this menu
     /// It can be used with [`TranslateAcceleratorW`](windows_sys::Win32::UI::WindowsAndMessaging::TranslateAcceleratorW)
     /// in the event loop to enable accelerators
@@ -192,69 +527,3230 @@ impl Menu {
         self.inner.borrow_mut().haccel()
     }
 
-    /// Removes this menu from a win32 window
+    /// Returns the id, label and accelerator of every item in this menu that has an accelerator,
+    /// including the items of nested submenus, in menu order.
     ///
-    /// # Safety
+    /// This is useful to render a "keyboard shortcuts" help sheet, even when the menu bar
+    /// isn't visible. Mnemonic markers (`&`) are removed from the labels.
+    pub fn shortcut_hints(&self) -> Vec<(MenuId, String, Accelerator)> {
+        self.inner.borrow().shortcut_hints()
+    }
+
+    /// Returns the accelerators assigned to more than one item of this menu and its nested
+    /// submenus, with the ids of these items, in menu order.
     ///
-    /// The `hwnd` must be a valid window HWND.
-    pub unsafe fn remove_for_hwnd(&self, hwnd: isize) -> crate::Result<()> {
-        self.inner.borrow_mut().remove_for_hwnd(hwnd)
+    /// Windows only activates one of the items sharing an accelerator, so this is useful to
+    /// assert in tests that a menu has no ambiguous shortcuts. Accelerators with the same
+    /// modifiers and key conflict, however they were created.
+    pub fn accelerator_conflicts(&self) -> Vec<(Accelerator, Vec<MenuId>)> {
+        self.inner.borrow().accelerator_conflicts()
     }
 
-    /// Hides this menu from a win32 window
+    /// Returns the id and accelerator of every item of this menu and its nested submenus whose
+    /// accelerator is one of `reserved`, in menu order.
     ///
-    /// # Safety
+    /// This is useful to warn about shortcuts that shadow the ones of a host application
+    /// or of the system, like `Ctrl+W` in a browser. Accelerators with the same modifiers
+    /// and key match, however they were created.
+    pub fn accelerators_shadowing(&self, reserved: &[Accelerator]) -> Vec<(MenuId, Accelerator)> {
+        self.inner.borrow().accelerators_shadowing(reserved)
+    }
+
+    /// Returns the id of the item that the key chord `vk` with `modifiers` would activate,
+    /// looked up in the accelerator table of this menu without a window or a message loop,
+    /// or `None` if no enabled item has this accelerator.
     ///
-    /// The `hwnd` must be a valid window HWND.
-    pub unsafe fn hide_for_hwnd(&self, hwnd: isize) -> crate::Result<()> {
-        self.inner.borrow().hide_for_hwnd(hwnd)
+    /// `vk` is a Windows virtual-key code, for example `VK_S` (`0x53`) for the S key.
+    /// This is useful to test the shortcuts of an app, or to handle them in integrations
+    /// that don't go through `TranslateAcceleratorW`.
+    pub fn translate_accelerator(
+        &self,
+        vk: u32,
+        modifiers: crate::accelerator::Modifiers,
+    ) -> Option<MenuId> {
+        self.inner.borrow().translate_accelerator(vk, modifiers)
     }
 
-    /// Shows this menu on a win32 window
+    /// Returns a report of the state of this menu for bug reports, with the number of items,
+    /// the windows it was added to, its accelerator table, and the ids and accelerators
+    /// shared by several items, which are usually mistakes.
+    pub fn diagnostics(&self) -> MenuDiagnostics {
+        self.inner.borrow().diagnostics()
+    }
+
+    /// Returns the accelerators of this menu and its nested submenus as plain text,
+    /// one `id = accelerator` line per item, for example `save = Ctrl+S`.
     ///
-    /// # Safety
+    /// The result can be edited by users and applied with [`Menu::import_accelerators`].
+    pub fn export_accelerators(&self) -> String {
+        crate::keymap::export(self)
+    }
+
+    /// Applies a keymap in the format of [`Menu::export_accelerators`] to the items of this menu
+    /// and its nested submenus, all the items with a matching id are updated.
     ///
-    /// The `hwnd` must be a valid window HWND.
-    pub unsafe fn show_for_hwnd(&self, hwnd: isize) -> crate::Result<()> {
-        self.inner.borrow().show_for_hwnd(hwnd)
+    /// Empty lines and lines starting with `#` are ignored, and an empty accelerator
+    /// (`save =`) removes the accelerator of the item. Ids containing `=` are not supported.
+    ///
+    /// Nothing is applied if any line is invalid, refers to an unknown id or has an accelerator
+    /// that can't be registered, [`Error::InvalidKeymapLine`](crate::Error::InvalidKeymapLine)
+    /// is returned with the number of the first such line instead.
+    pub fn import_accelerators(&self, keymap: &str) -> crate::Result<()> {
+        crate::keymap::import(self, keymap)
     }
 
-    /// Returns whether this menu visible on a on a win32 window
+    /// Gives the items of this menu and its nested submenus that have no accelerator
+    /// the conventional accelerator of their command, for apps with items named after
+    /// common commands.
     ///
-    /// # Safety
+    /// The command of an item is its id, or its label if the id isn't a known command,
+    /// compared case-insensitively and ignoring mnemonics, a trailing `...` and `_` or `-`
+    /// in place of spaces, so `save_as` and `Save &As...` are both "Save As".
     ///
-    /// The `hwnd` must be a valid window HWND.
-    pub unsafe fn is_visible_on_hwnd(&self, hwnd: isize) -> bool {
-        self.inner.borrow().is_visible_on_hwnd(hwnd)
+    /// | Command    | Accelerator  |
+    /// |------------|--------------|
+    /// | New        | Ctrl+N       |
+    /// | Open       | Ctrl+O       |
+    /// | Save       | Ctrl+S       |
+    /// | Save As    | Ctrl+Shift+S |
+    /// | Print      | Ctrl+P       |
+    /// | Close      | Ctrl+W       |
+    /// | Undo       | Ctrl+Z       |
+    /// | Redo       | Ctrl+Y       |
+    /// | Cut        | Ctrl+X       |
+    /// | Copy       | Ctrl+C       |
+    /// | Paste      | Ctrl+V       |
+    /// | Delete     | Delete       |
+    /// | Select All | Ctrl+A       |
+    /// | Find       | Ctrl+F       |
+    /// | Find Next  | F3           |
+    /// | Replace    | Ctrl+H       |
+    /// | Refresh    | F5           |
+    /// | Help       | F1           |
+    ///
+    /// Items that already have an accelerator, predefined items, and items whose conventional
+    /// accelerator is already used by another item of this menu are left untouched.
+    pub fn apply_standard_accelerators(&self) -> crate::Result<()> {
+        crate::keymap::apply_standard(self)
     }
-}
 
-impl ContextMenu for Menu {
-    fn hpopupmenu(&self) -> isize {
-        self.inner.borrow().hpopupmenu()
+    /// Describes this menu and its nested submenus as JSON, for external tooling
+    /// like designers, documentation generators or test fixtures.
+    ///
+    /// The root is an object with the `id` of this menu and its `items`, each item is an object with:
+    ///
+    /// - `kind`: one of `"item"`, `"check"`, `"icon"`, `"submenu"` or `"predefined"`.
+    /// - `id`: the id of the item, omitted for predefined items since their ids are generated.
+    ///   Items read without an id get a generated one.
+    /// - `predefined`: for predefined items, the name of their constructor, for example `"separator"` or `"copy"`.
+    /// - `text`: the label, including mnemonic markers (`&`).
+    /// - `enabled`: whether the item is enabled, `true` when omitted.
+    /// - `checked`: for check items, whether the item is checked.
+    /// - `accelerator`: the accelerator in the format of [`Menu::export_accelerators`], if any.
+    /// - `children`: for submenus, their items.
+    ///
+    /// Icons, separator styles and about metadata are not included.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        crate::json::to_json(self)
     }
 
-    unsafe fn show_context_menu_for_hwnd(&self, hwnd: isize, position: Option<Position>) -> bool {
+    /// Describes the logical structure of this menu and its nested submenus: the ids, text,
+    /// enabled and checked states and accelerators of the items, without native handles.
+    ///
+    /// The descriptor can be stored with any serde format and the menu rebuilt with
+    /// [`Menu::from_descriptor`], keeping the ids of the items. It serializes to the format of
+    /// [`Menu::to_json`], and has the same limitations.
+    #[cfg(feature = "serde")]
+    pub fn to_descriptor(&self) -> crate::MenuDescriptor {
+        crate::json::to_descriptor(self)
+    }
+
+    /// Builds a new menu from a descriptor made by [`Menu::to_descriptor`].
+    ///
+    /// Returns [`Error::InvalidJson`](crate::Error::InvalidJson) if a predefined item is unknown,
+    /// or [`Error::AcceleratorParseError`](crate::Error::AcceleratorParseError) if an accelerator
+    /// can't be parsed.
+    #[cfg(feature = "serde")]
+    pub fn from_descriptor(descriptor: &crate::MenuDescriptor) -> crate::Result<Menu> {
+        crate::json::from_descriptor(descriptor)
+    }
+
+    /// Builds a new menu from JSON in the format of [`Menu::to_json`].
+    ///
+    /// Returns [`Error::InvalidJson`](crate::Error::InvalidJson) if `json` doesn't match the format.
+    #[cfg(feature = "serde")]
+    pub fn from_json(json: &str) -> crate::Result<Menu> {
+        crate::json::from_json(json)
+    }
+
+    /// Applies `updates` to the items with their ids, searching nested submenus too like
+    /// [`Menu::find_item_by_id`], for example to apply a view model in one call. The windows
+    /// of this menu are only redrawn once all the updates are applied.
+    ///
+    /// Fields of an [`ItemUpdate`] that are `None` are left as they are, and `checked` only
+    /// applies to [`CheckMenuItem`](crate::CheckMenuItem)s. Returns the ids that don't match
+    /// any item, in order, their updates are skipped.
+    ///
+    /// Returns an error if an accelerator can't be registered, the updates before it are kept.
+    pub fn update_items<I: IntoIterator<Item = (MenuId, ItemUpdate)>>(
+        &self,
+        updates: I,
+    ) -> crate::Result<Vec<MenuId>> {
+        let _update = self.begin_update();
+        self.apply_updates(updates)
+    }
+
+    fn apply_updates<I: IntoIterator<Item = (MenuId, ItemUpdate)>>(
+        &self,
+        updates: I,
+    ) -> crate::Result<Vec<MenuId>> {
+        let mut unknown = Vec::new();
+        for (id, update) in updates {
+            let Some(item) = self.find_item_by_id(&id) else {
+                unknown.push(id);
+                continue;
+            };
+
+            if let Some(text) = &update.text {
+                let change = item.child_mut().set_text(text);
+                change.notify();
+            }
+            if let Some(enabled) = update.enabled {
+                let change = item.child_mut().set_enabled(enabled);
+                change.notify();
+            }
+            if let (Some(checked), MenuItemKind::Check(check)) = (update.checked, &item) {
+                // unchecks the other items of its radio group
+                check.set_checked(checked);
+            }
+            if let Some(accelerator) = update.accelerator {
+                item.child_mut().set_accelerator(accelerator)?;
+            }
+        }
+        Ok(unknown)
+    }
+
+    /// Returns the first item with `id`, searching nested submenus too, depth-first.
+    ///
+    /// Useful to get back the item of a [`MenuEvent`](crate::MenuEvent), for example to toggle
+    /// a [`CheckMenuItem`](crate::CheckMenuItem), without keeping its handle around.
+    pub fn find_item_by_id(&self, id: &MenuId) -> Option<MenuItemKind> {
+        self.inner.borrow().find_item_by_id(id)
+    }
+
+    /// Enables or disables the first item with `id`, searching nested submenus too, like
+    /// [`Menu::find_item_by_id`], without keeping its handle around.
+    ///
+    /// Returns [`Error::NotAChildOfThisMenu`](crate::Error::NotAChildOfThisMenu) if there is no item with `id`.
+    pub fn set_enabled_by_id(&self, id: &MenuId, enabled: bool) -> crate::Result<()> {
+        let item = self
+            .find_item_by_id(id)
+            .ok_or(crate::Error::NotAChildOfThisMenu)?;
+        let change = item.child_mut().set_enabled(enabled);
+        change.notify();
+        Ok(())
+    }
+
+    /// Returns the first submenu, searching nested submenus too, whose label is `text`.
+    ///
+    /// Mnemonic markers (`&`) are ignored on both sides, so `"Tools"` finds a submenu labeled `"&Tools"`.
+    /// This is useful for plugins that contribute items to a well-known submenu without knowing its id.
+    pub fn find_submenu_by_text(&self, text: &str) -> Option<Submenu> {
+        self.inner.borrow().find_submenu_by_text(text, false)
+    }
+
+    /// Same as [`Menu::find_submenu_by_text`] but compares the labels case-insensitively.
+    pub fn find_submenu_by_text_ignore_case(&self, text: &str) -> Option<Submenu> {
+        self.inner.borrow().find_submenu_by_text(text, true)
+    }
+
+    /// Adds a function called with the new [`Menu::haccel`] whenever the accelerator table of
+    /// this menu is rebuilt, which happens every time an accelerator is added, changed or removed.
+    ///
+    /// The previous table is destroyed on rebuild, so event loops that keep the handle
+    /// around should refresh it here instead of using a stale handle.
+    ///
+    /// `f` may run while this menu is borrowed, so it must not access the menu, use the given handle instead.
+    pub fn on_haccel_changed<F: Fn(isize) + 'static>(&self, f: F) {
+        self.inner.borrow().on_haccel_changed(Rc::new(f))
+    }
+
+    /// Adds a function called with the [`MenuEvent`] of the item with `id` whenever
+    /// it is activated, from this menu or any of its nested submenus.
+    ///
+    /// This complements the handler set by [`MenuEvent::set_event_handler`], which still
+    /// receives every event, and saves matching on the id of every event.
+    ///
+    /// `f` may run while this menu is borrowed, so it must not access the menu, its items can be used.
+    pub fn on_item<F: Fn(&MenuEvent) + 'static>(&self, id: &MenuId, f: F) {
+        self.inner.borrow().on_item(id.clone(), Rc::new(f))
+    }
+
+    /// Set a function that gets the first chance to handle the accelerators of this menu.
+    ///
+    /// It is called with the triggered [`Accelerator`] before it is turned into a menu command,
+    /// returning `true` consumes the accelerator so the menu item isn't activated
+    /// and no [`MenuEvent`](crate::MenuEvent) is emitted, returning `false` lets it proceed normally.
+    ///
+    /// Use `None` to remove a previously set interceptor.
+    pub fn set_accelerator_interceptor<F: Fn(&Accelerator) -> bool + 'static>(
+        &self,
+        interceptor: Option<F>,
+    ) {
         self.inner
             .borrow_mut()
-            .show_context_menu_for_hwnd(hwnd, position)
+            .set_accelerator_interceptor(interceptor.map(|f| Rc::new(f) as _))
     }
 
-    unsafe fn attach_menu_subclass_for_hwnd(&self, hwnd: isize) {
-        self.inner.borrow().attach_menu_subclass_for_hwnd(hwnd)
+    /// Sets the minimum height, in physical pixels, of the items shown in the popups of this menu
+    /// (its submenus and its context menu), for example to make them touch-friendly. Use `0` to reset it.
+    ///
+    /// Native menu items are always measured using the system metrics, so a non-zero minimum height
+    /// converts every item of the popups, except separators, to an owner-drawn item.
+    /// Items of the menu bar itself are not affected.
+    pub fn set_item_min_height(&self, px: u32) {
+        self.inner.borrow().set_item_min_height(px)
     }
 
-    unsafe fn detach_menu_subclass_from_hwnd(&self, hwnd: isize) {
-        self.inner.borrow().detach_menu_subclass_from_hwnd(hwnd)
+    /// Limits the height, in physical pixels, of the popups of this menu (its submenus and its
+    /// context menu), items that don't fit are reached with scroll arrows. Use `None` to remove the limit.
+    ///
+    /// Popups taller than the screen already get scroll arrows from the system,
+    /// this only makes them scroll sooner, a limit taller than the screen has no effect.
+    /// The menu bar itself is not affected.
+    pub fn set_max_popup_height(&self, height: Option<u32>) {
+        self.inner
+            .borrow()
+            .set_max_popup_height(height.unwrap_or(0))
     }
-}
 
-/// The window menu bar theme
-#[repr(usize)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-pub enum MenuTheme {
-    Dark = 0,
-    Light = 1,
-    Auto = 2,
+    /// Draws the context menu of this menu off-screen, for documentation screenshots
+    /// or visual tests, and returns its pixels as RGBA rows from top to bottom with the width and height.
+    ///
+    /// Every item is drawn the way owner-drawn items are, with the colors of `theme`.
+    /// The layout uses the system metrics, so for a `dpi` other than the system DPI
+    /// the image is scaled. A `dpi` of `0` uses the system DPI.
+    pub fn render_to_bitmap(
+        &self,
+        theme: MenuTheme,
+        dpi: u32,
+    ) -> crate::Result<(Vec<u8>, u32, u32)> {
+        self.inner.borrow().render_to_bitmap(theme, dpi)
+    }
+
+    /// A counter incremented by any change to this menu or its nested submenus, structural
+    /// (items appended, inserted or removed) or to the state of an item (text, enabled,
+    /// checked, accelerator, icon... etc).
+    ///
+    /// Callers mirroring the menu can skip re-reading it while the generation is unchanged.
+    /// Only the value changing is meaningful, the number of increments per change is not.
+    pub fn generation(&self) -> u64 {
+        self.inner.borrow().generation()
+    }
+
+    /// A stream that yields whenever this menu changed, as reported by [`Menu::generation`],
+    /// for example to rebuild a mirrored view of the menu from a task.
+    ///
+    /// Changes are coalesced: all the changes made since the stream last yielded are reported once,
+    /// changes made before this function is called are not reported. The stream never ends.
+    #[cfg(feature = "async")]
+    pub fn changes(&self) -> impl futures_core::Stream<Item = ()> {
+        self.inner.borrow().changes()
+    }
+
+    /// Subscribes to state changes of every item in this menu, including the items of nested submenus,
+    /// for example to keep a toolbar in sync with the menu.
+    ///
+    /// `subscriber` is called with the id of the item and its new [`ItemState`] whenever
+    /// the text, the enabled state or the checked state of an item changes,
+    /// including check items toggled by the user.
+    ///
+    /// The changed item is borrowed while `subscriber` runs, so it must not access that item.
+    pub fn subscribe_state<F: Fn(&MenuId, ItemState) + 'static>(
+        &self,
+        subscriber: F,
+    ) -> SubscriptionId {
+        self.inner.borrow().subscribe_state(Rc::new(subscriber))
+    }
+
+    /// Removes a subscriber added with [`Menu::subscribe_state`], returns `false` if it was already removed.
+    pub fn unsubscribe_state(&self, id: SubscriptionId) -> bool {
+        self.inner.borrow().unsubscribe_state(id)
+    }
+
+    /// Whether this menu is read-only, see [`Menu::set_read_only`].
+    pub fn is_read_only(&self) -> bool {
+        self.inner.borrow().is_read_only()
+    }
+
+    /// Makes this menu purely informational, for example a status dropdown.
+    ///
+    /// Items of a read-only menu keep their enabled look and can still be highlighted,
+    /// but activating them, including through their accelerators or from the context menu,
+    /// does nothing and emits no [`MenuEvent`](crate::MenuEvent).
+    pub fn set_read_only(&self, read_only: bool) {
+        self.inner.borrow_mut().set_read_only(read_only)
+    }
+
+    /// Whether pressing the accelerator of a disabled item beeps, see [`Menu::set_beep_on_disabled`].
+    pub fn beeps_on_disabled(&self) -> bool {
+        self.inner.borrow().beeps_on_disabled()
+    }
+
+    /// Sets whether pressing the accelerator of a disabled item plays the default system beep,
+    /// `true` by default. Either way the item isn't activated and no [`MenuEvent`] is emitted.
+    ///
+    /// This only covers the accelerators of this menu handled by its subclass,
+    /// beeps from other menus or windows, like unmatched mnemonics, are left to the system.
+    pub fn set_beep_on_disabled(&self, beep: bool) {
+        self.inner.borrow_mut().set_beep_on_disabled(beep)
+    }
+
+    /// Whether keyboard navigation wraps around the popups of this menu, see [`Menu::set_nav_wrap`].
+    pub fn nav_wraps(&self) -> bool {
+        self.inner.borrow().nav_wraps()
+    }
+
+    /// Whether this menu is currently shown, from the moment its menu bar or its context menu
+    /// opens until it closes, for example to pause animations while the modal menu loop runs.
+    ///
+    /// The menu bar is only seen opening through the menu subclass, see [`Menu::init_for_hwnd`].
+    pub fn is_tracking(&self) -> bool {
+        self.tracking.get() > 0
+    }
+
+    /// Sets whether pressing Up on the first item or Down on the last item of the popups
+    /// of this menu (its submenus and its context menu) moves to the other end, `true`
+    /// by default like other Windows menus. When `false`, the highlight stays on the item.
+    ///
+    /// The keys are intercepted while the menu is shown, which requires the menu subclass,
+    /// see [`Menu::init_for_hwnd`] and [`ContextMenu::attach_menu_subclass_for_hwnd`].
+    pub fn set_nav_wrap(&self, wrap: bool) {
+        self.inner.borrow_mut().set_nav_wrap(wrap)
+    }
+
+    /// Whether pressing Alt or F10 activates the menu bar, see [`Menu::set_alt_activates_bar`].
+    pub fn alt_activates_bar(&self) -> bool {
+        self.inner.borrow().alt_activates_bar()
+    }
+
+    /// Sets whether pressing Alt or F10, alone or with the mnemonic of a menu, activates the menu
+    /// bar, `true` by default. Games and full-screen apps can disable it so these keys don't
+    /// enter the modal menu loop and pause their input, Alt+Space still opens the window menu.
+    ///
+    /// Accelerators keep working. This only affects the windows this menu is attached to with
+    /// [`Menu::init_for_hwnd`], through the menu subclass, where the menu bar can still be
+    /// opened with the mouse.
+    pub fn set_alt_activates_bar(&self, activates: bool) {
+        self.inner.borrow_mut().set_alt_activates_bar(activates)
+    }
+
+    /// Set a function that sees every raw message received by the menu subclass of `hwnd`
+    /// before the menu handles it, for interop with windows that need unusual message handling.
+    ///
+    /// The hook is called with the message, `wparam` and `lparam`, returning `Some(lresult)`
+    /// returns `lresult` from the window procedure and skips any handling by the menu
+    /// (including `WM_COMMAND`, so no [`MenuEvent`](crate::MenuEvent) is emitted for it),
+    /// returning `None` lets the menu handle the message as usual.
+    ///
+    /// Only one hook can be set per window, setting another one replaces it.
+    /// The hook is removed when this menu is removed from the window with [`Menu::remove_for_hwnd`].
+    ///
+    /// ## Reentrancy
+    ///
+    /// The hook runs inside the window procedure, so it must not block and any message it
+    /// sends synchronously (`SendMessageW` to `hwnd` for example) re-enters the hook.
+    /// It may replace or remove itself while running, the current call completes regardless.
+    pub fn set_raw_message_hook_for_hwnd<F: Fn(u32, usize, isize) -> Option<isize> + 'static>(
+        &self,
+        hwnd: isize,
+        hook: F,
+    ) {
+        self.inner
+            .borrow_mut()
+            .set_raw_message_hook_for_hwnd(hwnd, Some(Rc::new(hook)))
+    }
+
+    /// Removes the raw message hook of `hwnd` set by [`Menu::set_raw_message_hook_for_hwnd`].
+    pub fn remove_raw_message_hook_for_hwnd(&self, hwnd: isize) {
+        self.inner
+            .borrow_mut()
+            .set_raw_message_hook_for_hwnd(hwnd, None)
+    }
+
+    /// Processes a menu related message (`WM_COMMAND`, `WM_MENUSELECT`, `WM_MEASUREITEM`...
+    /// etc) sent to `hwnd`, emitting [`MenuEvent`](crate::MenuEvent)s like the subclass installed by
+    /// [`Menu::init_for_hwnd`] does.
+    ///
+    /// This is meant for windows whose messages are dispatched by a custom loop,
+    /// where installing the subclass isn't possible. Returns `Some` with the result of the
+    /// message if it was handled, otherwise the message still needs default processing.
+    ///
+    /// # Safety
+    ///
+    /// The `hwnd` must be a valid window HWND and `wparam` and `lparam` must be valid for `msg`.
+    pub unsafe fn handle_message(
+        &self,
+        hwnd: isize,
+        msg: u32,
+        wparam: usize,
+        lparam: isize,
+    ) -> Option<isize> {
+        // no borrow is held so event handlers can use the menu, like within the subclass
+        crate::platform_impl::Menu::handle_message(self.inner.as_ptr(), hwnd, msg, wparam, lparam)
+    }
+
+    /// Removes this menu from a win32 window
+    ///
+    /// # Safety
+    ///
+    /// The `hwnd` must be a valid window HWND.
+    pub unsafe fn remove_for_hwnd(&self, hwnd: isize) -> crate::Result<()> {
+        self.inner.borrow_mut().remove_for_hwnd(hwnd)
+    }
+
+    /// Same as [`Menu::remove_for_hwnd`] but for a window implementing
+    /// [`HasWindowHandle`](raw_window_handle::HasWindowHandle), see [`Menu::init_for_window`].
+    #[cfg(feature = "raw-window-handle")]
+    pub fn remove_for_window(
+        &self,
+        window: &impl raw_window_handle::HasWindowHandle,
+    ) -> crate::Result<()> {
+        let hwnd = crate::util::hwnd_of(window)?;
+        unsafe { self.remove_for_hwnd(hwnd) }
+    }
+
+    /// Hides this menu from a win32 window
+    ///
+    /// # Safety
+    ///
+    /// The `hwnd` must be a valid window HWND.
+    pub unsafe fn hide_for_hwnd(&self, hwnd: isize) -> crate::Result<()> {
+        self.inner.borrow_mut().hide_for_hwnd(hwnd)
+    }
+
+    /// Shows this menu on a win32 window
+    ///
+    /// # Safety
+    ///
+    /// The `hwnd` must be a valid window HWND.
+    pub unsafe fn show_for_hwnd(&self, hwnd: isize) -> crate::Result<()> {
+        self.inner.borrow_mut().show_for_hwnd(hwnd)
+    }
+
+    /// Shows or hides the menu bar of a win32 window, keeping the menu attached to it.
+    ///
+    /// Unlike [`Menu::hide_for_hwnd`], a bar hidden this way is revealed while the menu is
+    /// activated with the keyboard (pressing Alt or F10) and hidden again when it closes.
+    /// In both cases accelerators keep working, as long as [`Menu::haccel`] is still used
+    /// in the event loop.
+    ///
+    /// # Safety
+    ///
+    /// The `hwnd` must be a valid window HWND.
+    pub unsafe fn set_bar_visible_for_hwnd(&self, hwnd: isize, visible: bool) -> crate::Result<()> {
+        self.inner
+            .borrow_mut()
+            .set_bar_visible_for_hwnd(hwnd, visible)
+    }
+
+    /// Returns whether this menu visible on a on a win32 window
+    ///
+    /// # Safety
+    ///
+    /// The `hwnd` must be a valid window HWND.
+    pub unsafe fn is_visible_on_hwnd(&self, hwnd: isize) -> bool {
+        self.inner.borrow().is_visible_on_hwnd(hwnd)
+    }
+
+    /// Shows this menu as a context menu inside a win32 window, at `x` and `y` in physical
+    /// pixels relative to the top-left corner of the client area, the coordinates of mouse
+    /// messages like `WM_RBUTTONUP` for example.
+    ///
+    /// This is [`ContextMenu::show_context_menu_for_hwnd`] with a [`PhysicalPosition`](crate::dpi::PhysicalPosition),
+    /// use it with a [`LogicalPosition`](crate::dpi::LogicalPosition) for coordinates scaled by the DPI of the window.
+    ///
+    /// Returns `true` if menu tracking ended because an item was selected, and `false` if menu tracking was cancelled for any reason.
+    ///
+    /// # Safety
+    ///
+    /// The `hwnd` must be a valid window HWND.
+    pub unsafe fn show_context_menu_for_hwnd_at(&self, hwnd: isize, x: i32, y: i32) -> bool {
+        let position = crate::dpi::PhysicalPosition::new(x, y);
+        self.show_context_menu_for_hwnd(hwnd, Some(position.into()))
+    }
+}
+
+impl ContextMenu for Menu {
+    fn hpopupmenu(&self) -> isize {
+        self.inner.borrow().hpopupmenu()
+    }
+
+    unsafe fn show_context_menu_for_hwnd(&self, hwnd: isize, position: Option<Position>) -> bool {
+        self.inner
+            .borrow_mut()
+            .show_context_menu_for_hwnd(hwnd, position)
+    }
+
+    unsafe fn show_context_menu_at_screen_pos(&self, hwnd: isize, position: Position) -> bool {
+        self.inner
+            .borrow_mut()
+            .show_context_menu_at_screen_pos_for_hwnd(hwnd, position)
+    }
+
+    unsafe fn show_context_menu_for_hwnd_blocking(
+        &self,
+        hwnd: isize,
+        position: Option<Position>,
+    ) -> Option<MenuId> {
+        self.inner
+            .borrow_mut()
+            .show_context_menu_for_hwnd_blocking(hwnd, position)
+    }
+
+    unsafe fn show_context_menu_with_options_for_hwnd(
+        &self,
+        hwnd: isize,
+        options: ContextMenuOptions,
+    ) -> bool {
+        self.inner
+            .borrow_mut()
+            .show_context_menu_with_options_for_hwnd(hwnd, &options)
+    }
+
+    unsafe fn show_below_control(&self, hwnd: isize, position: Position, size: Size) -> bool {
+        self.inner
+            .borrow_mut()
+            .show_below_control_for_hwnd(hwnd, position, size)
+    }
+
+    unsafe fn attach_menu_subclass_for_hwnd(&self, hwnd: isize) {
+        self.inner.borrow().attach_menu_subclass_for_hwnd(hwnd)
+    }
+
+    unsafe fn detach_menu_subclass_from_hwnd(&self, hwnd: isize) {
+        self.inner.borrow().detach_menu_subclass_from_hwnd(hwnd)
+    }
+}
+
+/// A batch of changes to a [`Menu`], see [`Menu::begin_update`].
+#[must_use = "the batch ends as soon as the guard is dropped"]
+pub struct MenuUpdateGuard<'a> {
+    menu: &'a Menu,
+}
+
+impl Drop for MenuUpdateGuard<'_> {
+    fn drop(&mut self) {
+        self.menu.inner.borrow_mut().end_update();
+    }
+}
+
+/// The state of a menu item, reported to the subscribers of [`Menu::subscribe_state`].
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemState {
+    /// Whether the item is enabled.
+    pub enabled: bool,
+    /// Whether the item is checked, `None` for items that aren't [`CheckMenuItem`](crate::CheckMenuItem)s.
+    pub checked: Option<bool>,
+    /// The text of the item.
+    pub text: String,
+    /// Whether the item is visible, see [`MenuItem::set_visible`](crate::MenuItem::set_visible).
+    pub visible: bool,
+}
+
+/// Changes to the properties of an item, see [`Menu::update_items`].
+///
+/// `None` leaves a property as it is.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ItemUpdate {
+    /// The new text of the item.
+    pub text: Option<String>,
+    /// Whether the item is enabled.
+    pub enabled: Option<bool>,
+    /// Whether the item is checked, only applies to [`CheckMenuItem`](crate::CheckMenuItem)s.
+    pub checked: Option<bool>,
+    /// The new accelerator of the item, `Some(None)` removes it.
+    pub accelerator: Option<Option<Accelerator>>,
+}
+
+impl ItemUpdate {
+    /// An update that leaves all the properties as they are.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets [`ItemUpdate::text`].
+    pub fn with_text<S: Into<String>>(mut self, text: S) -> Self {
+        self.text = Some(text.into());
+        self
+    }
+
+    /// Sets [`ItemUpdate::enabled`].
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.enabled = Some(enabled);
+        self
+    }
+
+    /// Sets [`ItemUpdate::checked`].
+    pub fn with_checked(mut self, checked: bool) -> Self {
+        self.checked = Some(checked);
+        self
+    }
+
+    /// Sets [`ItemUpdate::accelerator`].
+    pub fn with_accelerator(mut self, accelerator: Option<Accelerator>) -> Self {
+        self.accelerator = Some(accelerator);
+        self
+    }
+}
+
+/// A report of the state of a menu for bug reports, see [`Menu::diagnostics`].
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MenuDiagnostics {
+    /// The number of items in the menu and its nested submenus, an item added
+    /// to several submenus is counted once.
+    pub item_count: usize,
+    /// The windows the menu was added to with [`Menu::init_for_hwnd`].
+    pub hwnds: Vec<isize>,
+    /// Whether an accelerator table is registered, see [`Menu::haccel`].
+    pub accelerators_registered: bool,
+    /// The number of entries in the accelerator table.
+    pub haccel_item_count: usize,
+    /// The ids shared by several items, each listed once.
+    pub duplicate_ids: Vec<MenuId>,
+    /// The accelerators shared by several items, see [`Menu::accelerator_conflicts`].
+    pub accelerator_conflicts: Vec<(Accelerator, Vec<MenuId>)>,
+}
+
+/// Identifies a subscription made with [`Menu::subscribe_state`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(pub(crate) u32);
+
+/// The window menu bar theme
+///
+/// When it is dark, the check items of the popups of the window are owner-drawn
+/// with light check marks and radio bullets, which the system draws dark otherwise.
+#[repr(usize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MenuTheme {
+    Dark = 0,
+    Light = 1,
+    Auto = 2,
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        accelerator::{Accelerator, Code, Modifiers},
+        Menu, MenuItem, MenuTheme,
+    };
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_intercepts_accelerators() {
+        let save = MenuItem::new(
+            "Save",
+            true,
+            Some(Accelerator::new(Some(Modifiers::CONTROL), Code::KeyS)),
+        );
+        let open = MenuItem::new(
+            "Open",
+            true,
+            Some(Accelerator::new(Some(Modifiers::CONTROL), Code::KeyO)),
+        );
+        let menu = Menu::with_items(&[&save, &open]).unwrap();
+
+        assert!(!menu
+            .inner
+            .borrow()
+            .intercepts_accelerator(save.accelerator()));
+
+        menu.set_accelerator_interceptor(Some(|a: &Accelerator| a.key() == Code::KeyS));
+
+        {
+            let inner = menu.inner.borrow();
+            assert!(inner.intercepts_accelerator(save.accelerator()));
+            assert!(!inner.intercepts_accelerator(open.accelerator()));
+        }
+
+        // the interceptor can change the item of the accelerator
+        let save_ = save.clone();
+        menu.set_accelerator_interceptor(Some(move |_: &Accelerator| {
+            save_.set_enabled(false);
+            save_.set_text("Saving...");
+            true
+        }));
+        let hwnd = create_window() as isize;
+        let id = save.inner.borrow().internal_id() as usize;
+        let handled = unsafe {
+            menu.handle_message(
+                hwnd,
+                windows_sys::Win32::UI::WindowsAndMessaging::WM_COMMAND,
+                (1 << 16) | id,
+                0,
+            )
+        };
+        assert_eq!(handled, Some(0));
+        assert!(!save.is_enabled());
+        assert_eq!(save.text(), "Saving...");
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_sets_menu_as_class_default() {
+        let menu = Menu::new();
+        let other = Menu::new();
+
+        assert!(matches!(
+            menu.set_as_class_default(0),
+            Err(crate::Error::InvalidWindowClass)
+        ));
+
+        menu.set_as_class_default(0xC001).unwrap();
+        assert!(!other.remove_as_class_default(0xC001));
+        assert!(menu.remove_as_class_default(0xC001));
+        assert!(!menu.remove_as_class_default(0xC001));
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_disables_system_close() {
+        use windows_sys::Win32::UI::WindowsAndMessaging::{
+            DestroyWindow, GetMenuState, GetSystemMenu, MF_BYCOMMAND, MF_GRAYED, SC_CLOSE,
+        };
+
+        let hwnd = create_window();
+
+        let is_grayed = || unsafe {
+            GetMenuState(GetSystemMenu(hwnd, 0), SC_CLOSE, MF_BYCOMMAND) & MF_GRAYED != 0
+        };
+
+        let menu = Menu::new();
+        unsafe { menu.disable_system_close_for_hwnd(hwnd as _, true) }.unwrap();
+        assert!(is_grayed());
+        unsafe { menu.disable_system_close_for_hwnd(hwnd as _, false) }.unwrap();
+        assert!(!is_grayed());
+
+        unsafe { DestroyWindow(hwnd) };
+    }
+
+    // Tracking needs an open menu, to check it interactively: init a menu for a window,
+    // poll `highlighted_item_for_hwnd` from a `WM_TIMER` while hovering the items of
+    // an open menu, it should follow the highlight and become `None` once the menu closes.
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn nothing_is_highlighted_by_default() {
+        let menu = Menu::new();
+        menu.append(&MenuItem::new("Item", true, None)).unwrap();
+        assert_eq!(menu.highlighted_item_for_hwnd(0), None);
+        assert_eq!(menu.highlighted_item_for_hwnd(1), None);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_applies_default_theme_on_init() {
+        use windows_sys::Win32::UI::WindowsAndMessaging::DestroyWindow;
+
+        let hwnd = create_window();
+        let other_hwnd = create_window();
+
+        let menu = Menu::with_theme(MenuTheme::Dark);
+        unsafe { menu.init_for_hwnd(hwnd as _) }.unwrap();
+        unsafe { menu.init_for_hwnd_with_theme(other_hwnd as _, MenuTheme::Light) }.unwrap();
+        assert_eq!(
+            menu.inner.borrow().theme_for_hwnd(hwnd as _),
+            Some(MenuTheme::Dark)
+        );
+        assert_eq!(
+            menu.inner.borrow().theme_for_hwnd(other_hwnd as _),
+            Some(MenuTheme::Light)
+        );
+
+        unsafe {
+            DestroyWindow(hwnd);
+            DestroyWindow(other_hwnd);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_notifies_state_subscribers_of_nested_items() {
+        use std::{cell::RefCell, rc::Rc};
+
+        use crate::{CheckMenuItem, ItemState, MenuId, Submenu};
+
+        let nested = CheckMenuItem::with_id("nested", "Nested", true, false, None);
+        let inner = Submenu::with_items("Inner", true, &[&nested]).unwrap();
+        let outer = Submenu::with_items("Outer", true, &[&inner]).unwrap();
+        let menu = Menu::with_items(&[&outer]).unwrap();
+
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let seen_c = seen.clone();
+        let subscription = menu.subscribe_state(move |id, state| {
+            seen_c.borrow_mut().push((id.clone(), state));
+        });
+
+        nested.set_enabled(false);
+        nested.set_enabled(false);
+        nested.set_checked(true);
+        assert_eq!(
+            *seen.borrow(),
+            vec![
+                (
+                    MenuId::new("nested"),
+                    ItemState {
+                        enabled: false,
+                        checked: Some(false),
+                        text: "Nested".to_string(),
+                        visible: true,
+                    }
+                ),
+                (
+                    MenuId::new("nested"),
+                    ItemState {
+                        enabled: false,
+                        checked: Some(true),
+                        text: "Nested".to_string(),
+                        visible: true,
+                    }
+                ),
+            ]
+        );
+
+        assert!(menu.unsubscribe_state(subscription));
+        assert!(!menu.unsubscribe_state(subscription));
+        nested.set_text("Renamed");
+        assert_eq!(seen.borrow().len(), 2);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_lets_state_subscribers_read_the_item() {
+        use std::{cell::RefCell, rc::Rc};
+
+        use crate::{CheckMenuItem, MenuItemKind};
+
+        let item = CheckMenuItem::with_id("item", "Item", true, false, None);
+        let menu = Menu::with_items(&[&item]).unwrap();
+
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let (seen_c, menu_c) = (seen.clone(), menu.clone());
+        menu.subscribe_state(move |id, _| {
+            if let Some(MenuItemKind::Check(item)) = menu_c.find_item_by_id(id) {
+                seen_c
+                    .borrow_mut()
+                    .push((item.text(), item.is_enabled(), item.is_checked()));
+            }
+        });
+
+        item.set_text("Renamed");
+        item.set_enabled(false);
+        item.set_checked(true);
+        assert_eq!(
+            *seen.borrow(),
+            vec![
+                ("Renamed".to_string(), true, false),
+                ("Renamed".to_string(), false, false),
+                ("Renamed".to_string(), false, true),
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_rebuilds_from_desired_menu() {
+        use crate::{MenuItemKind, PredefinedMenuItem, Submenu};
+
+        let open = MenuItem::with_id("open", "Open", true, None);
+        let save = MenuItem::with_id("save", "Save", true, None);
+        let file = Submenu::with_id_and_items(
+            "file",
+            "File",
+            true,
+            &[&open, &PredefinedMenuItem::separator(), &save],
+        )
+        .unwrap();
+        let menu = Menu::with_items(&[&file]).unwrap();
+
+        let desired = Menu::with_items(&[&Submenu::with_id_and_items(
+            "file",
+            "File",
+            true,
+            &[
+                &MenuItem::with_id("open", "Open", true, None),
+                &PredefinedMenuItem::separator(),
+                &MenuItem::with_id("save", "Save As", true, None),
+                &MenuItem::with_id("close", "Close", true, None),
+            ],
+        )
+        .unwrap()])
+        .unwrap();
+
+        // one changed label and one inserted item
+        assert_eq!(menu.sync_from(&desired).unwrap(), 2);
+
+        let items = file.items();
+        assert_eq!(items.len(), 4);
+        assert!(matches!(items[1], MenuItemKind::Predefined(_)));
+        assert_eq!(items[3].id(), "close");
+        assert_eq!(save.text(), "Save As");
+
+        // already converged
+        assert_eq!(menu.sync_from(&desired).unwrap(), 0);
+
+        // the inserted item is a copy, changing `desired` only shows up on the next sync
+        let MenuItemKind::Submenu(desired_file) = &desired.items()[0] else {
+            unreachable!()
+        };
+        let MenuItemKind::MenuItem(desired_close) = &desired_file.items()[3] else {
+            unreachable!()
+        };
+        desired_close.set_text("Close All");
+        assert_eq!(items[3].as_menuitem().unwrap().text(), "Close");
+        assert_eq!(menu.sync_from(&desired).unwrap(), 1);
+        assert_eq!(items[3].as_menuitem().unwrap().text(), "Close All");
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_rejects_windows_of_other_threads() {
+        use std::sync::mpsc;
+
+        let (hwnd_tx, hwnd_rx) = mpsc::channel();
+        let (done_tx, done_rx) = mpsc::channel::<()>();
+        let thread = std::thread::spawn(move || {
+            let hwnd = create_window();
+            hwnd_tx.send(hwnd as isize).unwrap();
+            // keep the window alive until the test is done with it
+            let _ = done_rx.recv();
+            unsafe { windows_sys::Win32::UI::WindowsAndMessaging::DestroyWindow(hwnd) };
+        });
+
+        let hwnd = hwnd_rx.recv().unwrap();
+        let menu = Menu::new();
+        assert!(matches!(
+            unsafe { menu.init_for_hwnd(hwnd) },
+            Err(crate::Error::WrongThread)
+        ));
+
+        done_tx.send(()).unwrap();
+        thread.join().unwrap();
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_creates_menus_on_the_thread_that_owns_them() {
+        use crate::Submenu;
+        use windows_sys::Win32::UI::WindowsAndMessaging::IsGUIThread;
+
+        std::thread::spawn(|| {
+            let item = MenuItem::new("Open", true, None);
+            let submenu = Submenu::with_items("File", true, &[&item]).unwrap();
+            let menu = Menu::with_items(&[&submenu]).unwrap();
+            // creating them made this thread a GUI thread that owns them
+            assert_ne!(unsafe { IsGUIThread(0) }, 0);
+            assert!(menu.append(&MenuItem::new("Save", true, None)).is_ok());
+            assert!(submenu.remove(&item).is_ok());
+            assert_eq!(menu.items().len(), 2);
+        })
+        .join()
+        .unwrap();
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_lists_shortcut_hints() {
+        use crate::{MenuId, Submenu};
+
+        let save = Accelerator::new(Some(Modifiers::CONTROL), Code::KeyS);
+        let find = Accelerator::new(Some(Modifiers::CONTROL), Code::KeyF);
+        let menu = Menu::with_items(&[
+            &Submenu::with_items(
+                "&File",
+                true,
+                &[
+                    &MenuItem::with_id("save", "&Save", true, Some(save)),
+                    &MenuItem::with_id("close", "Close", true, None),
+                ],
+            )
+            .unwrap(),
+            &MenuItem::with_id("find", "Find && Replace", true, Some(find)),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            menu.shortcut_hints(),
+            vec![
+                (MenuId::new("save"), "Save".to_string(), save),
+                (MenuId::new("find"), "Find & Replace".to_string(), find),
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_measures_taller_with_more_items() {
+        use crate::{ContextMenu, PredefinedMenuItem};
+
+        let short = Menu::with_items(&[&MenuItem::new("Open", true, None)]).unwrap();
+        let long = Menu::with_items(&[
+            &MenuItem::new("Open", true, None),
+            &PredefinedMenuItem::separator(),
+            &MenuItem::new("Save", true, None),
+            &MenuItem::new("Close", true, None),
+        ])
+        .unwrap();
+
+        let hwnd = create_window();
+        let short = unsafe { short.measure(hwnd as _) }
+            .unwrap()
+            .to_physical::<u32>(1.0);
+        let long = unsafe { long.measure(hwnd as _) }
+            .unwrap()
+            .to_physical::<u32>(1.0);
+        assert!(long.height > short.height);
+        assert!(short.width > 0);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_clamps_measured_height_to_max_popup_height() {
+        use crate::{ContextMenu, IsMenuItem, Submenu};
+
+        let items = (0..50)
+            .map(|i| MenuItem::new(format!("Item {i}"), true, None))
+            .collect::<Vec<_>>();
+        let items = items
+            .iter()
+            .map(|i| i as &dyn IsMenuItem)
+            .collect::<Vec<_>>();
+        let submenu = Submenu::with_items("Many", true, &items).unwrap();
+        let menu = Menu::new();
+        menu.append_items(&items).unwrap();
+        menu.append(&submenu).unwrap();
+
+        let hwnd = create_window();
+        let height = |menu: &dyn ContextMenu| {
+            unsafe { menu.measure(hwnd as _) }
+                .unwrap()
+                .to_physical::<u32>(1.0)
+                .height
+        };
+        assert!(height(&menu) > 100);
+
+        menu.set_max_popup_height(Some(100));
+        assert_eq!(height(&menu), 100);
+        assert_eq!(height(&submenu), 100);
+
+        menu.set_max_popup_height(None);
+        assert!(height(&menu) > 100);
+        assert!(height(&submenu) > 100);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_finds_nested_item_by_id() {
+        use crate::{CheckMenuItem, MenuId, MenuItemKind, Submenu};
+
+        let wrap = CheckMenuItem::with_id("wrap", "Word Wrap", true, false, None);
+        let view = Submenu::with_items(
+            "&View",
+            true,
+            &[&Submenu::with_items("&Layout", true, &[&wrap]).unwrap()],
+        )
+        .unwrap();
+        let menu = Menu::with_items(&[
+            &Submenu::with_items(
+                "&Edit",
+                true,
+                &[&MenuItem::with_id("copy", "Copy", true, None)],
+            )
+            .unwrap(),
+            &view,
+        ])
+        .unwrap();
+
+        match menu.find_item_by_id(&MenuId::new("wrap")) {
+            Some(MenuItemKind::Check(item)) => item.set_checked(true),
+            _ => panic!("expected the check item"),
+        }
+        assert!(wrap.is_checked());
+
+        assert!(matches!(
+            view.find_item_by_id(&MenuId::new("wrap")),
+            Some(MenuItemKind::Check(_))
+        ));
+        assert!(view.find_item_by_id(&MenuId::new("copy")).is_none());
+        assert!(menu.find_item_by_id(&MenuId::new("missing")).is_none());
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_finds_nested_submenu_by_text() {
+        use crate::Submenu;
+
+        let tools = Submenu::with_id("tools", "&Tools", true);
+        let menu = Menu::with_items(&[
+            &Submenu::with_items("&Edit", true, &[&MenuItem::new("Tools", true, None)]).unwrap(),
+            &Submenu::with_items("&View", true, &[&tools]).unwrap(),
+        ])
+        .unwrap();
+
+        let found = menu.find_submenu_by_text("Tools").unwrap();
+        assert_eq!(found.id(), tools.id());
+        found
+            .append(&MenuItem::new("Plugin Action", true, None))
+            .unwrap();
+        assert_eq!(tools.items().len(), 1);
+
+        assert!(menu.find_submenu_by_text("tools").is_none());
+        assert_eq!(
+            menu.find_submenu_by_text_ignore_case("tools").unwrap().id(),
+            tools.id()
+        );
+        assert!(menu.find_submenu_by_text("Help").is_none());
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_keeps_accelerators_with_hidden_bar() {
+        use std::sync::{Arc, Mutex};
+        use windows_sys::Win32::UI::WindowsAndMessaging::{SendMessageW, WM_COMMAND};
+
+        let save = MenuItem::with_id(
+            "hidden-bar-save",
+            "Save",
+            true,
+            Some(Accelerator::new(Some(Modifiers::CONTROL), Code::KeyS)),
+        );
+        let menu = Menu::with_items(&[&save]).unwrap();
+        let hwnd = create_window();
+        unsafe { menu.init_for_hwnd(hwnd as _) }.unwrap();
+
+        unsafe { menu.set_bar_visible_for_hwnd(hwnd as _, false) }.unwrap();
+        assert!(!unsafe { menu.is_visible_on_hwnd(hwnd as _) });
+        assert!(menu.haccel() != 0);
+
+        let events = Arc::new(Mutex::new(0));
+        let events_ = events.clone();
+        let observer = crate::MenuEvent::add_observer(move |e| {
+            if e.id() == "hidden-bar-save" {
+                *events_.lock().unwrap() += 1;
+            }
+        });
+
+        // the high-order word is 1 for commands sent by accelerators
+        let id = save.inner.borrow().internal_id() as usize;
+        unsafe { SendMessageW(hwnd, WM_COMMAND, (1 << 16) | id, 0) };
+        crate::MenuEvent::remove_observer(observer);
+        assert_eq!(*events.lock().unwrap(), 1);
+
+        unsafe { menu.set_bar_visible_for_hwnd(hwnd as _, true) }.unwrap();
+        assert!(unsafe { menu.is_visible_on_hwnd(hwnd as _) });
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_sets_bar_hmenu_on_init() {
+        use crate::ContextMenu;
+        use windows_sys::Win32::UI::WindowsAndMessaging::GetMenu;
+
+        let menu = Menu::with_items(&[&MenuItem::new("Item", true, None)]).unwrap();
+        assert_ne!(menu.hmenu_bar(), 0);
+        assert_ne!(menu.hpopupmenu(), 0);
+        assert_ne!(menu.hmenu_bar(), menu.hpopupmenu());
+
+        let hwnd = create_window();
+        unsafe { menu.init_for_hwnd(hwnd as _) }.unwrap();
+        assert_eq!(unsafe { GetMenu(hwnd) } as isize, menu.hmenu_bar());
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_builds_the_standard_app_menu() {
+        use crate::{standard_ids, MenuId, StandardAppMenuBuilder};
+
+        let menu = Menu::standard_app_menu();
+        let submenus = menu
+            .items()
+            .iter()
+            .map(|i| i.id().as_ref().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            submenus,
+            [standard_ids::FILE, standard_ids::EDIT, standard_ids::HELP]
+        );
+
+        let copy = menu
+            .find_item_by_id(&MenuId::new(standard_ids::COPY))
+            .unwrap();
+        let copy = copy.as_predefined_menuitem().unwrap();
+        assert_eq!(copy.text(), "&Copy");
+        assert_eq!(
+            menu.translate_accelerator(0x43, Modifiers::CONTROL),
+            Some(MenuId::new(standard_ids::COPY))
+        );
+        assert_eq!(
+            menu.translate_accelerator(0x53, Modifiers::CONTROL),
+            Some(MenuId::new(standard_ids::SAVE))
+        );
+
+        // apps add their own items
+        let file = menu
+            .find_item_by_id(&MenuId::new(standard_ids::FILE))
+            .unwrap();
+        file.as_submenu_unchecked()
+            .insert(&MenuItem::new("Save &As...", true, None), 3)
+            .unwrap();
+        assert_eq!(file.as_submenu_unchecked().items().len(), 6);
+
+        let menu = StandardAppMenuBuilder::new()
+            .file(false)
+            .help(false)
+            .build()
+            .unwrap();
+        assert_eq!(menu.items().len(), 1);
+        assert_eq!(menu.items()[0].id(), standard_ids::EDIT);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_sends_events_for_standard_items_without_action() {
+        use std::sync::{Arc, Mutex};
+
+        use crate::{standard_ids, MenuId};
+        use windows_sys::Win32::UI::WindowsAndMessaging::{SendMessageW, WM_COMMAND};
+
+        let menu = Menu::standard_app_menu();
+        let hwnd = create_window();
+        unsafe { menu.init_for_hwnd(hwnd as _) }.unwrap();
+
+        let ids = [standard_ids::UNDO, standard_ids::REDO, standard_ids::ABOUT];
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let events_ = events.clone();
+        let observer = crate::MenuEvent::add_observer(move |e| {
+            if ids.contains(&e.id().as_ref()) {
+                events_.lock().unwrap().push(e.id().clone());
+            }
+        });
+
+        for id in ids {
+            let item = menu.find_item_by_id(&MenuId::new(id)).unwrap();
+            let id = item.child().internal_id() as usize;
+            unsafe { SendMessageW(hwnd, WM_COMMAND, id, 0) };
+        }
+        crate::MenuEvent::remove_observer(observer);
+        assert_eq!(*events.lock().unwrap(), ids.map(MenuId::new));
+        assert_eq!(
+            menu.translate_accelerator(0x5A, Modifiers::CONTROL),
+            Some(MenuId::new(standard_ids::UNDO))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_copies_items_of_win32_menus() {
+        use crate::MenuId;
+        use windows_sys::Win32::UI::WindowsAndMessaging::{
+            AppendMenuW, CreateMenu, CreatePopupMenu, DestroyMenu, InsertMenuItemW, MENUITEMINFOW,
+            MFS_CHECKED, MFT_RADIOCHECK, MF_CHECKED, MF_GRAYED, MF_POPUP, MF_SEPARATOR, MF_STRING,
+            MIIM_FTYPE, MIIM_ID, MIIM_STATE, MIIM_STRING,
+        };
+
+        let wide = |s: &str| s.encode_utf16().chain([0]).collect::<Vec<_>>();
+        let menu = unsafe {
+            let file = CreatePopupMenu();
+            AppendMenuW(file, MF_STRING, 101, wide("&Open\tCtrl+O").as_ptr());
+            AppendMenuW(file, MF_SEPARATOR, 0, std::ptr::null());
+            AppendMenuW(file, MF_STRING | MF_GRAYED, 102, wide("Close").as_ptr());
+            let hmenu = CreateMenu();
+            AppendMenuW(hmenu, MF_POPUP, file as _, wide("&File").as_ptr());
+            AppendMenuW(hmenu, MF_STRING | MF_CHECKED, 103, wide("Wrap").as_ptr());
+            for (id, text, state) in [(104, "Small", MFS_CHECKED), (105, "Large", 0)] {
+                let mut text = wide(text);
+                let mut info: MENUITEMINFOW = std::mem::zeroed();
+                info.cbSize = std::mem::size_of::<MENUITEMINFOW>() as _;
+                info.fMask = MIIM_FTYPE | MIIM_STATE | MIIM_ID | MIIM_STRING;
+                info.fType = MFT_RADIOCHECK;
+                info.fState = state;
+                info.wID = id;
+                info.dwTypeData = text.as_mut_ptr();
+                InsertMenuItemW(hmenu, id, false.into(), &info);
+            }
+
+            let menu = Menu::copy_from_hmenu(hmenu as _).unwrap();
+            // the items were copied
+            DestroyMenu(hmenu);
+            menu
+        };
+
+        let items = menu.items();
+        assert_eq!(items.len(), 4);
+        let file = items[0].as_submenu_unchecked();
+        assert_eq!(file.text(), "&File");
+        let file_items = file.items();
+        assert_eq!(file_items.len(), 3);
+        let open = file_items[0].as_menuitem_unchecked();
+        assert_eq!(open.id(), "101");
+        assert_eq!(open.text(), "&Open");
+        assert_eq!(
+            open.accelerator(),
+            Some(Accelerator::new(Some(Modifiers::CONTROL), Code::KeyO))
+        );
+        assert!(file_items[1].as_predefined_menuitem().is_some());
+        assert!(!file_items[2].as_menuitem_unchecked().is_enabled());
+        // a checked item that isn't a radio item nor has check mark bitmaps isn't a check item
+        assert!(items[1].as_menuitem().is_some());
+        let (small, large) = (
+            items[2].as_check_menuitem_unchecked(),
+            items[3].as_check_menuitem_unchecked(),
+        );
+        assert!(small.is_checked() && !large.is_checked());
+        assert!(small.radio_group().is_some());
+        assert_eq!(small.radio_group(), large.radio_group());
+        assert!(menu.find_item_by_id(&MenuId::new("102")).is_some());
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_collects_items() {
+        use crate::IsMenuItem;
+
+        let menu = (1..=3)
+            .map(|i| {
+                Box::new(MenuItem::with_id(
+                    format!("recent-{i}"),
+                    format!("Recent {i}"),
+                    true,
+                    None,
+                )) as Box<dyn IsMenuItem>
+            })
+            .collect::<crate::Result<Menu>>()
+            .unwrap();
+        let ids = menu
+            .items()
+            .iter()
+            .map(|i| i.id().as_ref().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["recent-1", "recent-2", "recent-3"]);
+
+        let menu = Menu::try_from_iter(
+            ["Cut", "Copy"].map(|text| Box::new(MenuItem::new(text, true, None)) as _),
+        )
+        .unwrap();
+        assert_eq!(menu.items()[1].as_menuitem_unchecked().text(), "Copy");
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_inserts_skipping_separators() {
+        use crate::PredefinedMenuItem;
+
+        let menu = Menu::with_items(&[
+            &MenuItem::with_id("first", "First", true, None),
+            &PredefinedMenuItem::separator(),
+            &MenuItem::with_id("second", "Second", true, None),
+        ])
+        .unwrap();
+
+        menu.insert_skipping_separators(&MenuItem::with_id("new", "New", true, None), 1)
+            .unwrap();
+        let ids = |items: Vec<crate::MenuItemKind>| {
+            items
+                .iter()
+                .map(|i| i.id().as_ref().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids(menu.non_separator_items()), ["first", "new", "second"]);
+        assert!(menu.items()[1].as_predefined_menuitem().is_some());
+        assert_eq!(menu.items()[2].id(), "new");
+
+        menu.insert_skipping_separators(&MenuItem::with_id("last", "Last", true, None), 3)
+            .unwrap();
+        assert_eq!(
+            ids(menu.non_separator_items()),
+            ["first", "new", "second", "last"]
+        );
+        assert_eq!(menu.items().len(), 5);
+
+        // past the end
+        menu.insert_skipping_separators(&MenuItem::with_id("far", "Far", true, None), 10)
+            .unwrap();
+        assert_eq!(menu.items().len(), 6);
+        assert_eq!(menu.items()[5].id(), "far");
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_handles_messages_without_subclass() {
+        use std::sync::{Arc, Mutex};
+        use windows_sys::Win32::UI::WindowsAndMessaging::{WM_COMMAND, WM_NULL};
+
+        let item = MenuItem::with_id("manual-message", "Item", true, None);
+        let menu = Menu::with_items(&[&item]).unwrap();
+
+        let events = Arc::new(Mutex::new(0));
+        let events_ = events.clone();
+        let observer = crate::MenuEvent::add_observer(move |e| {
+            if e.id() == "manual-message" {
+                *events_.lock().unwrap() += 1;
+            }
+        });
+
+        let id = item.inner.borrow().internal_id() as usize;
+        let hwnd = create_window() as isize;
+        let handled = unsafe { menu.handle_message(hwnd, WM_COMMAND, id, 0) };
+        let unhandled = unsafe { menu.handle_message(hwnd, WM_NULL, 0, 0) };
+        crate::MenuEvent::remove_observer(observer);
+
+        assert_eq!(handled, Some(0));
+        assert_eq!(unhandled, None);
+        assert_eq!(*events.lock().unwrap(), 1);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_clears_all_items() {
+        use crate::{ContextMenu, Submenu};
+        use windows_sys::Win32::UI::WindowsAndMessaging::GetMenuItemCount;
+
+        let recent = Submenu::with_items(
+            "Recent",
+            true,
+            &[
+                &MenuItem::new("a.txt", true, None),
+                &MenuItem::new("b.txt", true, None),
+            ],
+        )
+        .unwrap();
+        let save = MenuItem::new("Save", true, Some("Ctrl+S".parse().unwrap()));
+        let menu = Menu::with_items(&[&save, &recent]).unwrap();
+        let haccel = menu.haccel();
+
+        let removed = recent.clear().unwrap();
+        assert_eq!(removed.len(), 2);
+        assert!(recent.items().is_empty());
+        assert_eq!(unsafe { GetMenuItemCount(recent.hpopupmenu() as _) }, 0);
+
+        let removed = menu.clear().unwrap();
+        assert_eq!(removed.len(), 2);
+        assert_eq!(removed[0].id(), save.id());
+        assert!(menu.items().is_empty());
+        assert_eq!(unsafe { GetMenuItemCount(menu.hmenu_bar() as _) }, 0);
+        assert_eq!(unsafe { GetMenuItemCount(menu.hpopupmenu() as _) }, 0);
+        assert_eq!(menu.haccel(), haccel);
+
+        menu.append(&MenuItem::new("Open", true, None)).unwrap();
+        assert_eq!(menu.items().len(), 1);
+        assert_eq!(unsafe { GetMenuItemCount(menu.hmenu_bar() as _) }, 1);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_keeps_one_radio_item_checked() {
+        use crate::{CheckMenuItem, ContextMenu, Submenu};
+        use windows_sys::Win32::UI::WindowsAndMessaging::{
+            GetMenuItemInfoW, MENUITEMINFOW, MFT_RADIOCHECK, MIIM_FTYPE, WM_COMMAND,
+        };
+
+        let small = CheckMenuItem::new_radio("Small", true, true, "size");
+        let medium = CheckMenuItem::new_radio("Medium", true, false, "size");
+        let large = CheckMenuItem::new_radio("Large", true, true, "size");
+        let bold = CheckMenuItem::new("Bold", true, true, None);
+        let view = Submenu::with_items("&View", true, &[&small, &medium]).unwrap();
+        let more = Submenu::with_items("&More", true, &[&large, &bold]).unwrap();
+        let menu = Menu::with_items(&[&view, &more]).unwrap();
+
+        assert_eq!(large.radio_group().as_deref(), Some("size"));
+        assert_eq!(bold.radio_group(), None);
+        let checked = || [&small, &medium, &large].map(|i| i.is_checked());
+        // joining the group checked unchecks the others
+        assert_eq!(checked(), [false, false, true]);
+
+        medium.set_checked(true);
+        assert_eq!(checked(), [false, true, false]);
+
+        let hwnd = create_window() as isize;
+        let activate = |item: &CheckMenuItem| unsafe {
+            let id = item.inner.borrow().internal_id() as usize;
+            menu.handle_message(hwnd, WM_COMMAND, id, 0)
+        };
+        activate(&small);
+        assert_eq!(checked(), [true, false, false]);
+        activate(&small);
+        assert_eq!(checked(), [true, false, false]);
+        // items outside the group are unaffected
+        assert!(bold.is_checked());
+
+        let mut info: MENUITEMINFOW = unsafe { std::mem::zeroed() };
+        info.cbSize = std::mem::size_of::<MENUITEMINFOW>() as _;
+        info.fMask = MIIM_FTYPE;
+        let id = large.inner.borrow().internal_id();
+        unsafe { GetMenuItemInfoW(more.hpopupmenu() as _, id, 0, &mut info) };
+        assert_ne!(info.fType & MFT_RADIOCHECK, 0);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_keeps_radio_groups_exclusive_when_copying_and_rebuilding() {
+        use crate::{platform_impl::radio, CheckMenuItem};
+
+        let groups = radio::group_count();
+        let a = CheckMenuItem::new_radio("A", true, true, "rebuilt");
+        let b = CheckMenuItem::new_radio("B", true, false, "rebuilt");
+        let menu = Menu::with_items(&[&a, &b]).unwrap();
+        assert_eq!(radio::group_count(), groups + 1);
+
+        // both checked in `desired`, the group keeps the last one
+        let desired = Menu::with_items(&[
+            &CheckMenuItem::with_id(a.id().clone(), "A", true, true, None),
+            &CheckMenuItem::with_id(b.id().clone(), "B", true, true, None),
+        ])
+        .unwrap();
+        menu.rebuild_from(&desired).unwrap();
+        assert_eq!([a.is_checked(), b.is_checked()], [false, true]);
+
+        let c = b.clone_as_new();
+        assert_eq!(c.radio_group().as_deref(), Some("rebuilt"));
+        assert!(!c.is_checked() && b.is_checked());
+        c.set_checked(true);
+        assert!(!b.is_checked());
+
+        drop((menu, desired, a, b, c));
+        assert_eq!(radio::group_count(), groups);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_calls_item_handlers_for_their_id() {
+        use crate::{CheckMenuItem, MenuId, Submenu};
+        use std::{cell::RefCell, rc::Rc};
+        use windows_sys::Win32::UI::WindowsAndMessaging::WM_COMMAND;
+
+        let open = MenuItem::with_id("open", "Open", true, None);
+        let wrap = CheckMenuItem::with_id("wrap", "Word Wrap", true, false, None);
+        let menu = Menu::with_items(&[
+            &open,
+            &Submenu::with_items("&View", true, &[&wrap]).unwrap(),
+        ])
+        .unwrap();
+
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        for id in ["open", "wrap"] {
+            let calls = calls.clone();
+            menu.on_item(&MenuId::new(id), move |e| {
+                calls.borrow_mut().push((id, e.id().clone()))
+            });
+        }
+
+        let hwnd = create_window() as isize;
+        let command = |internal_id: u32| unsafe {
+            menu.handle_message(hwnd, WM_COMMAND, internal_id as usize, 0)
+        };
+        command(open.inner.borrow().internal_id());
+        assert_eq!(*calls.borrow(), vec![("open", MenuId::new("open"))]);
+
+        let wrap_handler = calls.clone();
+        let wrap_ = wrap.clone();
+        // handlers can use the activated item
+        menu.on_item(&MenuId::new("wrap"), move |_| {
+            wrap_handler
+                .borrow_mut()
+                .push(("checked", MenuId::new(wrap_.is_checked().to_string())))
+        });
+        command(wrap.inner.borrow().internal_id());
+        assert_eq!(
+            *calls.borrow(),
+            vec![
+                ("open", MenuId::new("open")),
+                ("wrap", MenuId::new("wrap")),
+                ("checked", MenuId::new("true")),
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_emits_submenu_events() {
+        use std::{
+            cell::RefCell,
+            sync::{Arc, Mutex},
+        };
+
+        use crate::{ContextMenu, Submenu, SubmenuEvent, SubmenuEventKind};
+        use windows_sys::Win32::UI::WindowsAndMessaging::{WM_INITMENUPOPUP, WM_UNINITMENUPOPUP};
+
+        thread_local! {
+            static LAZY: RefCell<Option<Submenu>> = const { RefCell::new(None) };
+        }
+
+        let lazy = Submenu::with_id("lazy", "Lazy", true);
+        let menu = Menu::with_items(&[&lazy]).unwrap();
+        let hwnd = create_window() as isize;
+        LAZY.set(Some(lazy.clone()));
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let events_ = events.clone();
+        SubmenuEvent::set_event_handler(Some(move |event: SubmenuEvent| {
+            if event.id != "lazy" {
+                return;
+            }
+            // filled right before it is shown
+            if event.kind == SubmenuEventKind::Opened {
+                LAZY.with_borrow(|lazy| {
+                    let item = MenuItem::new("Fresh", true, None);
+                    lazy.as_ref().unwrap().set_items(&[&item]).unwrap();
+                });
+            }
+            events_.lock().unwrap().push(event.kind);
+        }));
+
+        let hmenu = lazy.hpopupmenu() as usize;
+        unsafe { menu.handle_message(hwnd, WM_INITMENUPOPUP, hmenu, 0) };
+        assert_eq!(lazy.items().len(), 1);
+        unsafe { menu.handle_message(hwnd, WM_UNINITMENUPOPUP, hmenu, 0) };
+        SubmenuEvent::set_event_handler(None::<fn(SubmenuEvent)>);
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![SubmenuEventKind::Opened, SubmenuEventKind::Closed]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_builds_lazy_submenus_on_open() {
+        use std::{cell::Cell, rc::Rc};
+
+        use crate::{ContextMenu, Submenu};
+        use windows_sys::Win32::UI::WindowsAndMessaging::{WM_INITMENUPOPUP, WM_UNINITMENUPOPUP};
+
+        let fonts = Submenu::new("Fonts", true);
+        let view = Submenu::with_items("View", true, &[&fonts]).unwrap();
+        let menu = Menu::with_items(&[&view]).unwrap();
+        let hwnd = create_window() as isize;
+        let open = |submenu: &Submenu| unsafe {
+            let hmenu = submenu.hpopupmenu() as usize;
+            menu.handle_message(hwnd, WM_INITMENUPOPUP, hmenu, 0);
+            menu.handle_message(hwnd, WM_UNINITMENUPOPUP, hmenu, 0);
+        };
+
+        let builds = Rc::new(Cell::new(0));
+        let builds_ = builds.clone();
+        fonts.set_lazy_builder(move |fonts| {
+            builds_.set(builds_.get() + 1);
+            for name in ["Arial", "Consolas", "Segoe UI"] {
+                fonts.append(&MenuItem::new(name, true, None)).unwrap();
+            }
+        });
+        assert!(fonts.items().is_empty());
+
+        open(&view);
+        assert_eq!(builds.get(), 0);
+        open(&fonts);
+        open(&fonts);
+        assert_eq!(builds.get(), 1);
+        assert_eq!(fonts.items().len(), 3);
+
+        assert!(!fonts.rebuilds_on_open());
+        fonts.set_rebuild_on_open(true);
+        assert!(fonts.rebuilds_on_open());
+        open(&fonts);
+        assert_eq!(builds.get(), 2);
+        assert_eq!(fonts.items().len(), 3);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_builds_lazy_submenus_that_were_in_use() {
+        use crate::Submenu;
+
+        let fonts = Submenu::new("Fonts", true);
+        fonts.set_lazy_builder(|fonts| {
+            fonts.append(&MenuItem::new("Arial", true, None)).unwrap();
+        });
+
+        {
+            let _in_use = fonts.inner.borrow();
+            crate::platform_impl::lazy::build(&fonts.inner);
+        }
+        assert!(fonts.items().is_empty());
+
+        crate::platform_impl::lazy::build(&fonts.inner);
+        assert_eq!(fonts.items().len(), 1);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_clears_submenu_on_close() {
+        use crate::{ContextMenu, Submenu};
+        use windows_sys::Win32::UI::WindowsAndMessaging::{WM_INITMENUPOPUP, WM_UNINITMENUPOPUP};
+
+        let recent = Submenu::new("Recent", true);
+        let menu = Menu::with_items(&[&recent]).unwrap();
+        let hwnd = create_window() as isize;
+        let open_and_close = || unsafe {
+            let hmenu = recent.hpopupmenu() as usize;
+            menu.handle_message(hwnd, WM_INITMENUPOPUP, hmenu, 0);
+            menu.handle_message(hwnd, WM_UNINITMENUPOPUP, hmenu, 0);
+            dispatch_posted(&menu, hwnd);
+        };
+
+        recent
+            .set_items(&[&MenuItem::new("a.txt", true, None)])
+            .unwrap();
+        open_and_close();
+        assert_eq!(recent.items().len(), 1);
+
+        recent.set_clear_on_close(true);
+        open_and_close();
+        assert!(recent.items().is_empty());
+
+        recent
+            .set_items(&[&MenuItem::new("b.txt", true, None)])
+            .unwrap();
+        assert_eq!(recent.items().len(), 1);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_handles_clicks_in_submenus_that_clear_on_close() {
+        use crate::{ContextMenu, Submenu};
+        use std::sync::{Arc, Mutex};
+        use windows_sys::Win32::UI::WindowsAndMessaging::{
+            PostMessageW, WM_COMMAND, WM_INITMENUPOPUP, WM_UNINITMENUPOPUP,
+        };
+
+        let item = MenuItem::with_id("recent-a", "a.txt", true, None);
+        let recent = Submenu::with_items("Recent", true, &[&item]).unwrap();
+        recent.set_clear_on_close(true);
+        let menu = Menu::with_items(&[&recent]).unwrap();
+        let hwnd = create_window() as isize;
+
+        let events = Arc::new(Mutex::new(0));
+        let events_ = events.clone();
+        let observer = crate::MenuEvent::add_observer(move |e| {
+            if e.id() == "recent-a" {
+                *events_.lock().unwrap() += 1;
+            }
+        });
+
+        // the menu loop posts the command of the clicked item before closing the popup
+        let id = item.inner.borrow().internal_id() as usize;
+        unsafe {
+            let hmenu = recent.hpopupmenu() as usize;
+            menu.handle_message(hwnd, WM_INITMENUPOPUP, hmenu, 0);
+            PostMessageW(hwnd as _, WM_COMMAND, id, 0);
+            menu.handle_message(hwnd, WM_UNINITMENUPOPUP, hmenu, 0);
+            assert_eq!(recent.items().len(), 1);
+            dispatch_posted(&menu, hwnd);
+        }
+        crate::MenuEvent::remove_observer(observer);
+
+        assert_eq!(*events.lock().unwrap(), 1);
+        assert!(recent.items().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_streams_coalesced_changes() {
+        use futures_core::Stream;
+        use std::{
+            pin::{pin, Pin},
+            task::Poll,
+        };
+
+        fn has_change(changes: Pin<&mut impl Stream<Item = ()>>) -> bool {
+            let mut changes = changes;
+            futures_executor::block_on(std::future::poll_fn(|cx| {
+                Poll::Ready(changes.as_mut().poll_next(cx).is_ready())
+            }))
+        }
+
+        let menu = Menu::new();
+        let mut changes = pin!(menu.changes());
+        assert!(!has_change(changes.as_mut()));
+
+        menu.append(&MenuItem::new("First", true, None)).unwrap();
+        menu.append(&MenuItem::new("Second", true, None)).unwrap();
+        let next = std::future::poll_fn(|cx| changes.as_mut().poll_next(cx));
+        assert_eq!(futures_executor::block_on(next), Some(()));
+        // both appends were reported at once
+        assert!(!has_change(changes.as_mut()));
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_bumps_generation_on_nested_changes() {
+        use crate::Submenu;
+
+        let item = MenuItem::new("Nested", true, None);
+        let inner = Submenu::with_items("Inner", true, &[&item]).unwrap();
+        let outer = Submenu::with_items("Outer", true, &[&inner]).unwrap();
+        let menu = Menu::new();
+
+        let generation = menu.generation();
+        menu.append(&outer).unwrap();
+        assert_ne!(menu.generation(), generation);
+
+        let generation = menu.generation();
+        item.set_text("Nested");
+        assert_eq!(menu.generation(), generation);
+        item.set_text("Renamed");
+        assert_ne!(menu.generation(), generation);
+
+        let generation = menu.generation();
+        inner.append(&MenuItem::new("Added", true, None)).unwrap();
+        assert_ne!(menu.generation(), generation);
+
+        let generation = menu.generation();
+        inner.remove(&item).unwrap();
+        assert_ne!(menu.generation(), generation);
+
+        // the removed item no longer belongs to the menu
+        let generation = menu.generation();
+        item.set_enabled(false);
+        assert_eq!(menu.generation(), generation);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_round_trips_accelerators() {
+        use crate::Submenu;
+
+        let save = Accelerator::new(Some(Modifiers::CONTROL), Code::KeyS);
+        let next = Accelerator::new(Some(Modifiers::CONTROL), Code::PageDown);
+        let save_item = MenuItem::with_id("save", "&Save", true, Some(save));
+        let next_item = MenuItem::with_id("next-tab", "Next Tab", true, Some(next));
+        let menu = Menu::with_items(&[
+            &Submenu::with_items("&File", true, &[&save_item]).unwrap(),
+            &next_item,
+        ])
+        .unwrap();
+
+        let keymap = menu.export_accelerators();
+        assert_eq!(keymap, "save = Ctrl+S\nnext-tab = Ctrl+PageDown\n");
+
+        save_item.set_accelerator(None).unwrap();
+        next_item.set_accelerator(None).unwrap();
+        assert!(menu.shortcut_hints().is_empty());
+
+        menu.import_accelerators(&format!("# my keymap\n\n{keymap}"))
+            .unwrap();
+        assert_eq!(save_item.accelerator(), Some(save));
+        assert_eq!(next_item.accelerator(), Some(next));
+        assert_eq!(menu.export_accelerators(), keymap);
+
+        assert!(matches!(
+            menu.import_accelerators("save = Ctrl+S\nmissing = Ctrl+M"),
+            Err(crate::Error::InvalidKeymapLine { line: 2, .. })
+        ));
+        assert!(matches!(
+            menu.import_accelerators("save =\nnext-tab = Ctrl+Nope"),
+            Err(crate::Error::InvalidKeymapLine { line: 2, .. })
+        ));
+        // nothing was applied from the invalid keymaps
+        assert_eq!(save_item.accelerator(), Some(save));
+        assert_eq!(next_item.accelerator(), Some(next));
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_applies_standard_accelerators() {
+        use crate::{CheckMenuItem, Submenu};
+
+        let save = MenuItem::new("&Save", true, None);
+        let copy = MenuItem::with_id("copy", "Duplicate", true, None);
+        let open = CheckMenuItem::new(
+            "Open...",
+            true,
+            false,
+            Some(Accelerator::new(Some(Modifiers::CONTROL), Code::KeyK)),
+        );
+        let plain = MenuItem::new("Frobnicate", true, None);
+
+        let menu = Menu::new();
+        let submenu = Submenu::with_items("File", true, &[&save, &open, &plain]).unwrap();
+        menu.append_items(&[&submenu, &copy]).unwrap();
+        menu.apply_standard_accelerators().unwrap();
+
+        assert_eq!(
+            save.accelerator(),
+            Some(Accelerator::new(Some(Modifiers::CONTROL), Code::KeyS))
+        );
+        assert_eq!(
+            copy.accelerator(),
+            Some(Accelerator::new(Some(Modifiers::CONTROL), Code::KeyC))
+        );
+        assert_eq!(
+            open.inner.borrow().accelerator(),
+            Some(Accelerator::new(Some(Modifiers::CONTROL), Code::KeyK))
+        );
+        assert_eq!(plain.accelerator(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_round_trips_json() {
+        use crate::{CheckMenuItem, PredefinedMenuItem, Submenu};
+
+        let recent = Submenu::with_id_and_items(
+            "recent",
+            "Open &Recent",
+            false,
+            &[&MenuItem::with_id("clear", "Clear", true, None)],
+        )
+        .unwrap();
+        let file = Submenu::with_id_and_items(
+            "file",
+            "&File",
+            true,
+            &[
+                &MenuItem::with_id(
+                    "save",
+                    "&Save",
+                    true,
+                    Some(Accelerator::new(Some(Modifiers::CONTROL), Code::KeyS)),
+                ),
+                &PredefinedMenuItem::separator(),
+                &recent,
+                &PredefinedMenuItem::quit(None),
+            ],
+        )
+        .unwrap();
+        let menu = Menu::with_id_and_items(
+            "main",
+            &[
+                &file,
+                &CheckMenuItem::with_id(
+                    "wrap",
+                    "Word Wrap",
+                    true,
+                    true,
+                    Some(Accelerator::new(Some(Modifiers::ALT), Code::KeyZ)),
+                ),
+            ],
+        )
+        .unwrap();
+
+        let json = menu.to_json();
+        let restored = Menu::from_json(&json).unwrap();
+        assert_eq!(restored.to_json(), json);
+
+        assert_eq!(restored.id(), "main");
+        let items = restored.items();
+        assert_eq!(items.len(), 2);
+        let file = items[0].as_submenu_unchecked();
+        assert_eq!(file.id(), "file");
+        assert_eq!(file.items().len(), 4);
+        let recent = file.items()[2].as_submenu_unchecked().clone();
+        assert!(!recent.is_enabled());
+        assert_eq!(recent.items()[0].id(), "clear");
+        let wrap = items[1].as_check_menuitem_unchecked();
+        assert!(wrap.is_checked());
+
+        assert!(matches!(
+            Menu::from_json("{\"items\": 1}"),
+            Err(crate::Error::InvalidJson(_))
+        ));
+
+        // items without an id get a generated one each
+        let menu = Menu::from_json(
+            r#"{"id": "m", "items": [{"kind": "item", "text": "A"}, {"kind": "item", "text": "B"}]}"#,
+        )
+        .unwrap();
+        let items = menu.items();
+        assert!(!items[0].id().as_ref().is_empty());
+        assert_ne!(items[0].id(), items[1].id());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_round_trips_descriptors() {
+        use crate::{
+            CheckMenuItem, ItemDescriptorKind, MenuDescriptor, MenuId, PredefinedMenuItem, Submenu,
+        };
+
+        let edit = Submenu::with_id_and_items(
+            "edit",
+            "&Edit",
+            true,
+            &[
+                &PredefinedMenuItem::copy(None),
+                &MenuItem::with_id(
+                    "find",
+                    "&Find",
+                    false,
+                    Some(Accelerator::new(Some(Modifiers::CONTROL), Code::KeyF)),
+                ),
+            ],
+        )
+        .unwrap();
+        let menu = Menu::with_id_and_items(
+            "main",
+            &[
+                &edit,
+                &CheckMenuItem::with_id("wrap", "Word Wrap", true, true, None),
+            ],
+        )
+        .unwrap();
+
+        let descriptor = menu.to_descriptor();
+        assert_eq!(descriptor.id, "main");
+        assert_eq!(descriptor.items[0].kind, ItemDescriptorKind::Submenu);
+        assert_eq!(descriptor.items[0].children[0].id, None);
+        assert_eq!(descriptor.items[1].checked, Some(true));
+
+        // stored with any serde format, here a JSON value
+        let stored = serde_json::to_value(&descriptor).unwrap();
+        let loaded: MenuDescriptor = serde_json::from_value(stored).unwrap();
+        let restored = Menu::from_descriptor(&loaded).unwrap();
+        assert_eq!(restored.to_descriptor(), descriptor);
+
+        let find = restored.find_item_by_id(&MenuId::new("find")).unwrap();
+        let find = find.as_menuitem_unchecked();
+        assert!(!find.is_enabled());
+        assert_eq!(
+            find.accelerator(),
+            Some(Accelerator::new(Some(Modifiers::CONTROL), Code::KeyF))
+        );
+        assert!(restored
+            .find_item_by_id(&MenuId::new("wrap"))
+            .unwrap()
+            .as_check_menuitem_unchecked()
+            .is_checked());
+
+        let mut invalid = descriptor;
+        invalid.items[1].accelerator = Some("Control+Nothing".to_string());
+        assert!(matches!(
+            Menu::from_descriptor(&invalid),
+            Err(crate::Error::AcceleratorParseError(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_stores_json_data_in_items() {
+        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Recent {
+            path: String,
+            line: u32,
+        }
+
+        let recent = Recent {
+            path: "notes.txt".into(),
+            line: 42,
+        };
+        let item = MenuItem::with_id("recent", "notes.txt", true, None);
+        assert_eq!(item.data_json::<Recent>(), None);
+        item.set_data_json(&recent).unwrap();
+        assert_eq!(item.data_json::<Recent>(), Some(recent));
+        assert_eq!(item.data_json::<u32>(), None);
+
+        let menu = Menu::with_items(&[&item]).unwrap();
+        let restored = Menu::from_json(&menu.to_json()).unwrap();
+        let item = restored.items()[0].as_menuitem_unchecked().clone();
+        assert_eq!(
+            item.data_json::<Recent>(),
+            Some(Recent {
+                path: "notes.txt".into(),
+                line: 42,
+            })
+        );
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_translates_accelerators() {
+        use crate::{
+            accelerator::{Code, Modifiers},
+            MenuId, Submenu,
+        };
+        use windows_sys::Win32::UI::Input::KeyboardAndMouse::{VK_O, VK_S};
+
+        let file = Submenu::new("File", true);
+        let menu = Menu::with_items(&[&file]).unwrap();
+        let save = MenuItem::with_id(
+            "save",
+            "Save",
+            true,
+            Some(Accelerator::new(Some(Modifiers::CONTROL), Code::KeyS)),
+        );
+        let save_as = MenuItem::with_id(
+            "save_as",
+            "Save As",
+            true,
+            Some(Accelerator::new(
+                Some(Modifiers::CONTROL | Modifiers::SHIFT),
+                Code::KeyS,
+            )),
+        );
+        file.append_items(&[&save, &save_as]).unwrap();
+
+        let ctrl = Modifiers::CONTROL;
+        assert_eq!(
+            menu.translate_accelerator(VK_S as _, ctrl),
+            Some(MenuId::new("save"))
+        );
+        assert_eq!(
+            menu.translate_accelerator(VK_S as _, ctrl | Modifiers::SHIFT),
+            Some(MenuId::new("save_as"))
+        );
+        assert_eq!(
+            menu.translate_accelerator(VK_S as _, Modifiers::empty()),
+            None
+        );
+        assert_eq!(menu.translate_accelerator(VK_O as _, ctrl), None);
+
+        save.set_enabled(false);
+        assert_eq!(menu.translate_accelerator(VK_S as _, ctrl), None);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_updates_items_by_id() {
+        use crate::{
+            accelerator::{Code, Modifiers},
+            CheckMenuItem, ItemUpdate, MenuId, Submenu,
+        };
+
+        let open = MenuItem::with_id("open", "Open", true, None);
+        let save = MenuItem::with_id("save", "Save", true, None);
+        let wrap = CheckMenuItem::with_id("wrap", "Wrap", true, false, None);
+        let file = Submenu::with_items("File", true, &[&open, &save]).unwrap();
+        let menu = Menu::with_items(&[&file, &wrap]).unwrap();
+
+        let accelerator = Accelerator::new(Some(Modifiers::CONTROL), Code::KeyS);
+        let unknown = menu
+            .update_items([
+                (MenuId::new("open"), ItemUpdate::new().with_text("Open...")),
+                (
+                    MenuId::new("save"),
+                    ItemUpdate::new()
+                        .with_enabled(false)
+                        .with_accelerator(Some(accelerator)),
+                ),
+                (MenuId::new("wrap"), ItemUpdate::new().with_checked(true)),
+                (
+                    MenuId::new("missing"),
+                    ItemUpdate::new().with_enabled(false),
+                ),
+            ])
+            .unwrap();
+
+        assert_eq!(unknown, vec![MenuId::new("missing")]);
+        assert_eq!(open.text(), "Open...");
+        assert!(open.is_enabled());
+        assert!(!save.is_enabled());
+        assert_eq!(save.accelerator(), Some(accelerator));
+        assert!(wrap.is_checked());
+        assert_eq!(wrap.text(), "Wrap");
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_sets_enabled_by_id() {
+        use crate::{MenuId, Submenu};
+
+        let paste = MenuItem::with_id("paste", "Paste", true, None);
+        let edit = Submenu::with_items("Edit", true, &[&paste]).unwrap();
+        let menu = Menu::with_items(&[&edit]).unwrap();
+
+        menu.set_enabled_by_id(&MenuId::new("paste"), false)
+            .unwrap();
+        assert!(!paste.is_enabled());
+        menu.set_enabled_by_id(&MenuId::new("paste"), true).unwrap();
+        assert!(paste.is_enabled());
+
+        assert!(matches!(
+            menu.set_enabled_by_id(&MenuId::new("missing"), false),
+            Err(crate::Error::NotAChildOfThisMenu)
+        ));
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_reports_accelerator_conflicts() {
+        use crate::{MenuId, Submenu};
+
+        let save = MenuItem::with_id(
+            "save",
+            "Save",
+            true,
+            Some(Accelerator::new(Some(Modifiers::CONTROL), Code::KeyS)),
+        );
+        let open = MenuItem::with_id(
+            "open",
+            "Open",
+            true,
+            Some(Accelerator::new(Some(Modifiers::CONTROL), Code::KeyO)),
+        );
+        let file = Submenu::with_items("File", true, &[&save, &open]).unwrap();
+        let menu = Menu::with_items(&[&file]).unwrap();
+        assert!(menu.accelerator_conflicts().is_empty());
+
+        let settings = MenuItem::with_id(
+            "settings",
+            "Settings",
+            true,
+            Some("Control+KeyS".parse().unwrap()),
+        );
+        menu.append(&settings).unwrap();
+        assert_eq!(
+            menu.accelerator_conflicts(),
+            vec![(
+                Accelerator::new(Some(Modifiers::CONTROL), Code::KeyS),
+                vec![MenuId::new("save"), MenuId::new("settings")]
+            )]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_reports_accelerators_shadowing_reserved_ones() {
+        use crate::{MenuId, Submenu};
+
+        let close = MenuItem::with_id(
+            "close",
+            "Close",
+            true,
+            Some("Control+KeyW".parse().unwrap()),
+        );
+        let save = MenuItem::with_id(
+            "save",
+            "Save",
+            true,
+            Some(Accelerator::new(Some(Modifiers::CONTROL), Code::KeyS)),
+        );
+        let file = Submenu::with_items("File", true, &[&save, &close]).unwrap();
+        let menu = Menu::with_items(&[&file]).unwrap();
+
+        let ctrl_w = Accelerator::new(Some(Modifiers::CONTROL), Code::KeyW);
+        let ctrl_t = Accelerator::new(Some(Modifiers::CONTROL), Code::KeyT);
+        assert_eq!(
+            menu.accelerators_shadowing(&[ctrl_t, ctrl_w]),
+            vec![(MenuId::new("close"), ctrl_w)]
+        );
+        assert!(menu.accelerators_shadowing(&[ctrl_t]).is_empty());
+        assert!(menu.accelerators_shadowing(&[]).is_empty());
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_reports_duplicates_and_conflicts_in_diagnostics() {
+        use crate::{MenuId, Submenu};
+
+        let ctrl_s = Accelerator::new(Some(Modifiers::CONTROL), Code::KeyS);
+        let save = MenuItem::with_id("save", "Save", true, Some(ctrl_s));
+        let save_as = MenuItem::with_id("save", "Save As", true, None);
+        let settings = MenuItem::with_id("settings", "Settings", true, Some(ctrl_s));
+        let file = Submenu::new("File", true);
+        let menu = Menu::with_items(&[&file, &settings]).unwrap();
+        file.append_items(&[&save, &save_as]).unwrap();
+
+        let diagnostics = menu.diagnostics();
+        assert_eq!(diagnostics.item_count, 4);
+        assert!(diagnostics.hwnds.is_empty());
+        assert!(diagnostics.accelerators_registered);
+        assert_eq!(diagnostics.haccel_item_count, 2);
+        assert_eq!(diagnostics.duplicate_ids, vec![MenuId::new("save")]);
+        assert_eq!(
+            diagnostics.accelerator_conflicts,
+            vec![(ctrl_s, vec![MenuId::new("save"), MenuId::new("settings")])]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_rebinds_accelerators_in_haccel() {
+        use windows_sys::Win32::UI::{
+            Input::KeyboardAndMouse::{VK_O, VK_S},
+            WindowsAndMessaging::{CopyAcceleratorTableW, ACCEL},
+        };
+
+        let ctrl_s = Accelerator::new(Some(Modifiers::CONTROL), Code::KeyS);
+        let save = MenuItem::new("Save", true, Some(ctrl_s));
+        let menu = Menu::with_items(&[&save]).unwrap();
+        assert_eq!(save.accelerator(), Some(ctrl_s));
+
+        let keys = || {
+            let mut table: [ACCEL; 4] = unsafe { std::mem::zeroed() };
+            let len = unsafe { CopyAcceleratorTableW(menu.haccel() as _, table.as_mut_ptr(), 4) };
+            table[..len as usize]
+                .iter()
+                .map(|a| a.key)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(keys(), vec![VK_S]);
+
+        let ctrl_o = Accelerator::new(Some(Modifiers::CONTROL), Code::KeyO);
+        save.set_accelerator(Some(ctrl_o)).unwrap();
+        assert_eq!(save.accelerator(), Some(ctrl_o));
+        assert_eq!(keys(), vec![VK_O]);
+
+        save.set_accelerator(None).unwrap();
+        assert_eq!(save.accelerator(), None);
+        assert!(keys().is_empty());
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_reports_haccel_rebuilds() {
+        use crate::Submenu;
+        use std::{cell::RefCell, rc::Rc};
+
+        let menu = Menu::new();
+        let submenu = Submenu::new("File", true);
+        menu.append(&submenu).unwrap();
+
+        let handles = Rc::new(RefCell::new(Vec::new()));
+        let handles_ = handles.clone();
+        menu.on_haccel_changed(move |haccel| handles_.borrow_mut().push(haccel));
+
+        menu.append(&MenuItem::new("Plain", true, None)).unwrap();
+        assert!(handles.borrow().is_empty());
+
+        let save = MenuItem::new(
+            "Save",
+            true,
+            Some(Accelerator::new(Some(Modifiers::CONTROL), Code::KeyS)),
+        );
+        submenu.append(&save).unwrap();
+        assert_eq!(*handles.borrow(), vec![menu.haccel()]);
+        assert!(menu.haccel() != 0);
+
+        save.set_accelerator(None).unwrap();
+        assert_eq!(handles.borrow().len(), 2);
+        assert_eq!(handles.borrow().last(), Some(&menu.haccel()));
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_batches_updates() {
+        use std::{cell::RefCell, rc::Rc};
+
+        let menu = Menu::new();
+        let handles = Rc::new(RefCell::new(Vec::new()));
+        let handles_ = handles.clone();
+        menu.on_haccel_changed(move |haccel| handles_.borrow_mut().push(haccel));
+
+        let ctrl = |code| Some(Accelerator::new(Some(Modifiers::CONTROL), code));
+        {
+            let _outer = menu.begin_update();
+            menu.append(&MenuItem::new("Save", true, ctrl(Code::KeyS)))
+                .unwrap();
+            {
+                let _inner = menu.begin_update();
+                menu.append(&MenuItem::new("Open", true, ctrl(Code::KeyO)))
+                    .unwrap();
+            }
+            // only the outermost guard rebuilds the table
+            assert_eq!(menu.haccel(), 0);
+            assert!(handles.borrow().is_empty());
+        }
+        assert!(menu.haccel() != 0);
+        assert_eq!(*handles.borrow(), vec![menu.haccel()]);
+
+        menu.append_items(&[
+            &MenuItem::new("New", true, ctrl(Code::KeyN)),
+            &MenuItem::new("Quit", true, ctrl(Code::KeyQ)),
+        ])
+        .unwrap();
+        assert_eq!(handles.borrow().len(), 2);
+        assert_eq!(handles.borrow().last(), Some(&menu.haccel()));
+
+        // nothing to rebuild without accelerators
+        menu.append_items(&[&MenuItem::new("Plain", true, None)])
+            .unwrap();
+        assert_eq!(handles.borrow().len(), 2);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_replaces_submenu_items_in_one_update() {
+        use crate::Submenu;
+        use std::{cell::RefCell, rc::Rc};
+
+        let ctrl = |code| Some(Accelerator::new(Some(Modifiers::CONTROL), code));
+        let recent = Submenu::with_items(
+            "Recent",
+            true,
+            &[
+                &MenuItem::new("A", true, ctrl(Code::Digit1)),
+                &MenuItem::new("B", true, ctrl(Code::Digit2)),
+                &MenuItem::new("C", true, ctrl(Code::Digit3)),
+            ],
+        )
+        .unwrap();
+        let menu = Menu::with_items(&[&recent]).unwrap();
+        let handles = Rc::new(RefCell::new(Vec::new()));
+        let handles_ = handles.clone();
+        menu.on_haccel_changed(move |haccel| handles_.borrow_mut().push(haccel));
+
+        recent
+            .set_items(&[
+                &MenuItem::new("D", true, ctrl(Code::Digit4)),
+                &MenuItem::new("E", true, ctrl(Code::Digit5)),
+            ])
+            .unwrap();
+        assert_eq!(recent.items().len(), 2);
+        assert_eq!(*handles.borrow(), vec![menu.haccel()]);
+
+        // inside an update of the menu, the menu rebuilds the table
+        {
+            let _update = menu.begin_update();
+            recent
+                .set_items(&[&MenuItem::new("F", true, ctrl(Code::Digit6))])
+                .unwrap();
+            assert_eq!(handles.borrow().len(), 1);
+        }
+        assert_eq!(handles.borrow().len(), 2);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_renders_context_menu_to_bitmap() {
+        use crate::{dpi::PhysicalSize, CheckMenuItem, ContextMenu, PredefinedMenuItem, Submenu};
+
+        let menu = Menu::new();
+        menu.append_items(&[
+            &MenuItem::new("Open", true, None),
+            &MenuItem::new(
+                "Save",
+                false,
+                Some(Accelerator::new(Some(Modifiers::CONTROL), Code::KeyS)),
+            ),
+            &PredefinedMenuItem::separator(),
+            &CheckMenuItem::new("Wrap", true, true, None),
+            &Submenu::new("Recent", true),
+        ])
+        .unwrap();
+
+        let hwnd = create_window();
+        let size: PhysicalSize<u32> = unsafe { menu.measure(hwnd as _) }.unwrap().to_physical(1.0);
+
+        let (rgba, width, height) = menu.render_to_bitmap(MenuTheme::Light, 0).unwrap();
+        assert_eq!((width, height), (size.width, size.height));
+        assert_eq!(rgba.len(), (width * height * 4) as usize);
+        assert!(rgba.chunks_exact(4).any(|p| p[..3] != [0, 0, 0]));
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_suppresses_alt_activation() {
+        use windows_sys::Win32::UI::WindowsAndMessaging::{SC_KEYMENU, WM_SYSCOMMAND};
+
+        let menu = Menu::with_items(&[&MenuItem::new("Item", true, None)]).unwrap();
+        let hwnd = create_window() as isize;
+        assert!(menu.alt_activates_bar());
+        // not handled, so the default processing enters the menu loop
+        let alt = unsafe { menu.handle_message(hwnd, WM_SYSCOMMAND, SC_KEYMENU as _, 0) };
+        assert_eq!(alt, None);
+
+        menu.set_alt_activates_bar(false);
+        assert!(!menu.alt_activates_bar());
+        let alt = unsafe { menu.handle_message(hwnd, WM_SYSCOMMAND, SC_KEYMENU as _, 0) };
+        assert_eq!(alt, Some(0));
+        let mnemonic =
+            unsafe { menu.handle_message(hwnd, WM_SYSCOMMAND, SC_KEYMENU as _, 'f' as _) };
+        assert_eq!(mnemonic, Some(0));
+        assert!(!menu.is_tracking());
+
+        // the window menu
+        let space = unsafe { menu.handle_message(hwnd, WM_SYSCOMMAND, SC_KEYMENU as _, ' ' as _) };
+        assert_eq!(space, None);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_tracks_menu_loops() {
+        use windows_sys::Win32::UI::WindowsAndMessaging::{WM_ENTERMENULOOP, WM_EXITMENULOOP};
+
+        let menu = Menu::with_items(&[&MenuItem::new("Item", true, None)]).unwrap();
+        assert!(!menu.is_tracking());
+
+        let hwnd = create_window() as isize;
+        unsafe { menu.handle_message(hwnd, WM_ENTERMENULOOP, 0, 0) };
+        assert!(menu.is_tracking());
+        unsafe { menu.handle_message(hwnd, WM_EXITMENULOOP, 0, 0) };
+        assert!(!menu.is_tracking());
+
+        // the loops of context menus, possibly of other menus, aren't counted by the subclass
+        unsafe { menu.handle_message(hwnd, WM_ENTERMENULOOP, 1, 0) };
+        assert!(!menu.is_tracking());
+        unsafe { menu.handle_message(hwnd, WM_EXITMENULOOP, 1, 0) };
+        assert!(!menu.is_tracking());
+    }
+
+    /// Interactive check of the tracking state, run it with
+    /// `cargo test -- --ignored it_is_tracking_while_shown` and press Escape
+    /// once the context menu opens: the menu must be tracking while it is shown.
+    #[test]
+    #[ignore = "interactive"]
+    fn it_is_tracking_while_shown() {
+        use crate::{dpi::PhysicalPosition, ContextMenu};
+        use std::cell::RefCell;
+        use windows_sys::Win32::{
+            Foundation::HWND,
+            UI::WindowsAndMessaging::{KillTimer, SetTimer, ShowWindow, SW_SHOW},
+        };
+
+        thread_local! {
+            static MENU: Menu = Menu::with_items(&[&MenuItem::new("Item", true, None)]).unwrap();
+            static SAMPLES: RefCell<Vec<bool>> = const { RefCell::new(Vec::new()) };
+        }
+
+        // timers keep firing in the modal menu loop
+        unsafe extern "system" fn sample(_: HWND, _: u32, _: usize, _: u32) {
+            let tracking = MENU.with(|menu| menu.is_tracking());
+            SAMPLES.with_borrow_mut(|samples| samples.push(tracking));
+        }
+
+        let hwnd = create_window();
+        unsafe { ShowWindow(hwnd, SW_SHOW) };
+        let timer = unsafe { SetTimer(std::ptr::null_mut(), 0, 50, Some(sample)) };
+        MENU.with(|menu| unsafe {
+            menu.show_context_menu_for_hwnd(hwnd as _, Some(PhysicalPosition::new(10, 10).into()))
+        });
+        unsafe { KillTimer(std::ptr::null_mut(), timer) };
+
+        assert!(SAMPLES.with_borrow(|samples| samples.contains(&true)));
+        assert!(!MENU.with(|menu| menu.is_tracking()));
+    }
+
+    /// Interactive check of the initial selection of context menus, run it with
+    /// `cargo test -- --ignored it_opens_context_menu_with_selection`
+    /// and press Enter as soon as the menu opens: "Second" must be highlighted and activated.
+    #[test]
+    #[ignore = "interactive"]
+    fn it_opens_context_menu_with_selection() {
+        use crate::{dpi::PhysicalPosition, ContextMenu, ContextMenuOptions};
+        use windows_sys::Win32::UI::WindowsAndMessaging::{ShowWindow, SW_SHOW};
+
+        let menu = Menu::with_items(&[
+            &MenuItem::with_id("first", "First", true, None),
+            &MenuItem::with_id("second", "Second", true, None),
+        ])
+        .unwrap();
+
+        let hwnd = create_window();
+        unsafe { ShowWindow(hwnd, SW_SHOW) };
+
+        let activated = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let activated_ = activated.clone();
+        let observer =
+            crate::MenuEvent::add_observer(move |e| activated_.lock().unwrap().push(e.id.clone()));
+
+        let options = ContextMenuOptions::new()
+            .with_position(PhysicalPosition::new(10, 10))
+            .with_select(1);
+        assert!(unsafe { menu.show_context_menu_with_options_for_hwnd(hwnd as _, options) });
+        crate::MenuEvent::remove_observer(observer);
+        assert_eq!(
+            *activated.lock().unwrap(),
+            vec![crate::MenuId::new("second")]
+        );
+    }
+
+    /// Interactive check of non-wrapping navigation, run it with
+    /// `cargo test -- --ignored it_stops_navigation_at_the_ends`: press Down repeatedly,
+    /// the highlight must stop on "Last", then press Up repeatedly, it must stop on "First",
+    /// then press Escape to close the menu.
+    #[test]
+    #[ignore = "interactive"]
+    fn it_stops_navigation_at_the_ends() {
+        use crate::{dpi::PhysicalPosition, ContextMenu};
+        use windows_sys::Win32::UI::WindowsAndMessaging::{ShowWindow, SW_SHOW};
+
+        let menu = Menu::with_items(&[
+            &MenuItem::new("First", true, None),
+            &MenuItem::new("Middle", true, None),
+            &MenuItem::new("Last", true, None),
+        ])
+        .unwrap();
+        menu.set_nav_wrap(false);
+
+        let hwnd = create_window();
+        unsafe { ShowWindow(hwnd, SW_SHOW) };
+        unsafe { menu.init_for_hwnd(hwnd as _) }.unwrap();
+
+        let position = PhysicalPosition::new(10, 10);
+        assert!(!unsafe { menu.show_context_menu_for_hwnd(hwnd as _, Some(position.into())) });
+    }
+
+    /// Interactive check of the cascade side of submenus, run it with
+    /// `cargo test -- --ignored it_cascades_submenus_to_the_left`: the menu must open to the left
+    /// of the position, hover "More" and "Even More", both must open to the left of their parent,
+    /// then press Escape to close the menu.
+    #[test]
+    #[ignore = "interactive"]
+    fn it_cascades_submenus_to_the_left() {
+        use crate::{dpi::PhysicalPosition, CascadeSide, ContextMenu, Submenu};
+        use windows_sys::Win32::UI::WindowsAndMessaging::{ShowWindow, SW_SHOW};
+
+        let even_more =
+            Submenu::with_items("Even More", true, &[&MenuItem::new("Last", true, None)]).unwrap();
+        let more = Submenu::with_items("More", true, &[&even_more]).unwrap();
+        let submenu =
+            Submenu::with_items("Root", true, &[&MenuItem::new("Item", true, None), &more])
+                .unwrap();
+        submenu.set_cascade_side(CascadeSide::Left);
+
+        let hwnd = create_window();
+        unsafe { ShowWindow(hwnd, SW_SHOW) };
+        let position = PhysicalPosition::new(400, 10);
+        unsafe { submenu.show_context_menu_for_hwnd(hwnd as _, Some(position.into())) };
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_cascades_nested_submenus_to_the_chosen_side() {
+        use std::cell::{Cell, RefCell};
+
+        use crate::{dpi::PhysicalPosition, CascadeSide, ContextMenu, ContextMenuOptions, Submenu};
+        use windows_sys::Win32::{
+            Foundation::{HWND, RECT},
+            UI::{
+                Input::KeyboardAndMouse::VK_RIGHT,
+                WindowsAndMessaging::{
+                    EndMenu, KillTimer, PostMessageW, SetTimer, ShowWindow, SW_SHOW, WM_KEYDOWN,
+                },
+            },
+        };
+
+        thread_local! {
+            static OWNER: Cell<isize> = const { Cell::new(0) };
+            static TICKS: Cell<u32> = const { Cell::new(0) };
+            static POPUPS: RefCell<Vec<RECT>> = const { RefCell::new(Vec::new()) };
+        }
+
+        // runs in the menu loop, opens the highlighted submenu then closes the menus
+        unsafe extern "system" fn tick(_: HWND, _: u32, _: usize, _: u32) {
+            let ticks = TICKS.replace(TICKS.get() + 1);
+            let open = popups();
+            if open.len() >= 2 || ticks > 40 {
+                POPUPS.set(open);
+                EndMenu();
+            } else if open.len() == 1 {
+                PostMessageW(OWNER.get() as _, WM_KEYDOWN, VK_RIGHT as _, 0);
+            }
+        }
+
+        let more =
+            Submenu::with_items("More", true, &[&MenuItem::new("Last", true, None)]).unwrap();
+        let root = Submenu::with_items("Root", true, &[&more]).unwrap();
+        let hwnd = create_window();
+        unsafe { ShowWindow(hwnd, SW_SHOW) };
+        OWNER.set(hwnd as _);
+
+        for side in [CascadeSide::Left, CascadeSide::Right] {
+            root.set_cascade_side(side);
+            TICKS.set(0);
+            let options = ContextMenuOptions::new()
+                .with_position(PhysicalPosition::new(400, 200))
+                .with_select(0);
+            unsafe {
+                let timer = SetTimer(std::ptr::null_mut(), 0, 50, Some(tick));
+                root.show_context_menu_with_options_for_hwnd(hwnd as _, options);
+                KillTimer(std::ptr::null_mut(), timer);
+            }
+
+            let popups = POPUPS.take();
+            assert_eq!(popups.len(), 2, "the nested submenu didn't open");
+            let (nested, parent) = (popups[0], popups[1]);
+            let center = |r: RECT| (r.left + r.right) / 2;
+            match side {
+                CascadeSide::Left => assert!(center(nested) < center(parent)),
+                _ => assert!(center(nested) > center(parent)),
+            }
+        }
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_shows_context_menu_at_physical_position() {
+        use std::cell::{Cell, RefCell};
+
+        use windows_sys::Win32::{
+            Foundation::{HWND, POINT, RECT},
+            Graphics::Gdi::ClientToScreen,
+            UI::WindowsAndMessaging::{EndMenu, KillTimer, SetTimer, ShowWindow, SW_SHOW},
+        };
+
+        thread_local! {
+            static TICKS: Cell<u32> = const { Cell::new(0) };
+            static POPUPS: RefCell<Vec<RECT>> = const { RefCell::new(Vec::new()) };
+        }
+
+        // runs in the menu loop, records the popup then closes it
+        unsafe extern "system" fn tick(_: HWND, _: u32, _: usize, _: u32) {
+            let ticks = TICKS.replace(TICKS.get() + 1);
+            let open = popups();
+            if !open.is_empty() || ticks > 40 {
+                POPUPS.set(open);
+                EndMenu();
+            }
+        }
+
+        let menu = Menu::with_items(&[&MenuItem::new("Item", true, None)]).unwrap();
+        let hwnd = create_window();
+        unsafe { ShowWindow(hwnd, SW_SHOW) };
+
+        unsafe {
+            let timer = SetTimer(std::ptr::null_mut(), 0, 50, Some(tick));
+            assert!(!menu.show_context_menu_for_hwnd_at(hwnd as _, 20, 30));
+            KillTimer(std::ptr::null_mut(), timer);
+        }
+
+        let popups = POPUPS.take();
+        assert_eq!(popups.len(), 1, "the context menu didn't open");
+        // the position is only offset by the client area origin, whatever the dpi
+        let mut pt = POINT { x: 20, y: 30 };
+        unsafe { ClientToScreen(hwnd, &mut pt) };
+        let popup = popups[0];
+        assert_eq!(popup.top, pt.y);
+        // the popup opens on either side of the position depending on the system alignment
+        assert!(popup.left == pt.x || popup.right == pt.x);
+    }
+
+    /// Interactive check of context menus at screen coordinates, run it with
+    /// `cargo test -- --ignored it_shows_context_menu_at_screen_pos`:
+    /// the menu must open at the mouse cursor, then press Escape to close it.
+    #[test]
+    #[ignore = "interactive"]
+    fn it_shows_context_menu_at_screen_pos() {
+        use crate::{dpi::PhysicalPosition, ContextMenu};
+        use windows_sys::Win32::{
+            Foundation::POINT,
+            UI::WindowsAndMessaging::{GetCursorPos, ShowWindow, SW_SHOW},
+        };
+
+        let menu = Menu::with_items(&[&MenuItem::new("Item", true, None)]).unwrap();
+
+        let hwnd = create_window();
+        unsafe { ShowWindow(hwnd, SW_SHOW) };
+
+        let mut pt = POINT { x: 0, y: 0 };
+        unsafe { GetCursorPos(&mut pt) };
+        let position = PhysicalPosition::new(pt.x, pt.y);
+        assert!(!unsafe { menu.show_context_menu_at_screen_pos(hwnd as _, position.into()) });
+    }
+
+    /// Interactive check of blocking context menus, run it with
+    /// `cargo test -- --ignored it_returns_selected_id_from_blocking_context_menu`
+    /// and click "Second".
+    #[test]
+    #[ignore = "interactive"]
+    fn it_returns_selected_id_from_blocking_context_menu() {
+        use crate::{dpi::PhysicalPosition, ContextMenu, MenuId};
+        use windows_sys::Win32::UI::WindowsAndMessaging::{ShowWindow, SW_SHOW};
+
+        let menu = Menu::with_items(&[
+            &MenuItem::with_id("first", "First", true, None),
+            &MenuItem::with_id("second", "Second", true, None),
+        ])
+        .unwrap();
+
+        let hwnd = create_window();
+        unsafe { ShowWindow(hwnd, SW_SHOW) };
+
+        let position = PhysicalPosition::new(10, 10);
+        let selected =
+            unsafe { menu.show_context_menu_for_hwnd_blocking(hwnd as _, Some(position.into())) };
+        assert_eq!(selected, Some(MenuId::new("second")));
+    }
+
+    /// Interactive check of context menus kept open on check, run it with
+    /// `cargo test -- --ignored it_keeps_context_menu_open_on_check`,
+    /// click "Bold" then "Italic": the menu must open again at the same place after each click,
+    /// then press Escape to close it.
+    #[test]
+    #[ignore = "interactive"]
+    fn it_keeps_context_menu_open_on_check() {
+        use crate::{dpi::PhysicalPosition, CheckMenuItem, ContextMenu, ContextMenuOptions};
+        use windows_sys::Win32::UI::WindowsAndMessaging::{ShowWindow, SW_SHOW};
+
+        let bold = CheckMenuItem::new("Bold", true, false, None);
+        let italic = CheckMenuItem::new("Italic", true, false, None);
+        let menu = Menu::with_items(&[&bold, &italic]).unwrap();
+
+        let hwnd = create_window();
+        unsafe { ShowWindow(hwnd, SW_SHOW) };
+
+        let options = ContextMenuOptions::new()
+            .with_position(PhysicalPosition::new(10, 10))
+            .with_keep_open_on_check(true);
+        assert!(unsafe { menu.show_context_menu_with_options_for_hwnd(hwnd as _, options) });
+        assert!(bold.is_checked());
+        assert!(italic.is_checked());
+    }
+
+    /// Interactive check of one-shot context menus, run it with
+    /// `cargo test -- --ignored it_destroys_handles_of_one_shot_context_menus`:
+    /// the menu must open and close by itself three times.
+    #[test]
+    #[ignore = "interactive"]
+    fn it_destroys_handles_of_one_shot_context_menus() {
+        use crate::{dpi::PhysicalPosition, ContextMenu, PredefinedMenuItem, Submenu};
+        use windows_sys::Win32::{
+            Foundation::HWND,
+            System::Threading::{GetCurrentProcess, GetGuiResources, GR_USEROBJECTS},
+            UI::WindowsAndMessaging::{EndMenu, KillTimer, SetTimer, ShowWindow, SW_SHOW},
+        };
+
+        unsafe extern "system" fn dismiss(hwnd: HWND, _: u32, id: usize, _: u32) {
+            KillTimer(hwnd, id);
+            EndMenu();
+        }
+
+        let hwnd = create_window();
+        unsafe { ShowWindow(hwnd, SW_SHOW) };
+
+        let show_once = || {
+            let menu = Menu::with_items(&[
+                &MenuItem::new(
+                    "First",
+                    true,
+                    Some(Accelerator::new(Some(Modifiers::CONTROL), Code::KeyF)),
+                ),
+                &PredefinedMenuItem::separator(),
+                &Submenu::with_items("More", true, &[&MenuItem::new("Second", true, None)])
+                    .unwrap(),
+            ])
+            .unwrap();
+            let position = PhysicalPosition::new(10, 10);
+            unsafe {
+                SetTimer(hwnd, 1, 300, Some(dismiss));
+                menu.show_context_menu_for_hwnd_once(hwnd as _, Some(position.into()))
+            }
+        };
+        let user_objects = || unsafe { GetGuiResources(GetCurrentProcess(), GR_USEROBJECTS) };
+
+        // the first menu may create objects that are kept for the process, like its window class
+        assert_eq!(show_once(), None);
+        let before = user_objects();
+        assert_eq!(show_once(), None);
+        assert_eq!(show_once(), None);
+        assert_eq!(user_objects(), before);
+    }
+
+    /// Interactive check of dismissing context menus, run it with
+    /// `cargo test -- --ignored it_dismisses_context_menu`:
+    /// the menu must open and close by itself after half a second.
+    #[test]
+    #[ignore = "interactive"]
+    fn it_dismisses_context_menu() {
+        use crate::{dpi::PhysicalPosition, ContextMenu};
+        use std::cell::RefCell;
+        use windows_sys::Win32::{
+            Foundation::HWND,
+            UI::WindowsAndMessaging::{KillTimer, SetTimer, ShowWindow, SW_SHOW},
+        };
+
+        thread_local! {
+            static MENU: RefCell<Option<Menu>> = const { RefCell::new(None) };
+        }
+
+        unsafe extern "system" fn dismiss(hwnd: HWND, _: u32, id: usize, _: u32) {
+            KillTimer(hwnd, id);
+            MENU.with_borrow(|menu| menu.as_ref().unwrap().dismiss());
+        }
+
+        let menu = Menu::with_items(&[&MenuItem::with_id("first", "First", true, None)]).unwrap();
+        // nothing is tracking the menu yet
+        menu.dismiss();
+        MENU.with_borrow_mut(|m| *m = Some(menu.clone()));
+
+        let hwnd = create_window();
+        unsafe {
+            ShowWindow(hwnd, SW_SHOW);
+            SetTimer(hwnd, 1, 500, Some(dismiss));
+        }
+
+        let position = PhysicalPosition::new(10, 10);
+        assert!(!unsafe { menu.show_context_menu_for_hwnd(hwnd as _, Some(position.into())) });
+    }
+
+    #[test]
+    #[cfg(feature = "raw-window-handle")]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_inits_for_window_handles() {
+        use crate::{
+            raw_window_handle::{
+                HandleError, HasWindowHandle, RawWindowHandle, WebWindowHandle, Win32WindowHandle,
+                WindowHandle,
+            },
+            ContextMenu, Error,
+        };
+        use std::num::NonZeroIsize;
+
+        struct Window(RawWindowHandle);
+
+        impl HasWindowHandle for Window {
+            fn window_handle(&self) -> Result<WindowHandle<'_>, HandleError> {
+                Ok(unsafe { WindowHandle::borrow_raw(self.0) })
+            }
+        }
+
+        let hwnd = NonZeroIsize::new(create_window() as _).unwrap();
+        let window = Window(Win32WindowHandle::new(hwnd).into());
+        let menu = Menu::with_items(&[&MenuItem::new("Item", true, None)]).unwrap();
+        menu.init_for_window(&window).unwrap();
+        assert!(unsafe { menu.is_visible_on_hwnd(hwnd.get()) });
+        assert!(matches!(
+            menu.init_for_window(&window),
+            Err(Error::AlreadyInitialized)
+        ));
+        menu.remove_for_window(&window).unwrap();
+        assert!(!unsafe { menu.is_visible_on_hwnd(hwnd.get()) });
+
+        let web = Window(WebWindowHandle::new(1).into());
+        assert!(matches!(
+            menu.init_for_window(&web),
+            Err(Error::NotAWin32Window)
+        ));
+        assert!(matches!(
+            menu.show_context_menu_for_window(&web, None),
+            Err(Error::NotAWin32Window)
+        ));
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_translates_accelerators_without_the_message_loop() {
+        use crate::Error;
+        use windows_sys::Win32::UI::{
+            Input::KeyboardAndMouse::VK_F5,
+            WindowsAndMessaging::{
+                PeekMessageW, PostMessageW, MSG, PM_REMOVE, WM_KEYDOWN, WM_NULL,
+            },
+        };
+
+        let hwnd = create_window();
+        let menu = Menu::with_items(&[&MenuItem::new(
+            "Refresh",
+            true,
+            Some(Accelerator::new(None, Code::F5)),
+        )])
+        .unwrap();
+        assert!(matches!(
+            unsafe { menu.enable_accelerators_for_hwnd(hwnd as _) },
+            Err(Error::NotInitialized)
+        ));
+
+        unsafe { menu.init_for_hwnd(hwnd as _) }.unwrap();
+        unsafe { menu.enable_accelerators_for_hwnd(hwnd as _) }.unwrap();
+        let press_f5 = || unsafe {
+            PostMessageW(hwnd, WM_KEYDOWN, VK_F5 as _, 0);
+            let mut msg: MSG = std::mem::zeroed();
+            assert!(PeekMessageW(&mut msg, hwnd, 0, 0, PM_REMOVE) != 0);
+            msg.message
+        };
+        assert_eq!(press_f5(), WM_NULL);
+
+        assert!(menu.disable_accelerators_for_hwnd(hwnd as _));
+        assert!(!menu.disable_accelerators_for_hwnd(hwnd as _));
+        assert_eq!(press_f5(), WM_KEYDOWN);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_hides_items_keeping_their_position() {
+        use crate::{ContextMenu, Submenu};
+        use windows_sys::Win32::UI::WindowsAndMessaging::{
+            DispatchMessageW, GetMenuItemCount, GetMenuStringW, PeekMessageW, MF_BYPOSITION, MSG,
+            PM_REMOVE, WM_INITMENUPOPUP,
+        };
+
+        let labels = |hmenu: isize| {
+            let count = unsafe { GetMenuItemCount(hmenu as _) };
+            (0..count)
+                .map(|position| {
+                    let mut label = [0u16; 32];
+                    let len = unsafe {
+                        GetMenuStringW(
+                            hmenu as _,
+                            position as _,
+                            label.as_mut_ptr(),
+                            label.len() as _,
+                            MF_BYPOSITION,
+                        )
+                    };
+                    String::from_utf16_lossy(&label[..len as usize])
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let a = MenuItem::new("A", true, None);
+        let b = MenuItem::new("B", true, None);
+        let c = MenuItem::new("C", true, None);
+        let menu = Menu::with_items(&[&a, &b, &c]).unwrap();
+        assert!(b.is_visible());
+        b.set_visible(false);
+        c.set_visible(false);
+        assert!(!b.is_visible());
+        assert_eq!(labels(menu.hpopupmenu()), ["A"]);
+        assert_eq!(menu.items().len(), 3);
+        assert_eq!(menu.visible_items().len(), 1);
+
+        // positions are the ones of `items`, hidden items included
+        menu.insert(&MenuItem::new("D", true, None), 2).unwrap();
+        assert_eq!(labels(menu.hpopupmenu()), ["A", "D"]);
+
+        // shown items are inserted back once the menu is displayed
+        c.set_visible(true);
+        b.set_visible(true);
+        let hwnd = create_window();
+        unsafe { menu.init_for_hwnd(hwnd as _) }.unwrap();
+        assert_eq!(labels(menu.hpopupmenu()), ["A", "B", "D", "C"]);
+        assert_eq!(labels(menu.hmenu_bar()), ["A", "B", "D", "C"]);
+
+        // and right after for menu bars
+        b.set_visible(false);
+        assert_eq!(labels(menu.hmenu_bar()), ["A", "D", "C"]);
+        b.set_visible(true);
+        unsafe {
+            let mut msg: MSG = std::mem::zeroed();
+            while PeekMessageW(&mut msg, hwnd, 0, 0, PM_REMOVE) != 0 {
+                DispatchMessageW(&msg);
+            }
+        }
+        assert_eq!(labels(menu.hmenu_bar()), ["A", "B", "D", "C"]);
+
+        let x = MenuItem::new("X", true, None);
+        let submenu =
+            Submenu::with_items("Sub", true, &[&x, &MenuItem::new("Y", true, None)]).unwrap();
+        menu.append(&submenu).unwrap();
+        x.set_visible(false);
+        assert_eq!(labels(submenu.hpopupmenu()), ["Y"]);
+        assert_eq!(submenu.visible_items().len(), 1);
+        x.set_visible(true);
+        unsafe { menu.handle_message(hwnd as _, WM_INITMENUPOPUP, submenu.hpopupmenu() as _, 0) };
+        assert_eq!(labels(submenu.hpopupmenu()), ["X", "Y"]);
+
+        // hiding and showing an item changes the menu and is reported to subscribers
+        let seen = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let seen_c = seen.clone();
+        menu.subscribe_state(move |id, state| {
+            seen_c.borrow_mut().push((id.clone(), state.visible));
+        });
+        let generation = menu.generation();
+        x.set_visible(false);
+        assert_ne!(menu.generation(), generation);
+        let generation = menu.generation();
+        x.set_visible(false);
+        assert_eq!(menu.generation(), generation);
+        x.set_visible(true);
+        assert_ne!(menu.generation(), generation);
+        assert_eq!(
+            *seen.borrow(),
+            [(x.id().clone(), false), (x.id().clone(), true)]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn it_shows_hidden_items_with_their_current_icon() {
+        use crate::{icon::Icon, ContextMenu};
+        use windows_sys::Win32::UI::WindowsAndMessaging::{
+            GetMenuItemInfoW, MENUITEMINFOW, MFT_OWNERDRAW, MIIM_BITMAP, MIIM_FTYPE,
+        };
+
+        let info = |menu: &Menu, item: &MenuItem| unsafe {
+            let mut info: MENUITEMINFOW = std::mem::zeroed();
+            info.cbSize = std::mem::size_of::<MENUITEMINFOW>() as _;
+            info.fMask = MIIM_BITMAP | MIIM_FTYPE;
+            let id = item.inner.borrow().internal_id();
+            assert!(GetMenuItemInfoW(menu.hpopupmenu() as _, id, false.into(), &mut info) != 0);
+            info
+        };
+
+        let item = MenuItem::new("Open", true, None);
+        let menu = Menu::with_items(&[&item]).unwrap();
+        assert!(info(&menu, &item).hbmpItem.is_null());
+
+        // changes made while the item is hidden apply once it is shown
+        item.set_visible(false);
+        item.set_icon(Some(Icon::from_rgba(vec![255; 4 * 4], 2, 2).unwrap()));
+        menu.set_item_min_height(40);
+        item.set_visible(true);
+        unsafe { menu.init_for_hwnd(create_window() as _) }.unwrap();
+
+        let info = info(&menu, &item);
+        assert!(!info.hbmpItem.is_null());
+        assert_ne!(info.fType & MFT_OWNERDRAW, 0);
+    }
+
+    /// Creates a hidden top-level window for tests that need a real `HWND`.
+    fn create_window() -> windows_sys::Win32::Foundation::HWND {
+        use windows_sys::Win32::UI::WindowsAndMessaging::{CreateWindowExW, WS_OVERLAPPEDWINDOW};
+
+        let class = "STATIC\0".encode_utf16().collect::<Vec<u16>>();
+        let hwnd = unsafe {
+            CreateWindowExW(
+                0,
+                class.as_ptr(),
+                std::ptr::null(),
+                WS_OVERLAPPEDWINDOW,
+                0,
+                0,
+                100,
+                100,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                std::ptr::null(),
+            )
+        };
+        assert!(!hwnd.is_null());
+        hwnd
+    }
+
+    /// The visible menu windows of this thread, the most recently opened first.
+    fn popups() -> Vec<windows_sys::Win32::Foundation::RECT> {
+        use windows_sys::Win32::{
+            Foundation::{HWND, LPARAM, RECT},
+            System::Threading::GetCurrentThreadId,
+            UI::WindowsAndMessaging::{
+                EnumThreadWindows, GetClassNameW, GetWindowRect, IsWindowVisible,
+            },
+        };
+
+        unsafe extern "system" fn push(hwnd: HWND, rects: LPARAM) -> i32 {
+            let mut class = [0u16; 16];
+            let len = GetClassNameW(hwnd, class.as_mut_ptr(), class.len() as _);
+            if String::from_utf16_lossy(&class[..len as usize]) == "#32768"
+                && IsWindowVisible(hwnd) != 0
+            {
+                let mut rect = std::mem::zeroed();
+                GetWindowRect(hwnd, &mut rect);
+                (*(rects as *mut Vec<RECT>)).push(rect);
+            }
+            1
+        }
+
+        let mut rects = Vec::new();
+        unsafe { EnumThreadWindows(GetCurrentThreadId(), Some(push), &mut rects as *mut _ as _) };
+        rects
+    }
+
+    /// Passes the messages posted to `hwnd` to [`Menu::handle_message`], like a custom loop does.
+    fn dispatch_posted(menu: &Menu, hwnd: isize) {
+        use windows_sys::Win32::UI::WindowsAndMessaging::{PeekMessageW, MSG, PM_REMOVE};
+
+        unsafe {
+            let mut msg: MSG = std::mem::zeroed();
+            while PeekMessageW(&mut msg, hwnd as _, 0, 0, PM_REMOVE) != 0 {
+                menu.handle_message(hwnd, msg.message, msg.wParam, msg.lParam);
+            }
+        }
+    }
 }
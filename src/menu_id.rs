@@ -1,6 +1,13 @@
 use std::{convert::Infallible, str::FromStr};
 
 /// An unique id that is associated with a menu or a menu item.
+///
+/// Equality, ordering and hashing only depend on the id string, so they are stable across runs
+/// and a `MenuId` can be stored or used as a `HashMap` key.
+///
+/// To use a typed command enum, convert it into a `MenuId` through its [`Display`](std::fmt::Display)
+/// implementation (any `T: ToString` converts into a `MenuId`) and convert it back
+/// with [`MenuId::parse`] using its [`FromStr`] implementation.
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Default, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MenuId(pub String);
@@ -10,6 +17,25 @@ impl MenuId {
     pub fn new<S: AsRef<str>>(id: S) -> Self {
         Self(id.as_ref().to_string())
     }
+
+    /// Parses this id into another type, usually a command enum that was converted into this id.
+    ///
+    /// ```
+    /// # use muda_win::MenuId;
+    /// # #[derive(Debug, PartialEq)]
+    /// # enum Command { Save }
+    /// # impl std::str::FromStr for Command {
+    /// #     type Err = ();
+    /// #     fn from_str(s: &str) -> Result<Self, ()> {
+    /// #         match s { "save" => Ok(Command::Save), _ => Err(()) }
+    /// #     }
+    /// # }
+    /// let id = MenuId::new("save");
+    /// assert_eq!(id.parse::<Command>(), Ok(Command::Save));
+    /// ```
+    pub fn parse<T: FromStr>(&self) -> Result<T, T::Err> {
+        self.0.parse()
+    }
 }
 
 impl AsRef<str> for MenuId {
@@ -82,4 +108,38 @@ mod test {
         assert_eq!(&MenuId::new("t"), &MenuId::new("t"));
         assert_eq!(MenuId::new("t").as_ref(), "t");
     }
+
+    #[test]
+    fn parses_into_command_enum() {
+        #[derive(Debug, PartialEq)]
+        enum Command {
+            Open,
+            Save,
+        }
+
+        impl std::fmt::Display for Command {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    Command::Open => write!(f, "open"),
+                    Command::Save => write!(f, "save"),
+                }
+            }
+        }
+
+        impl std::str::FromStr for Command {
+            type Err = String;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    "open" => Ok(Command::Open),
+                    "save" => Ok(Command::Save),
+                    _ => Err(format!("unknown command: {s}")),
+                }
+            }
+        }
+
+        assert_eq!(MenuId::from(Command::Save).parse(), Ok(Command::Save));
+        assert_eq!(MenuId::new("open").parse(), Ok(Command::Open));
+        assert!(MenuId::new("close").parse::<Command>().is_err());
+    }
 }
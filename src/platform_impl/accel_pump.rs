@@ -0,0 +1,96 @@
+//! Accelerators without access to the message loop, see [`Menu::enable_accelerators_for_hwnd`](crate::Menu::enable_accelerators_for_hwnd).
+//!
+//! Accelerators only work if the message loop calls `TranslateAcceleratorW`, which isn't
+//! possible when the loop belongs to a library like `winit`. Instead, a thread-local
+//! `WH_GETMESSAGE` hook translates the keyboard messages of the registered windows, and of
+//! their child windows, as they are removed from the queue and turns the translated ones
+//! into `WM_NULL` so they aren't dispatched.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    rc::Weak,
+};
+
+use windows_sys::Win32::{
+    Foundation::{HWND, LPARAM, LRESULT, WPARAM},
+    System::Threading::GetCurrentThreadId,
+    UI::WindowsAndMessaging::{
+        CallNextHookEx, GetAncestor, SetWindowsHookExW, TranslateAcceleratorW, UnhookWindowsHookEx,
+        GA_ROOT, HC_ACTION, HHOOK, MSG, PM_REMOVE, WH_GETMESSAGE, WM_KEYFIRST, WM_KEYLAST, WM_NULL,
+    },
+};
+
+use super::{Hwnd, Menu};
+
+thread_local! {
+    static WINDOWS: RefCell<HashMap<Hwnd, Weak<RefCell<Menu>>>> = RefCell::new(HashMap::new());
+    static GET_MESSAGE_HOOK: Cell<HHOOK> = const { Cell::new(std::ptr::null_mut()) };
+}
+
+pub(crate) fn register(hwnd: Hwnd, menu: Weak<RefCell<Menu>>) {
+    WINDOWS.with_borrow_mut(|windows| {
+        windows.retain(|_, menu| menu.strong_count() > 0);
+        windows.insert(hwnd, menu);
+    });
+
+    if GET_MESSAGE_HOOK.get().is_null() {
+        let hook = unsafe {
+            SetWindowsHookExW(
+                WH_GETMESSAGE,
+                Some(get_message_proc),
+                std::ptr::null_mut(),
+                GetCurrentThreadId(),
+            )
+        };
+        GET_MESSAGE_HOOK.set(hook);
+    }
+}
+
+/// Stops translating the accelerators of `menu` for `hwnd`, returns whether they were translated.
+pub(crate) fn unregister(hwnd: Hwnd, menu: *const Menu) -> bool {
+    let removed = WINDOWS.with_borrow_mut(|windows| {
+        let registered = windows
+            .get(&hwnd)
+            .and_then(|m| m.upgrade())
+            .is_some_and(|m| std::ptr::eq(m.as_ptr(), menu));
+        if registered {
+            windows.remove(&hwnd);
+        }
+        // the windows of menus that are being dropped
+        windows.retain(|_, menu| menu.strong_count() > 0);
+        registered
+    });
+
+    if WINDOWS.with_borrow(|windows| windows.is_empty()) {
+        let hook = GET_MESSAGE_HOOK.replace(std::ptr::null_mut());
+        if !hook.is_null() {
+            unsafe { UnhookWindowsHookEx(hook) };
+        }
+    }
+
+    removed
+}
+
+unsafe extern "system" fn get_message_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    // messages that are only peeked will be seen again when they are removed
+    if code == HC_ACTION as i32 && wparam == PM_REMOVE as usize {
+        let msg = &mut *(lparam as *mut MSG);
+        if (WM_KEYFIRST..=WM_KEYLAST).contains(&msg.message) {
+            let root: HWND = GetAncestor(msg.hwnd, GA_ROOT);
+            let menu = WINDOWS.with_borrow(|windows| {
+                windows.get(&(root as Hwnd)).and_then(|menu| menu.upgrade())
+            });
+            // the menu is mutably borrowed while its context menu is shown
+            let haccel = menu
+                .as_ref()
+                .and_then(|menu| menu.try_borrow().ok().map(|menu| menu.haccel()))
+                .unwrap_or(0);
+            if haccel != 0 && TranslateAcceleratorW(root, haccel as _, msg) != 0 {
+                msg.message = WM_NULL;
+            }
+        }
+    }
+
+    CallNextHookEx(std::ptr::null_mut(), code, wparam, lparam)
+}
@@ -11,17 +11,22 @@ use crate::accelerator::{Accelerator, AcceleratorParseError};
 impl Accelerator {
     // Convert a hotkey to an accelerator.
     pub fn to_accel(&self, menu_id: u16) -> crate::Result<ACCEL> {
-        let mut virt_key = FVIRTKEY;
-        let key_mods: Modifiers = self.mods;
-        if key_mods.contains(Modifiers::CONTROL) {
-            virt_key |= FCONTROL;
-        }
-        if key_mods.contains(Modifiers::ALT) {
-            virt_key |= FALT;
-        }
-        if key_mods.contains(Modifiers::SHIFT) {
-            virt_key |= FSHIFT;
-        }
+        let (virt_key, raw_key) = self.raw_accel()?;
+        Ok(ACCEL {
+            fVirt: virt_key,
+            key: raw_key,
+            cmd: menu_id,
+        })
+    }
+
+    /// The `fVirt` flags and virtual-key code of this accelerator, as passed to `CreateAcceleratorTableW`
+    /// in the [`ACCEL`] entries built by [`Accelerator::to_accel`].
+    ///
+    /// The flags always contain `FVIRTKEY`, plus `FCONTROL`, `FALT` and `FSHIFT` for the modifiers.
+    ///
+    /// Returns an error if the key has no virtual-key code.
+    pub fn raw_accel(&self) -> crate::Result<(u8, u16)> {
+        let mut virt_key = accel_flags(self.mods);
 
         let vk_code = key_to_vk(&self.key)?;
         let mod_code = vk_code >> 8;
@@ -36,12 +41,24 @@ impl Accelerator {
         }
         let raw_key = vk_code & 0x00ff;
 
-        Ok(ACCEL {
-            fVirt: virt_key,
-            key: raw_key,
-            cmd: menu_id,
-        })
+        Ok((virt_key, raw_key))
+    }
+}
+
+/// The `fVirt` flags of an [`ACCEL`] entry for `mods`, `FVIRTKEY` plus `FCONTROL`, `FALT`
+/// and `FSHIFT` for the modifiers, other modifiers can't be part of an accelerator table.
+pub(crate) fn accel_flags(mods: Modifiers) -> u8 {
+    let mut flags = FVIRTKEY;
+    if mods.contains(Modifiers::CONTROL) {
+        flags |= FCONTROL;
     }
+    if mods.contains(Modifiers::ALT) {
+        flags |= FALT;
+    }
+    if mods.contains(Modifiers::SHIFT) {
+        flags |= FSHIFT;
+    }
+    flags
 }
 
 // used to build accelerators table from Key
@@ -162,18 +179,20 @@ fn key_to_vk(key: &Code) -> Result<VIRTUAL_KEY, AcceleratorParseError> {
     })
 }
 
+/// Modifiers are always written in the canonical `Ctrl+Alt+Shift+Windows` order,
+/// regardless of the order they were parsed in.
 impl fmt::Display for Accelerator {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let key_mods: Modifiers = self.mods;
         if key_mods.contains(Modifiers::CONTROL) {
             write!(f, "Ctrl+")?;
         }
-        if key_mods.contains(Modifiers::SHIFT) {
-            write!(f, "Shift+")?;
-        }
         if key_mods.contains(Modifiers::ALT) {
             write!(f, "Alt+")?;
         }
+        if key_mods.contains(Modifiers::SHIFT) {
+            write!(f, "Shift+")?;
+        }
         if key_mods.contains(Modifiers::SUPER) {
             write!(f, "Windows+")?;
         }
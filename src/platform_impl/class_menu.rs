@@ -0,0 +1,104 @@
+//! Default menus for window classes.
+//!
+//! Win32 only allows a window class to reference a menu resource by name through
+//! `WNDCLASS::lpszMenuName`, so menus built at runtime can't be set as a class default.
+//! Instead, a thread-local `WH_CBT` hook hands the menu to every new top-level window
+//! of a registered class through its `CREATESTRUCTW::hMenu`, right before it is created.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    rc::Weak,
+};
+
+use windows_sys::Win32::{
+    Foundation::{HWND, LPARAM, LRESULT, WPARAM},
+    System::Threading::GetCurrentThreadId,
+    UI::{
+        Shell::SetWindowSubclass,
+        WindowsAndMessaging::{
+            CallNextHookEx, GetClassWord, SetWindowsHookExW, UnhookWindowsHookEx, CBT_CREATEWNDW,
+            GCW_ATOM, HCBT_CREATEWND, HHOOK, WH_CBT, WS_CHILD,
+        },
+    },
+};
+
+use super::{dwrefdata_from_obj, menu_subclass_proc, Menu, MENU_SUBCLASS_ID};
+
+thread_local! {
+    static CLASS_MENUS: RefCell<HashMap<u16, Weak<RefCell<Menu>>>> = RefCell::new(HashMap::new());
+    static CBT_HOOK: Cell<HHOOK> = const { Cell::new(std::ptr::null_mut()) };
+}
+
+pub(crate) fn register(class_atom: u16, menu: Weak<RefCell<Menu>>) -> crate::Result<()> {
+    if class_atom == 0 {
+        return Err(crate::Error::InvalidWindowClass);
+    }
+
+    CLASS_MENUS.with_borrow_mut(|menus| menus.insert(class_atom, menu));
+
+    if CBT_HOOK.get().is_null() {
+        let hook = unsafe {
+            SetWindowsHookExW(
+                WH_CBT,
+                Some(cbt_hook_proc),
+                std::ptr::null_mut(),
+                GetCurrentThreadId(),
+            )
+        };
+        CBT_HOOK.set(hook);
+    }
+
+    Ok(())
+}
+
+/// Removes the default menu of `class_atom` if it is `menu`, returns whether it was removed.
+pub(crate) fn unregister(class_atom: u16, menu: &Weak<RefCell<Menu>>) -> bool {
+    let removed = CLASS_MENUS.with_borrow_mut(|menus| {
+        if menus.get(&class_atom).is_some_and(|m| m.ptr_eq(menu)) {
+            menus.remove(&class_atom);
+            true
+        } else {
+            false
+        }
+    });
+
+    if CLASS_MENUS.with_borrow(|menus| menus.is_empty()) {
+        let hook = CBT_HOOK.replace(std::ptr::null_mut());
+        if !hook.is_null() {
+            unsafe { UnhookWindowsHookEx(hook) };
+        }
+    }
+
+    removed
+}
+
+unsafe extern "system" fn cbt_hook_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    if code == HCBT_CREATEWND as i32 {
+        let hwnd = wparam as HWND;
+        let cs = &mut *(*(lparam as *mut CBT_CREATEWNDW)).lpcs;
+
+        // menus can only be set on top-level windows that didn't specify their own menu
+        if cs.style as u32 & WS_CHILD == 0 && cs.hMenu.is_null() {
+            let class_atom = GetClassWord(hwnd, GCW_ATOM);
+            let menu =
+                CLASS_MENUS.with_borrow(|menus| menus.get(&class_atom).and_then(|m| m.upgrade()));
+            if let Some(menu) = menu {
+                if let Ok(menu) = menu.try_borrow() {
+                    cs.hMenu = menu.hmenu;
+                    menu.hwnds
+                        .borrow_mut()
+                        .insert(hwnd as _, menu.default_theme());
+                    SetWindowSubclass(
+                        hwnd,
+                        Some(menu_subclass_proc),
+                        MENU_SUBCLASS_ID,
+                        dwrefdata_from_obj(&*menu),
+                    );
+                }
+            }
+        }
+    }
+
+    CallNextHookEx(std::ptr::null_mut(), code, wparam, lparam)
+}
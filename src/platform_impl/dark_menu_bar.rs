@@ -24,6 +24,11 @@ use windows_sys::{
 pub const WM_UAHDRAWMENU: u32 = 0x0091;
 pub const WM_UAHDRAWMENUITEM: u32 = 0x0092;
 
+pub const BACKGROUND_COLOR: u32 = 2829099;
+pub const SELECTED_BACKGROUND_COLOR: u32 = 4276545;
+pub const TEXT_COLOR: u32 = 16777215;
+pub const DISABLED_TEXT_COLOR: u32 = 7171437;
+
 #[repr(C)]
 struct UAHMENUITEMMETRICS0 {
     cx: u32,
@@ -71,7 +76,6 @@ impl Drop for HBrush {
 }
 
 fn background_brush() -> HBRUSH {
-    const BACKGROUND_COLOR: u32 = 2829099;
     static mut BACKGROUND_BRUSH: Option<HBrush> = None;
     unsafe {
         if BACKGROUND_BRUSH.is_none() {
@@ -82,7 +86,6 @@ fn background_brush() -> HBRUSH {
 }
 
 fn selected_background_brush() -> HBRUSH {
-    const SELECTED_BACKGROUND_COLOR: u32 = 4276545;
     static mut SELECTED_BACKGROUND_BRUSH: Option<HBrush> = None;
     unsafe {
         if SELECTED_BACKGROUND_BRUSH.is_none() {
@@ -221,9 +224,6 @@ pub fn draw(hwnd: super::Hwnd, msg: u32, _wparam: WPARAM, lparam: LPARAM) {
 
                 FillRect((*pudmi).um.hdc, &(*pudmi).dis.rcItem, bg_brush);
 
-                const TEXT_COLOR: u32 = 16777215;
-                const DISABLED_TEXT_COLOR: u32 = 7171437;
-
                 let text_brush = match i_text_state_id {
                     MPI_DISABLED => DISABLED_TEXT_COLOR,
                     _ => TEXT_COLOR,
@@ -246,7 +246,12 @@ pub fn draw(hwnd: super::Hwnd, msg: u32, _wparam: WPARAM, lparam: LPARAM) {
 }
 
 pub fn should_use_dark_mode(hwnd: super::Hwnd) -> bool {
-    should_apps_use_dark_mode() && !is_high_contrast() && is_dark_mode_allowed_for_window(hwnd as _)
+    should_app_use_dark_mode() && is_dark_mode_allowed_for_window(hwnd as _)
+}
+
+/// Whether the app should be dark, regardless of any window.
+pub fn should_app_use_dark_mode() -> bool {
+    should_apps_use_dark_mode() && !is_high_contrast()
 }
 
 static HUXTHEME: LazyLock<isize> =
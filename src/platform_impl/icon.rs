@@ -1,16 +1,18 @@
-use std::{fmt, io, mem, path::Path, sync::Arc};
+use std::{ffi::OsStr, fmt, fs, io, mem, path::Path, sync::Arc};
 
 use windows_sys::{
     core::PCWSTR,
     Win32::{
-        Foundation::RECT,
+        Foundation::{HMODULE, RECT},
         Graphics::Gdi::{
-            CreateCompatibleDC, CreateDIBSection, DeleteDC, GetDC, ReleaseDC, SelectObject,
-            BITMAPINFO, BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS, HBITMAP,
+            CreateCompatibleDC, CreateDIBSection, DeleteDC, DeleteObject, GetDC, GetDIBits,
+            GetObjectW, ReleaseDC, SelectObject, BITMAP, BITMAPINFO, BITMAPINFOHEADER, BI_RGB,
+            DIB_RGB_COLORS, HBITMAP,
         },
         UI::WindowsAndMessaging::{
-            CreateIcon, DestroyIcon, DrawIconEx, LoadImageW, DI_NORMAL, HICON, IMAGE_ICON,
-            LR_DEFAULTSIZE, LR_LOADFROMFILE,
+            CopyIcon, CreateIcon, CreateIconFromResourceEx, DestroyIcon, DrawIconEx, GetIconInfo,
+            GetSystemMetrics, LoadImageW, DI_NORMAL, HICON, ICONINFO, IMAGE_ICON, LR_DEFAULTCOLOR,
+            LR_DEFAULTSIZE, LR_LOADFROMFILE, SM_CXSMICON, SM_CYSMICON,
         },
     },
 };
@@ -56,6 +58,13 @@ impl RgbaIcon {
     }
 }
 
+/// The magic bytes of PNG files.
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+/// The header of ICO files, a reserved zero word followed by `1` for icons.
+const ICO_SIGNATURE: &[u8] = &[0, 0, 1, 0];
+/// The icon format version `CreateIconFromResourceEx` expects.
+const ICON_RESOURCE_VERSION: u32 = 0x0003_0000;
+
 #[derive(Debug)]
 struct RaiiIcon {
     handle: HICON,
@@ -69,14 +78,19 @@ pub(crate) struct WinIcon {
 unsafe impl Send for WinIcon {}
 
 impl WinIcon {
+    pub fn handle(&self) -> HICON {
+        self.inner.handle
+    }
+
+    /// Draws the icon into a new bitmap of the small icon size, which follows the system DPI.
     pub unsafe fn to_hbitmap(&self) -> HBITMAP {
         let hdc = CreateCompatibleDC(std::ptr::null_mut());
 
         let rc = RECT {
             left: 0,
             top: 0,
-            right: 16,
-            bottom: 16,
+            right: GetSystemMetrics(SM_CXSMICON),
+            bottom: GetSystemMetrics(SM_CYSMICON),
         };
 
         let mut bitmap_info: BITMAPINFO = std::mem::zeroed();
@@ -120,6 +134,41 @@ impl WinIcon {
         hbitmap
     }
 
+    /// Reads back the pixels of the icon as straight (not premultiplied) RGBA,
+    /// returns `None` for monochrome icons or if the pixels can't be read.
+    pub fn to_rgba(&self) -> Option<(Vec<u8>, u32, u32)> {
+        unsafe {
+            let mut info: ICONINFO = std::mem::zeroed();
+            if GetIconInfo(self.handle(), &mut info) == 0 {
+                return None;
+            }
+
+            let rgba = if info.hbmColor.is_null() {
+                None
+            } else {
+                read_bitmap(info.hbmColor).map(|(mut pixels, width, height)| {
+                    // icons without an alpha channel use the mask for transparency
+                    if pixels.chunks_exact(PIXEL_SIZE).all(|p| p[3] == 0) {
+                        if let Some((mask, _, _)) = read_bitmap(info.hbmMask) {
+                            for (pixel, mask) in pixels
+                                .chunks_exact_mut(PIXEL_SIZE)
+                                .zip(mask.chunks_exact(PIXEL_SIZE))
+                            {
+                                pixel[3] = if mask[0] == 0 { u8::MAX } else { 0 };
+                            }
+                        }
+                    }
+                    (pixels, width, height)
+                })
+            };
+
+            DeleteObject(info.hbmColor);
+            DeleteObject(info.hbmMask);
+
+            rgba
+        }
+    }
+
     pub fn from_rgba(rgba: Vec<u8>, width: u32, height: u32) -> Result<Self, BadIcon> {
         let rgba_icon = RgbaIcon::from_rgba(rgba, width, height)?;
         rgba_icon.into_windows_icon()
@@ -136,19 +185,70 @@ impl WinIcon {
         path: P,
         size: Option<(u32, u32)>,
     ) -> Result<Self, BadIcon> {
-        // width / height of 0 along with LR_DEFAULTSIZE tells windows to load the default icon size
+        let path = path.as_ref();
+        let bytes = fs::read(path).map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => BadIcon::FileNotFound {
+                path: path.to_path_buf(),
+            },
+            _ => BadIcon::OsError(e),
+        })?;
+
+        let extension = path.extension().and_then(OsStr::to_str);
+        let is_extension = |ext: &str| extension.is_some_and(|e| e.eq_ignore_ascii_case(ext));
+        if bytes.starts_with(PNG_SIGNATURE)
+            || (!bytes.starts_with(ICO_SIGNATURE) && is_extension("png"))
+        {
+            Self::from_png(&bytes, size)
+        } else if bytes.starts_with(ICO_SIGNATURE) || is_extension("ico") {
+            Self::from_ico_path(path, size)
+        } else {
+            Err(BadIcon::UnsupportedFormat {
+                path: path.to_path_buf(),
+            })
+        }
+    }
+
+    /// Decodes a PNG image, Windows reads PNG data the way it reads PNG-compressed icon images.
+    fn from_png(bytes: &[u8], size: Option<(u32, u32)>) -> Result<Self, BadIcon> {
+        // a width / height of 0 without LR_DEFAULTSIZE loads the size of the image
         let (width, height) = size.unwrap_or((0, 0));
 
-        let wide_path = util::encode_wide(path.as_ref());
+        let handle = unsafe {
+            CreateIconFromResourceEx(
+                bytes.as_ptr(),
+                bytes.len() as u32,
+                true.into(),
+                ICON_RESOURCE_VERSION,
+                width as i32,
+                height as i32,
+                LR_DEFAULTCOLOR,
+            )
+        };
+        if !handle.is_null() {
+            Ok(WinIcon::from_handle(handle))
+        } else {
+            Err(BadIcon::OsError(io::Error::last_os_error()))
+        }
+    }
+
+    fn from_ico_path(path: &Path, size: Option<(u32, u32)>) -> Result<Self, BadIcon> {
+        // windows picks the image of the file closest to the requested size, by default
+        // the size menu icons are drawn at
+        let (width, height) = size.map_or_else(
+            || unsafe { (GetSystemMetrics(SM_CXSMICON), GetSystemMetrics(SM_CYSMICON)) },
+            |(width, height)| (width as i32, height as i32),
+        );
+
+        let wide_path = util::encode_wide(path);
 
         let handle = unsafe {
             LoadImageW(
                 std::ptr::null_mut(),
                 wide_path.as_ptr(),
                 IMAGE_ICON,
-                width as i32,
-                height as i32,
-                LR_DEFAULTSIZE | LR_LOADFROMFILE,
+                width,
+                height,
+                LR_LOADFROMFILE,
             )
         };
         if !handle.is_null() {
@@ -158,15 +258,33 @@ impl WinIcon {
         }
     }
 
+    /// Copies `hicon`, the caller keeps ownership of it.
+    pub(crate) unsafe fn from_hicon(hicon: HICON) -> Result<Self, BadIcon> {
+        let handle = CopyIcon(hicon);
+        if !handle.is_null() {
+            Ok(WinIcon::from_handle(handle))
+        } else {
+            Err(BadIcon::OsError(io::Error::last_os_error()))
+        }
+    }
+
     pub(crate) fn from_resource(
         resource_id: u16,
         size: Option<(u32, u32)>,
+    ) -> Result<Self, BadIcon> {
+        Self::from_resource_in_module(util::get_instance_handle(), resource_id, size)
+    }
+
+    pub(crate) fn from_resource_in_module(
+        module: HMODULE,
+        resource_id: u16,
+        size: Option<(u32, u32)>,
     ) -> Result<Self, BadIcon> {
         // width / height of 0 along with LR_DEFAULTSIZE tells windows to load the default icon size
         let (width, height) = size.unwrap_or((0, 0));
         let handle = unsafe {
             LoadImageW(
-                util::get_instance_handle(),
+                module,
                 resource_id as PCWSTR,
                 IMAGE_ICON,
                 width as i32,
@@ -182,6 +300,53 @@ impl WinIcon {
     }
 }
 
+/// Reads the pixels of `hbitmap` as 32bpp RGBA, top-down.
+unsafe fn read_bitmap(hbitmap: HBITMAP) -> Option<(Vec<u8>, u32, u32)> {
+    let mut bitmap: BITMAP = std::mem::zeroed();
+    if GetObjectW(
+        hbitmap,
+        std::mem::size_of::<BITMAP>() as _,
+        &mut bitmap as *mut _ as _,
+    ) == 0
+    {
+        return None;
+    }
+
+    let (width, height) = (bitmap.bmWidth, bitmap.bmHeight);
+    let mut bitmap_info: BITMAPINFO = std::mem::zeroed();
+    bitmap_info.bmiHeader.biSize = std::mem::size_of::<BITMAPINFOHEADER>() as _;
+    bitmap_info.bmiHeader.biWidth = width;
+    // negative height for a top-down bitmap
+    bitmap_info.bmiHeader.biHeight = -height;
+    bitmap_info.bmiHeader.biPlanes = 1;
+    bitmap_info.bmiHeader.biBitCount = 32;
+    bitmap_info.bmiHeader.biCompression = BI_RGB as _;
+
+    let mut pixels = vec![0u8; width as usize * height as usize * PIXEL_SIZE];
+    let hdc = GetDC(std::ptr::null_mut());
+    let lines = GetDIBits(
+        hdc,
+        hbitmap,
+        0,
+        height as _,
+        pixels.as_mut_ptr() as _,
+        &mut bitmap_info,
+        DIB_RGB_COLORS,
+    );
+    ReleaseDC(std::ptr::null_mut(), hdc);
+
+    if lines == 0 {
+        return None;
+    }
+
+    // BGRA to RGBA
+    for pixel in pixels.chunks_exact_mut(PIXEL_SIZE) {
+        pixel.swap(0, 2);
+    }
+
+    Some((pixels, width as _, height as _))
+}
+
 impl Drop for RaiiIcon {
     fn drop(&mut self) {
         unsafe { DestroyIcon(self.handle) };
@@ -0,0 +1,87 @@
+//! Menus built with the Win32 API, see [`Menu::copy_from_hmenu`](crate::Menu::copy_from_hmenu).
+//!
+//! A menu of this crate owns a menu bar and a popup menu with the same items, so the items of
+//! an existing `HMENU` are read and recreated as items of this crate rather than adopting it.
+
+use windows_sys::Win32::UI::WindowsAndMessaging::{
+    GetMenuItemCount, GetMenuItemInfoW, HMENU, MENUITEMINFOW, MFS_CHECKED, MFS_DISABLED,
+    MFT_RADIOCHECK, MFT_SEPARATOR, MIIM_CHECKMARKS, MIIM_FTYPE, MIIM_ID, MIIM_STATE, MIIM_STRING,
+    MIIM_SUBMENU,
+};
+
+use crate::{
+    accelerator::Accelerator, CheckMenuItem, IsMenuItem, MenuItem, PredefinedMenuItem, Submenu,
+};
+
+/// Recreates the items of `hmenu` and its submenus, the id of an item is its command id.
+pub(crate) unsafe fn items(hmenu: HMENU) -> crate::Result<Vec<Box<dyn IsMenuItem>>> {
+    let count = GetMenuItemCount(hmenu);
+    if count < 0 {
+        return Err(crate::Error::OsError(std::io::Error::last_os_error()));
+    }
+
+    let mut children: Vec<Box<dyn IsMenuItem>> = Vec::with_capacity(count as usize);
+    // consecutive radio items form a group, named after the first one
+    let mut radio_group = None;
+    for position in 0..count as u32 {
+        let mut info: MENUITEMINFOW = std::mem::zeroed();
+        info.cbSize = std::mem::size_of::<MENUITEMINFOW>() as _;
+        info.fMask =
+            MIIM_FTYPE | MIIM_STATE | MIIM_ID | MIIM_SUBMENU | MIIM_STRING | MIIM_CHECKMARKS;
+        if GetMenuItemInfoW(hmenu, position, true.into(), &mut info) == 0 {
+            return Err(crate::Error::OsError(std::io::Error::last_os_error()));
+        }
+
+        if info.fType & MFT_RADIOCHECK == 0 {
+            radio_group = None;
+        }
+
+        if info.fType & MFT_SEPARATOR != 0 {
+            children.push(Box::new(PredefinedMenuItem::separator()));
+            continue;
+        }
+
+        // `cch` is the length of the text without the nul terminator
+        let mut text = vec![0u16; info.cch as usize + 1];
+        info.dwTypeData = text.as_mut_ptr();
+        info.cch += 1;
+        GetMenuItemInfoW(hmenu, position, true.into(), &mut info);
+        let text = String::from_utf16_lossy(&text[..info.cch as usize]);
+
+        let enabled = info.fState & MFS_DISABLED == 0;
+        if !info.hSubMenu.is_null() {
+            let submenu = Submenu::new(&text, enabled);
+            for item in items(info.hSubMenu)? {
+                submenu.append(item.as_ref())?;
+            }
+            children.push(Box::new(submenu));
+            continue;
+        }
+
+        // the accelerator is shown after a tab, it is kept in the label if it can't be parsed
+        let (text, accelerator) = match text.split_once('\t') {
+            Some((label, accelerator)) => match accelerator.parse::<Accelerator>() {
+                Ok(accelerator) => (label.to_string(), Some(accelerator)),
+                Err(_) => (text.clone(), None),
+            },
+            None => (text.clone(), None),
+        };
+        let id = info.wID.to_string();
+        // any item can be checked, only radio items and items with check mark bitmaps are meant to be
+        let checkable = info.fType & MFT_RADIOCHECK != 0
+            || !info.hbmpChecked.is_null()
+            || !info.hbmpUnchecked.is_null();
+        if checkable {
+            let checked = info.fState & MFS_CHECKED != 0;
+            let item = CheckMenuItem::with_id(id.clone(), text, enabled, checked, accelerator);
+            if info.fType & MFT_RADIOCHECK != 0 {
+                let group = radio_group.get_or_insert_with(|| format!("{hmenu:?}:{id}"));
+                crate::platform_impl::radio::join(&item.inner, group);
+            }
+            children.push(Box::new(item));
+        } else {
+            children.push(Box::new(MenuItem::with_id(id, text, enabled, accelerator)));
+        }
+    }
+    Ok(children)
+}
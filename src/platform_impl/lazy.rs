@@ -0,0 +1,39 @@
+//! Deferred population of submenus, see [`Submenu::set_lazy_builder`](crate::Submenu::set_lazy_builder).
+//!
+//! The builder is kept in the [`MenuChild`] of its submenu, the subclass finds the submenu
+//! that is about to open by its popup `HMENU` in `WM_INITMENUPOPUP`, before the items are drawn.
+
+use std::{cell::RefCell, rc::Rc};
+
+use super::MenuChild;
+use crate::Submenu;
+
+pub(crate) type LazyBuilder = dyn Fn(&Submenu);
+
+/// Builds `submenu` if it has a builder that didn't run yet, or if it rebuilds on open.
+pub(crate) fn build(submenu: &Rc<RefCell<MenuChild>>) {
+    // built the next time if the submenu is in use now
+    let Ok(mut inner) = submenu.try_borrow_mut() else {
+        return;
+    };
+    let Some(builder) = inner
+        .lazy_builder
+        .clone()
+        .filter(|_| !inner.lazy_built || inner.rebuild_on_open)
+    else {
+        return;
+    };
+
+    // removing its own items only fails on another thread, where the builder must not run either
+    if inner.set_items(&[]).is_err() {
+        return;
+    }
+    inner.lazy_built = true;
+    let id = Rc::new(inner.id().clone());
+    drop(inner);
+
+    (builder.0)(&Submenu {
+        id,
+        inner: submenu.clone(),
+    });
+}
@@ -1,46 +1,77 @@
+mod accel_pump;
 mod accelerator;
+mod class_menu;
 mod dark_menu_bar;
 mod icon;
+pub(crate) mod import;
+pub(crate) mod lazy;
+mod msg_filter;
+mod nav_wrap;
+mod owner_draw;
+pub(crate) mod radio;
+mod render;
+mod root_state;
+pub(crate) mod sort;
+mod title_click;
 mod util;
+mod visibility;
 
 use self::dark_menu_bar::{WM_UAHDRAWMENU, WM_UAHDRAWMENUITEM};
 pub(crate) use self::icon::WinIcon as PlatformIcon;
+use self::msg_filter::MsgFilter;
+pub(crate) use self::owner_draw::measure_menu;
+use self::owner_draw::OwnerDrawData;
+pub(crate) use self::root_state::StateChange;
+use self::root_state::{HaccelObserver, ItemHandler, RootState, RootStates, StateSubscriber};
 use std::cell::Ref;
 
 use crate::{
     accelerator::Accelerator,
-    dpi::Position,
+    dpi::{PhysicalPosition, PhysicalSize, Position, Size},
     icon::{Icon, NativeIcon},
     items::PredefinedMenuItemType,
     items::*,
     util::{AddOp, Counter},
-    AboutMetadata, IsMenuItem, MenuEvent, MenuId, MenuItemKind, MenuItemType, MenuTheme,
+    AboutMetadata, ContextMenuOptions, IsMenuItem, ItemState, MenuDiagnostics, MenuEvent, MenuId,
+    MenuItemKind, MenuItemType, MenuTheme, SubmenuEvent, SubmenuEventKind, SubscriptionId,
 };
 use std::{
-    cell::{RefCell, RefMut},
-    collections::HashMap,
+    cell::{Cell, RefCell, RefMut},
+    collections::{HashMap, HashSet},
     fmt::Debug,
     rc::Rc,
 };
-use util::{decode_wide, encode_wide, Accel};
+pub(crate) use util::{check_ui_thread, escape_mnemonics, strip_mnemonic};
+use util::{decode_wide, encode_wide, Accel, Callback};
 use windows_sys::Win32::{
-    Foundation::{LPARAM, LRESULT, POINT, WPARAM},
-    Graphics::Gdi::{ClientToScreen, HBITMAP},
+    Foundation::{LPARAM, LRESULT, POINT, RECT, WPARAM},
+    Graphics::Gdi::{
+        ClientToScreen, GetMonitorInfoW, MonitorFromRect, ScreenToClient, HBITMAP, MONITORINFO,
+        MONITOR_DEFAULTTONEAREST,
+    },
+    System::Diagnostics::Debug::MessageBeep,
     UI::{
+        Controls::{DRAWITEMSTRUCT, MEASUREITEMSTRUCT, ODT_MENU},
         Input::KeyboardAndMouse::{
-            GetActiveWindow, SendInput, INPUT, INPUT_KEYBOARD, KEYEVENTF_KEYUP, VK_CONTROL,
+            GetActiveWindow, SendInput, INPUT, INPUT_KEYBOARD, KEYEVENTF_KEYUP, VK_CONTROL, VK_DOWN,
         },
         Shell::{DefSubclassProc, RemoveWindowSubclass, SetWindowSubclass},
         WindowsAndMessaging::{
             AppendMenuW, CreateAcceleratorTableW, CreateMenu, CreatePopupMenu,
             DestroyAcceleratorTable, DestroyMenu, DrawMenuBar, EnableMenuItem, GetCursorPos,
-            GetMenu, GetMenuItemInfoW, InsertMenuW, PostMessageW, PostQuitMessage, RemoveMenu,
-            SendMessageW, SetForegroundWindow, SetMenu, SetMenuItemInfoW, ShowWindow,
-            TrackPopupMenu, HACCEL, HMENU, MENUITEMINFOW, MFS_CHECKED, MFS_DISABLED, MF_BYCOMMAND,
+            GetMenu, GetMenuItemCount, GetMenuItemID, GetMenuItemInfoW, GetSubMenu, GetSystemMenu,
+            GetSystemMetrics, GetWindowThreadProcessId, InsertMenuW, IsWindowVisible, PostMessageW,
+            PostQuitMessage, RemoveMenu, SendMessageW, SetForegroundWindow, SetMenu,
+            SetMenuItemInfoW, SetWindowPos, ShowWindow, TrackPopupMenu, TrackPopupMenuEx, HACCEL,
+            HMENU, MB_OK, MENUITEMINFOW, MFS_CHECKED, MFS_DISABLED, MFT_SEPARATOR, MF_BYCOMMAND,
             MF_BYPOSITION, MF_CHECKED, MF_DISABLED, MF_ENABLED, MF_GRAYED, MF_POPUP, MF_SEPARATOR,
-            MF_STRING, MF_UNCHECKED, MIIM_BITMAP, MIIM_STATE, MIIM_STRING, SW_HIDE, SW_MAXIMIZE,
-            SW_MINIMIZE, TPM_LEFTALIGN, TPM_RETURNCMD, WM_CLOSE, WM_COMMAND, WM_NCACTIVATE,
-            WM_NCPAINT,
+            MF_STRING, MF_UNCHECKED, MIIM_BITMAP, MIIM_FTYPE, MIIM_STATE, MIIM_STRING, SC_CLOSE,
+            SC_KEYMENU, SM_MENUDROPALIGNMENT, SWP_FRAMECHANGED, SWP_NOACTIVATE, SWP_NOMOVE,
+            SWP_NOSIZE, SWP_NOZORDER, SW_HIDE, SW_MAXIMIZE, SW_MINIMIZE, TPMPARAMS, TPM_LEFTALIGN,
+            TPM_RETURNCMD, TPM_RIGHTALIGN, TPM_TOPALIGN, TPM_VERTICAL, WM_CANCELMODE, WM_CLOSE,
+            WM_COMMAND, WM_DRAWITEM, WM_ENTERMENULOOP, WM_EXITMENULOOP, WM_INITMENUPOPUP,
+            WM_KEYDOWN, WM_MEASUREITEM, WM_MENUSELECT, WM_NCACTIVATE, WM_NCPAINT, WM_SETREDRAW,
+            WM_SYSCOMMAND, WM_UNINITMENUPOPUP,
         },
     },
 };
@@ -95,7 +126,16 @@ macro_rules! inner_menu_child_and_flags {
     }};
 }
 
-type AccelWrapper = (HACCEL, HashMap<u32, Accel>);
+/// The accelerator table of a root menu and its entries. While an update of the menu is
+/// in progress, see [`Menu::begin_update`], rebuilding the table is deferred and the last
+/// field tells whether it has to be rebuilt when the update ends.
+type AccelWrapper = (HACCEL, HashMap<u32, Accel>, Option<bool>);
+/// Appended to the label of items that open a dialog.
+const ELLIPSIS: char = '\u{2026}';
+
+type AcceleratorInterceptor = dyn Fn(&Accelerator) -> bool;
+type RawMessageHook = dyn Fn(u32, usize, isize) -> Option<isize>;
+type AfterAction = dyn Fn();
 
 #[derive(Debug)]
 pub(crate) struct Menu {
@@ -106,6 +146,29 @@ pub(crate) struct Menu {
     hwnds: Rc<RefCell<HashMap<Hwnd, MenuTheme>>>,
     haccel_store: Rc<RefCell<AccelWrapper>>,
     children: Vec<Rc<RefCell<MenuChild>>>,
+    accelerator_interceptor: Option<Callback<AcceleratorInterceptor>>,
+    raw_message_hooks: HashMap<isize, Callback<RawMessageHook>>,
+    highlighted_items: HashMap<Hwnd, MenuId>,
+    /// Windows whose menu bar is hidden but revealed while the menu is activated with the keyboard.
+    hidden_bars: HashSet<Hwnd>,
+    default_theme: MenuTheme,
+    root_state: Rc<RootState>,
+    read_only: bool,
+    beep_on_disabled: bool,
+    nav_wrap: bool,
+    /// Installs the hook that stops the navigation from wrapping, see [`nav_wrap`].
+    nav_msg_filter: Option<MsgFilter>,
+    alt_activates_bar: bool,
+    /// Menu loops of the bar and context menu in progress, see [`Menu::is_tracking`](crate::Menu::is_tracking).
+    ///
+    /// Shared so it is updated by the subclass while the context menu holds `&mut self`.
+    tracking: Rc<Cell<u32>>,
+    /// Nested [`Menu::begin_update`] calls that didn't end yet.
+    update_depth: u32,
+    /// Windows whose painting is stopped until the outermost update ends.
+    updating_hwnds: Vec<Hwnd>,
+    /// The thread that created this menu, the only one that can change it.
+    thread_id: u32,
 }
 
 impl Drop for Menu {
@@ -126,6 +189,9 @@ impl Drop for Menu {
         }
 
         remove_from_children_stores(self.internal_id, &self.children);
+        for child in &self.children {
+            root_state::detach(&mut child.borrow_mut(), self.internal_id);
+        }
 
         for child in &self.children {
             let child_ = child.borrow();
@@ -147,6 +213,12 @@ impl Drop for Menu {
             }
             DestroyMenu(self.hmenu);
             DestroyMenu(self.hpopupmenu);
+            // the items no longer reference the table, see `remove_from_children_stores`
+            let haccel =
+                std::mem::replace(&mut self.haccel_store.borrow_mut().0, std::ptr::null_mut());
+            if !haccel.is_null() {
+                DestroyAcceleratorTable(haccel);
+            }
         }
     }
 }
@@ -159,9 +231,24 @@ impl Menu {
             internal_id,
             hmenu: unsafe { CreateMenu() },
             hpopupmenu: unsafe { CreatePopupMenu() },
-            haccel_store: Rc::new(RefCell::new((std::ptr::null_mut(), HashMap::new()))),
+            haccel_store: Rc::new(RefCell::new((std::ptr::null_mut(), HashMap::new(), None))),
             children: Vec::new(),
             hwnds: Rc::new(RefCell::new(HashMap::new())),
+            accelerator_interceptor: None,
+            raw_message_hooks: HashMap::new(),
+            highlighted_items: HashMap::new(),
+            hidden_bars: HashSet::new(),
+            default_theme: MenuTheme::Auto,
+            root_state: Rc::default(),
+            read_only: false,
+            beep_on_disabled: true,
+            nav_wrap: true,
+            nav_msg_filter: None,
+            alt_activates_bar: true,
+            tracking: Rc::default(),
+            update_depth: 0,
+            updating_hwnds: Vec::new(),
+            thread_id: util::owner_thread_id(),
         }
     }
 
@@ -170,13 +257,15 @@ impl Menu {
     }
 
     pub fn add_menu_item(&mut self, item: &dyn IsMenuItem, op: AddOp) -> crate::Result<()> {
+        util::check_thread(self.thread_id)?;
         let (child, mut flags) = inner_menu_child_and_flags!(item);
 
         {
-            child
-                .borrow_mut()
+            let mut child_ = child.borrow_mut();
+            child_
                 .root_menu_haccel_stores
                 .insert(self.internal_id, self.haccel_store.clone());
+            root_state::attach(&mut child_, self.internal_id, &self.root_state);
         }
 
         {
@@ -185,7 +274,7 @@ impl Menu {
                 flags |= MF_GRAYED;
             }
 
-            let mut text = child_.text.clone();
+            let mut text = child_.display_text();
 
             if let Some(accelerator) = &child_.accelerator {
                 let accel_str = accelerator.to_string();
@@ -198,6 +287,9 @@ impl Menu {
                     child_.internal_id(),
                     accelerator,
                 )?;
+                if self.update_depth == 0 {
+                    self.root_state.notify_haccel_changed(self.haccel());
+                }
             }
 
             let id = child_.internal_id() as usize;
@@ -210,16 +302,18 @@ impl Menu {
                         AppendMenuW(self.hpopupmenu, flags, id, text.as_ptr());
                     }
                     AddOp::Insert(position) => {
+                        let native_position =
+                            visibility::native_position(self.hmenu, &self.children, position);
                         InsertMenuW(
                             self.hmenu,
-                            position as _,
+                            native_position as _,
                             flags | MF_BYPOSITION,
                             id,
                             text.as_ptr(),
                         );
                         InsertMenuW(
                             self.hpopupmenu,
-                            position as _,
+                            native_position as _,
                             flags | MF_BYPOSITION,
                             id,
                             text.as_ptr(),
@@ -232,7 +326,7 @@ impl Menu {
         {
             let child_ = child.borrow();
 
-            if child_.item_type() == MenuItemType::Icon {
+            if child_.item_type() == MenuItemType::Icon || child_.icon.is_some() {
                 let hbitmap = child_
                     .icon
                     .as_ref()
@@ -245,11 +339,29 @@ impl Menu {
                     SetMenuItemInfoW(self.hpopupmenu, child_.internal_id, false.into(), &info);
                 };
             }
+
+            if child_.radio_group.is_some() {
+                unsafe {
+                    radio::set_radio_check(self.hmenu, child_.internal_id);
+                    radio::set_radio_check(self.hpopupmenu, child_.internal_id);
+                };
+            }
+
+            unsafe {
+                if child_.is_owner_drawn_in(true) {
+                    owner_draw::set_owner_drawn(self.hmenu, child_.internal_id(), true);
+                }
+                if child_.is_owner_drawn_in(false) {
+                    owner_draw::set_owner_drawn(self.hpopupmenu, child_.internal_id(), true);
+                }
+            }
         }
 
         // redraw the menu bar
-        for hwnd in self.hwnds.borrow().keys() {
-            unsafe { DrawMenuBar(*hwnd as _) };
+        if self.update_depth == 0 {
+            for hwnd in self.hwnds.borrow().keys() {
+                unsafe { DrawMenuBar(*hwnd as _) };
+            }
         }
 
         {
@@ -258,6 +370,10 @@ impl Menu {
                 .parents_hemnu
                 .push((self.hmenu, Some(self.hwnds.clone())));
             child_.parents_hemnu.push((self.hpopupmenu, None));
+            if !child_.visible {
+                child_.hide_in(self.hmenu);
+                child_.hide_in(self.hpopupmenu);
+            }
         }
 
         {
@@ -267,19 +383,51 @@ impl Menu {
             }
         }
 
+        self.root_state.bump_generation();
+
         Ok(())
     }
 
     pub fn remove(&mut self, item: &dyn IsMenuItem) -> crate::Result<()> {
+        self.remove_child(item)?;
+
+        // redraw the menu bar
+        if self.update_depth == 0 {
+            for hwnd in self.hwnds.borrow().keys() {
+                unsafe { DrawMenuBar(*hwnd as _) };
+            }
+        }
+
+        self.root_state.bump_generation();
+
+        Ok(())
+    }
+
+    pub fn clear(&mut self) -> crate::Result<Vec<MenuItemKind>> {
+        let items = self.items();
+        for item in &items {
+            self.remove_child(item.as_ref())?;
+        }
+
+        // redraw the menu bar once all the items are gone
+        if self.update_depth == 0 {
+            for hwnd in self.hwnds.borrow().keys() {
+                unsafe { DrawMenuBar(*hwnd as _) };
+            }
+        }
+
+        self.root_state.bump_generation();
+
+        Ok(items)
+    }
+
+    /// Removes `item` from the native menus and the children without redrawing.
+    fn remove_child(&mut self, item: &dyn IsMenuItem) -> crate::Result<()> {
+        util::check_thread(self.thread_id)?;
         let id = item.child().borrow().internal_id();
         unsafe {
             RemoveMenu(self.hmenu, id, MF_BYCOMMAND);
             RemoveMenu(self.hpopupmenu, id, MF_BYCOMMAND);
-
-            // redraw the menu bar
-            for hwnd in self.hwnds.borrow().keys() {
-                DrawMenuBar(*hwnd as _);
-            }
         }
 
         let child = item.child();
@@ -298,6 +446,10 @@ impl Menu {
                 .position(|&(h, _)| h == self.hpopupmenu)
                 .ok_or(crate::Error::NotAChildOfThisMenu)?;
             child.parents_hemnu.remove(index);
+            child
+                .hidden_in
+                .retain(|h| h.parent != self.hmenu && h.parent != self.hpopupmenu);
+            root_state::detach(&mut child, self.internal_id);
         }
 
         let index = self
@@ -317,10 +469,176 @@ impl Menu {
             .collect()
     }
 
+    pub fn non_separator_items(&self) -> Vec<MenuItemKind> {
+        self.children
+            .iter()
+            .filter(|c| !c.borrow().is_separator())
+            .map(|c| c.borrow().kind(c.clone()))
+            .collect()
+    }
+
+    /// Inserts the items that were shown again since they were hidden, see [`visibility`].
+    fn insert_shown_items(&self) {
+        unsafe { visibility::insert_shown([self.hmenu, self.hpopupmenu], &self.children) };
+    }
+
+    /// The position in this menu of the non-separator item at `logical_index`,
+    /// or the end of the menu if there are fewer items.
+    pub fn position_skipping_separators(&self, logical_index: usize) -> usize {
+        let mut count = 0;
+        for (position, child) in self.children.iter().enumerate() {
+            if !child.borrow().is_separator() {
+                if count == logical_index {
+                    return position;
+                }
+                count += 1;
+            }
+        }
+        self.children.len()
+    }
+
     fn find_by_id(&self, id: u32) -> Option<Rc<RefCell<MenuChild>>> {
         find_by_id(id, &self.children)
     }
 
+    pub fn find_item_by_id(&self, id: &MenuId) -> Option<MenuItemKind> {
+        find_by_menu_id(id, &self.children)
+    }
+
+    pub fn shortcut_hints(&self) -> Vec<(MenuId, String, Accelerator)> {
+        fn collect(
+            children: &Vec<Rc<RefCell<MenuChild>>>,
+            hints: &mut Vec<(MenuId, String, Accelerator)>,
+        ) {
+            for child in children {
+                let child = child.borrow();
+                if let Some(accelerator) = child.accelerator {
+                    hints.push((child.id.clone(), strip_mnemonic(&child.text), accelerator));
+                }
+                if child.item_type == MenuItemType::Submenu {
+                    collect(child.children.as_ref().unwrap(), hints);
+                }
+            }
+        }
+
+        let mut hints = Vec::new();
+        collect(&self.children, &mut hints);
+        hints
+    }
+
+    /// The id and accelerator of every item, an item added to several submenus is listed once.
+    fn item_accelerators(&self) -> Vec<(MenuId, Option<Accelerator>)> {
+        fn collect(
+            children: &Vec<Rc<RefCell<MenuChild>>>,
+            seen: &mut HashSet<*const RefCell<MenuChild>>,
+            items: &mut Vec<(MenuId, Option<Accelerator>)>,
+        ) {
+            for child in children {
+                if !seen.insert(Rc::as_ptr(child)) {
+                    continue;
+                }
+                let child = child.borrow();
+                items.push((child.id.clone(), child.accelerator));
+                if child.item_type == MenuItemType::Submenu {
+                    collect(child.children.as_ref().unwrap(), seen, items);
+                }
+            }
+        }
+
+        let mut items = Vec::new();
+        collect(&self.children, &mut HashSet::new(), &mut items);
+        items
+    }
+
+    pub fn accelerator_conflicts(&self) -> Vec<(Accelerator, Vec<MenuId>)> {
+        accelerator_conflicts(&self.item_accelerators())
+    }
+
+    pub fn accelerators_shadowing(&self, reserved: &[Accelerator]) -> Vec<(MenuId, Accelerator)> {
+        accelerators_shadowing(&self.item_accelerators(), reserved)
+    }
+
+    pub fn translate_accelerator(
+        &self,
+        vk: u32,
+        modifiers: crate::accelerator::Modifiers,
+    ) -> Option<MenuId> {
+        let flags = accelerator::accel_flags(modifiers);
+        let haccel_store = self.haccel_store.borrow();
+        // like `TranslateAcceleratorW`, disabled items don't fire,
+        // the lowest id wins when several items share the accelerator
+        let mut ids = haccel_store
+            .1
+            .iter()
+            .filter(|(_, Accel(accel))| accel.key as u32 == vk && accel.fVirt == flags)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids.into_iter()
+            .filter_map(|id| self.find_by_id(id))
+            .find(|item| item.borrow().is_enabled())
+            .map(|item| item.borrow().id().clone())
+    }
+
+    pub fn diagnostics(&self) -> MenuDiagnostics {
+        let items = self.item_accelerators();
+
+        let mut duplicate_ids = Vec::new();
+        for (i, (id, _)) in items.iter().enumerate() {
+            if items[..i].iter().any(|(other, _)| other == id) && !duplicate_ids.contains(id) {
+                duplicate_ids.push(id.clone());
+            }
+        }
+
+        let mut hwnds = self.hwnds.borrow().keys().copied().collect::<Vec<_>>();
+        hwnds.sort();
+
+        let haccel_store = self.haccel_store.borrow();
+        MenuDiagnostics {
+            item_count: items.len(),
+            hwnds,
+            accelerators_registered: !haccel_store.0.is_null(),
+            haccel_item_count: haccel_store.1.len(),
+            duplicate_ids,
+            accelerator_conflicts: accelerator_conflicts(&items),
+        }
+    }
+
+    pub fn find_submenu_by_text(&self, text: &str, ignore_case: bool) -> Option<Submenu> {
+        fn find(
+            children: &Vec<Rc<RefCell<MenuChild>>>,
+            matches: &dyn Fn(&str) -> bool,
+        ) -> Option<Rc<RefCell<MenuChild>>> {
+            for child in children {
+                let child_ = child.borrow();
+                if child_.item_type != MenuItemType::Submenu {
+                    continue;
+                }
+                if matches(&strip_mnemonic(&child_.text)) {
+                    return Some(child.clone());
+                }
+                if let Some(found) = find(child_.children.as_ref().unwrap(), matches) {
+                    return Some(found);
+                }
+            }
+            None
+        }
+
+        let text = strip_mnemonic(text);
+        let found = if ignore_case {
+            let text = text.to_lowercase();
+            find(&self.children, &|label| label.to_lowercase() == text)
+        } else {
+            find(&self.children, &|label| label == text)
+        }?;
+
+        let id = found.borrow().id().clone();
+        Some(Submenu {
+            id: Rc::new(id),
+            inner: found,
+        })
+    }
+
     pub fn haccel(&self) -> isize {
         self.haccel_store.borrow().0 as _
     }
@@ -329,6 +647,10 @@ impl Menu {
         self.hpopupmenu as _
     }
 
+    pub fn hmenu_bar(&self) -> isize {
+        self.hmenu as _
+    }
+
     pub unsafe fn init_for_hwnd_with_theme(
         &mut self,
         hwnd: isize,
@@ -338,7 +660,13 @@ impl Menu {
             return Err(crate::Error::AlreadyInitialized);
         }
 
+        util::check_thread(self.thread_id)?;
+        // windows can only be subclassed from the thread that created them
+        // SAFETY: HWND validity is upheld by caller
+        util::check_thread(GetWindowThreadProcessId(hwnd as _, std::ptr::null_mut()))?;
+
         self.hwnds.borrow_mut().insert(hwnd, theme);
+        self.insert_shown_items();
 
         // SAFETY: HWND validity is upheld by caller
         SetMenu(hwnd as _, self.hmenu);
@@ -353,8 +681,43 @@ impl Menu {
         Ok(())
     }
 
+    pub fn set_as_class_default(this: &Rc<RefCell<Self>>, class_atom: u16) -> crate::Result<()> {
+        class_menu::register(class_atom, Rc::downgrade(this))
+    }
+
+    pub fn remove_as_class_default(this: &Rc<RefCell<Self>>, class_atom: u16) -> bool {
+        class_menu::unregister(class_atom, &Rc::downgrade(this))
+    }
+
+    pub fn enable_accelerators_for_hwnd(
+        this: &Rc<RefCell<Self>>,
+        hwnd: isize,
+    ) -> crate::Result<()> {
+        if !this.borrow().hwnds.borrow().contains_key(&hwnd) {
+            return Err(crate::Error::NotInitialized);
+        }
+        accel_pump::register(hwnd, Rc::downgrade(this));
+        Ok(())
+    }
+
+    pub fn disable_accelerators_for_hwnd(&self, hwnd: isize) -> bool {
+        accel_pump::unregister(hwnd, self)
+    }
+
     pub unsafe fn init_for_hwnd(&mut self, hwnd: isize) -> crate::Result<()> {
-        self.init_for_hwnd_with_theme(hwnd, MenuTheme::Auto)
+        self.init_for_hwnd_with_theme(hwnd, self.default_theme)
+    }
+
+    pub fn default_theme(&self) -> MenuTheme {
+        self.default_theme
+    }
+
+    pub fn set_default_theme(&mut self, theme: MenuTheme) {
+        self.default_theme = theme;
+    }
+
+    pub fn theme_for_hwnd(&self, hwnd: isize) -> Option<MenuTheme> {
+        self.hwnds.borrow().get(&hwnd).copied()
     }
 
     pub unsafe fn remove_for_hwnd(&mut self, hwnd: isize) -> crate::Result<()> {
@@ -362,6 +725,10 @@ impl Menu {
             .borrow_mut()
             .remove(&hwnd)
             .ok_or(crate::Error::NotInitialized)?;
+        self.raw_message_hooks.remove(&hwnd);
+        self.highlighted_items.remove(&hwnd);
+        self.hidden_bars.remove(&hwnd);
+        accel_pump::unregister(hwnd, self);
 
         // SAFETY: HWND validity is upheld by caller
         SetMenu(hwnd as _, std::ptr::null_mut());
@@ -385,10 +752,31 @@ impl Menu {
         RemoveWindowSubclass(hwnd as _, Some(menu_subclass_proc), MENU_SUBCLASS_ID);
     }
 
-    pub unsafe fn hide_for_hwnd(&self, hwnd: isize) -> crate::Result<()> {
+    /// Same as the menu subclass but without default processing, `this` is passed
+    /// as a pointer like the subclass data so events can re-enter the menu.
+    pub unsafe fn handle_message(
+        this: *mut Self,
+        hwnd: isize,
+        msg: u32,
+        wparam: usize,
+        lparam: isize,
+    ) -> Option<isize> {
+        handle_message(
+            hwnd as _,
+            msg,
+            wparam,
+            lparam,
+            MENU_SUBCLASS_ID,
+            this as usize,
+            || None,
+        )
+    }
+
+    pub unsafe fn hide_for_hwnd(&mut self, hwnd: isize) -> crate::Result<()> {
         if !self.hwnds.borrow().contains_key(&hwnd) {
             return Err(crate::Error::NotInitialized);
         }
+        self.hidden_bars.remove(&hwnd);
 
         // SAFETY: HWND validity is upheld by caller
         SetMenu(hwnd as _, std::ptr::null_mut());
@@ -397,10 +785,11 @@ impl Menu {
         Ok(())
     }
 
-    pub unsafe fn show_for_hwnd(&self, hwnd: isize) -> crate::Result<()> {
+    pub unsafe fn show_for_hwnd(&mut self, hwnd: isize) -> crate::Result<()> {
         if !self.hwnds.borrow().contains_key(&hwnd) {
             return Err(crate::Error::NotInitialized);
         }
+        self.hidden_bars.remove(&hwnd);
 
         // SAFETY: HWND validity is upheld by caller
         SetMenu(hwnd as _, self.hmenu);
@@ -409,6 +798,99 @@ impl Menu {
         Ok(())
     }
 
+    pub unsafe fn set_bar_visible_for_hwnd(
+        &mut self,
+        hwnd: isize,
+        visible: bool,
+    ) -> crate::Result<()> {
+        if !self.hwnds.borrow().contains_key(&hwnd) {
+            return Err(crate::Error::NotInitialized);
+        }
+
+        if visible {
+            self.hidden_bars.remove(&hwnd);
+        } else {
+            self.hidden_bars.insert(hwnd);
+        }
+
+        // SAFETY: HWND validity is upheld by caller
+        SetMenu(
+            hwnd as _,
+            if visible {
+                self.hmenu
+            } else {
+                std::ptr::null_mut()
+            },
+        );
+        DrawMenuBar(hwnd as _);
+
+        Ok(())
+    }
+
+    pub fn highlighted_item_for_hwnd(&self, hwnd: isize) -> Option<MenuId> {
+        self.highlighted_items.get(&hwnd).cloned()
+    }
+
+    /// Updates the highlighted item of `hwnd` from the parameters of `WM_MENUSELECT`.
+    unsafe fn update_highlighted_item(&mut self, hwnd: isize, wparam: WPARAM, lparam: LPARAM) {
+        let flags = util::HIWORD(wparam as _) as u32;
+        let hmenu = lparam as HMENU;
+
+        // the menu was closed
+        if flags == 0xFFFF && hmenu.is_null() {
+            self.highlighted_items.remove(&hwnd);
+            return;
+        }
+
+        // submenus are identified by their position in the parent, and by their handle here
+        let id = if flags & MF_POPUP != 0 {
+            GetSubMenu(hmenu, util::LOWORD(wparam as _) as _) as u32
+        } else {
+            util::LOWORD(wparam as _) as u32
+        };
+
+        match self.find_by_id(id) {
+            Some(item) if flags & MF_SEPARATOR == 0 => {
+                self.highlighted_items
+                    .insert(hwnd, item.borrow().id().clone());
+            }
+            _ => {
+                self.highlighted_items.remove(&hwnd);
+            }
+        }
+    }
+
+    pub unsafe fn disable_system_close_for_hwnd(
+        &self,
+        hwnd: isize,
+        disabled: bool,
+    ) -> crate::Result<()> {
+        // SAFETY: HWND validity is upheld by caller
+        let system_menu = GetSystemMenu(hwnd as _, false.into());
+        if system_menu.is_null() {
+            return Err(crate::Error::NoSystemMenu);
+        }
+
+        let flags = if disabled { MF_GRAYED } else { MF_ENABLED };
+        if EnableMenuItem(system_menu, SC_CLOSE, MF_BYCOMMAND | flags) == -1 {
+            return Err(crate::Error::NoSystemMenu);
+        }
+
+        // the caption close button follows the state of `SC_CLOSE`,
+        // trigger a frame change so it is repainted right away
+        SetWindowPos(
+            hwnd as _,
+            std::ptr::null_mut(),
+            0,
+            0,
+            0,
+            0,
+            SWP_FRAMECHANGED | SWP_NOMOVE | SWP_NOSIZE | SWP_NOZORDER | SWP_NOACTIVATE,
+        );
+
+        Ok(())
+    }
+
     pub unsafe fn is_visible_on_hwnd(&self, hwnd: isize) -> bool {
         self.hwnds
             .borrow()
@@ -423,81 +905,391 @@ impl Menu {
         hwnd: isize,
         position: Option<Position>,
     ) -> bool {
-        let rc = show_context_menu(hwnd as _, self.hpopupmenu, position);
-        if let Some(item) = rc.and_then(|rc| self.find_by_id(rc)) {
-            unsafe {
-                menu_selected(hwnd as _, &mut item.borrow_mut());
-            }
-            return true;
-        }
-        false
+        self.show_popup_for_hwnd(hwnd, Placement::At(position), None, false)
+            .is_some()
     }
 
-    pub unsafe fn set_theme_for_hwnd(&self, hwnd: isize, theme: MenuTheme) -> crate::Result<()> {
-        if !self.hwnds.borrow().contains_key(&hwnd) {
-            return Err(crate::Error::NotInitialized);
-        }
-
-        // SAFETY: HWND validity is upheld by caller
-        SendMessageW(hwnd as _, MENU_UPDATE_THEME, 0, theme as _);
-
-        Ok(())
+    pub unsafe fn show_context_menu_at_screen_pos_for_hwnd(
+        &mut self,
+        hwnd: isize,
+        position: Position,
+    ) -> bool {
+        self.show_popup_for_hwnd(hwnd, Placement::AtScreen(position), None, false)
+            .is_some()
     }
-}
-
-type ParentMenu = (HMENU, Option<Rc<RefCell<HashMap<Hwnd, MenuTheme>>>>);
-
-/// A generic child in a menu
-#[derive(Debug)]
-pub(crate) struct MenuChild {
-    // shared fields between submenus and menu items
-    item_type: MenuItemType,
-    text: String,
-    enabled: bool,
-    parents_hemnu: Vec<ParentMenu>,
-    root_menu_haccel_stores: HashMap<u32, Rc<RefCell<AccelWrapper>>>,
 
-    // menu item fields
-    internal_id: u32,
-    id: MenuId,
-    accelerator: Option<Accelerator>,
+    pub unsafe fn show_context_menu_for_hwnd_blocking(
+        &mut self,
+        hwnd: isize,
+        position: Option<Position>,
+    ) -> Option<MenuId> {
+        self.show_popup_for_hwnd(hwnd, Placement::At(position), None, false)
+    }
 
-    // predefined menu item fields
-    predefined_item_type: Option<PredefinedMenuItemType>,
+    pub unsafe fn show_context_menu_with_options_for_hwnd(
+        &mut self,
+        hwnd: isize,
+        options: &ContextMenuOptions,
+    ) -> bool {
+        self.show_popup_for_hwnd(
+            hwnd,
+            Placement::At(options.position),
+            options.select,
+            options.keep_open_on_check,
+        )
+        .is_some()
+    }
 
-    // check menu item fields
-    checked: bool,
+    pub unsafe fn show_below_control_for_hwnd(
+        &mut self,
+        hwnd: isize,
+        position: Position,
+        size: Size,
+    ) -> bool {
+        self.show_popup_for_hwnd(hwnd, Placement::BelowControl(position, size), None, false)
+            .is_some()
+    }
 
-    // icon menu item fields
-    icon: Option<Icon>,
+    unsafe fn show_popup_for_hwnd(
+        &mut self,
+        hwnd: isize,
+        placement: Placement,
+        select: Option<usize>,
+        keep_open_on_check: bool,
+    ) -> Option<MenuId> {
+        let mut placement = placement;
+        if keep_open_on_check {
+            placement = pinned_placement(hwnd as _, placement);
+        }
 
-    // submenu fields
-    hmenu: HMENU,
-    hpopupmenu: HMENU,
-    pub children: Option<Vec<Rc<RefCell<MenuChild>>>>,
-}
+        self.insert_shown_items();
+        let mut selected = None;
+        loop {
+            self.tracking.set(self.tracking.get() + 1);
+            let rc = show_context_menu(
+                hwnd as _,
+                self.hpopupmenu,
+                placement.clone(),
+                select,
+                CascadeSide::Auto,
+            );
+            self.tracking.set(self.tracking.get() - 1);
+            let Some(item) = rc.and_then(|rc| self.find_by_id(rc)) else {
+                return selected;
+            };
+            if self.read_only {
+                return None;
+            }
 
-impl Drop for MenuChild {
-    fn drop(&mut self) {
-        if self.item_type == MenuItemType::Submenu {
             unsafe {
-                DestroyMenu(self.hmenu);
-                DestroyMenu(self.hpopupmenu);
+                menu_selected(hwnd as _, &item);
             }
-        }
+            selected = Some(item.borrow().id.clone());
 
-        if self.accelerator.is_some() {
-            for store in self.root_menu_haccel_stores.values() {
-                AccelAction::remove(&mut store.borrow_mut(), self.internal_id)
+            if !reopens_after(keep_open_on_check, &item.borrow()) {
+                return selected;
             }
         }
     }
-}
 
-/// Constructors
-impl MenuChild {
-    pub fn new(
-        text: &str,
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    pub fn beeps_on_disabled(&self) -> bool {
+        self.beep_on_disabled
+    }
+
+    pub fn set_beep_on_disabled(&mut self, beep: bool) {
+        self.beep_on_disabled = beep;
+    }
+
+    pub fn nav_wraps(&self) -> bool {
+        self.nav_wrap
+    }
+
+    pub fn alt_activates_bar(&self) -> bool {
+        self.alt_activates_bar
+    }
+
+    pub fn set_alt_activates_bar(&mut self, activates: bool) {
+        self.alt_activates_bar = activates;
+    }
+
+    /// The number of menu loops of this menu in progress, shared so it can be read
+    /// while the menu is mutably borrowed by its context menu.
+    pub fn tracking(&self) -> Rc<Cell<u32>> {
+        self.tracking.clone()
+    }
+
+    pub fn set_nav_wrap(&mut self, wrap: bool) {
+        self.nav_wrap = wrap;
+        self.nav_msg_filter = (!wrap).then(MsgFilter::new);
+    }
+
+    pub fn set_accelerator_interceptor(&mut self, interceptor: Option<Rc<AcceleratorInterceptor>>) {
+        self.accelerator_interceptor = interceptor.map(Callback);
+    }
+
+    /// Whether the `accelerator` of an item was consumed by the accelerator interceptor.
+    ///
+    /// The item must not be borrowed, the interceptor may change it.
+    pub fn intercepts_accelerator(&self, accelerator: Option<Accelerator>) -> bool {
+        // the interceptor may replace itself
+        match (self.accelerator_interceptor.clone(), accelerator) {
+            (Some(interceptor), Some(accelerator)) => interceptor(&accelerator),
+            _ => false,
+        }
+    }
+
+    /// Stops painting the visible windows of this menu, returns the windows
+    /// that have to be passed to [`Menu::resume_redraw`] afterwards.
+    pub fn suspend_redraw(&self) -> Vec<Hwnd> {
+        let hwnds = self.hwnds.borrow().keys().copied().collect::<Vec<_>>();
+        suspend_redraw(hwnds)
+    }
+
+    pub fn resume_redraw(&self, hwnds: &[Hwnd]) {
+        resume_redraw(hwnds)
+    }
+
+    /// Stops redrawing the menu bars and rebuilding the accelerator table of this menu
+    /// until the matching [`Menu::end_update`], nested calls are counted.
+    pub fn begin_update(&mut self) {
+        if self.update_depth == 0 {
+            self.updating_hwnds = self.suspend_redraw();
+            self.haccel_store.borrow_mut().2 = Some(false);
+        }
+        self.update_depth += 1;
+    }
+
+    /// Ends an update, the outermost one rebuilds the accelerator table and redraws
+    /// the menu bars once.
+    pub fn end_update(&mut self) {
+        self.update_depth = self.update_depth.saturating_sub(1);
+        if self.update_depth > 0 {
+            return;
+        }
+
+        let rebuild = self.haccel_store.borrow_mut().2.take() == Some(true);
+        if rebuild {
+            AccelAction::update_store(&mut self.haccel_store.borrow_mut());
+            self.root_state.notify_haccel_changed(self.haccel());
+        }
+
+        let hwnds = std::mem::take(&mut self.updating_hwnds);
+        self.resume_redraw(&hwnds);
+        // windows that were hidden when the update began
+        for hwnd in self.hwnds.borrow().keys().filter(|h| !hwnds.contains(h)) {
+            unsafe { DrawMenuBar(*hwnd as _) };
+        }
+    }
+
+    pub fn set_item_min_height(&self, height: u32) {
+        self.root_state.set_item_min_height(height);
+
+        fn update_owner_draw(children: &Vec<Rc<RefCell<MenuChild>>>) {
+            for child in children {
+                let child_ = child.borrow();
+                child_.update_owner_draw();
+                if child_.item_type == MenuItemType::Submenu {
+                    update_owner_draw(child_.children.as_ref().unwrap());
+                }
+            }
+        }
+
+        update_owner_draw(&self.children);
+    }
+
+    pub fn set_max_popup_height(&self, height: u32) {
+        self.root_state.set_max_popup_height(height);
+        unsafe { util::set_menu_max_height(self.hpopupmenu, height) };
+
+        fn update_max_popup_height(children: &Vec<Rc<RefCell<MenuChild>>>) {
+            for child in children {
+                let child_ = child.borrow();
+                if child_.item_type == MenuItemType::Submenu {
+                    child_.update_max_popup_height();
+                    update_max_popup_height(child_.children.as_ref().unwrap());
+                }
+            }
+        }
+
+        update_max_popup_height(&self.children);
+    }
+
+    pub fn render_to_bitmap(
+        &self,
+        theme: MenuTheme,
+        dpi: u32,
+    ) -> crate::Result<(Vec<u8>, u32, u32)> {
+        let palette = match theme {
+            MenuTheme::Dark => owner_draw::Palette::dark(),
+            MenuTheme::Auto if dark_menu_bar::should_app_use_dark_mode() => {
+                owner_draw::Palette::dark()
+            }
+            _ => unsafe { owner_draw::Palette::system() },
+        };
+        unsafe { render::render_popup(self.hpopupmenu, &|id| self.find_by_id(id), &palette, dpi) }
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.root_state.generation()
+    }
+
+    pub fn on_item(&self, id: MenuId, handler: Rc<ItemHandler>) {
+        self.root_state.on_item(id, handler)
+    }
+
+    #[cfg(feature = "async")]
+    pub fn changes(&self) -> root_state::Changes {
+        root_state::Changes::new(self.root_state.clone())
+    }
+
+    pub fn on_haccel_changed(&self, observer: Rc<HaccelObserver>) {
+        self.root_state.on_haccel_changed(observer)
+    }
+
+    pub fn subscribe_state(&self, subscriber: Rc<StateSubscriber>) -> SubscriptionId {
+        self.root_state.subscribe_state(subscriber)
+    }
+
+    pub fn unsubscribe_state(&self, id: SubscriptionId) -> bool {
+        self.root_state.unsubscribe_state(id)
+    }
+
+    pub fn set_raw_message_hook_for_hwnd(&mut self, hwnd: isize, hook: Option<Rc<RawMessageHook>>) {
+        match hook {
+            Some(hook) => self.raw_message_hooks.insert(hwnd, Callback(hook)),
+            None => self.raw_message_hooks.remove(&hwnd),
+        };
+    }
+
+    /// Runs the raw message hook of `hwnd`, `Some` means the message was handled by the hook.
+    fn call_raw_message_hook(
+        &self,
+        hwnd: isize,
+        msg: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> Option<LRESULT> {
+        // clone the hook so it can replace or remove itself while running
+        let hook = self.raw_message_hooks.get(&hwnd).cloned()?;
+        hook(msg, wparam, lparam)
+    }
+
+    pub unsafe fn set_theme_for_hwnd(&self, hwnd: isize, theme: MenuTheme) -> crate::Result<()> {
+        if !self.hwnds.borrow().contains_key(&hwnd) {
+            return Err(crate::Error::NotInitialized);
+        }
+
+        // SAFETY: HWND validity is upheld by caller
+        SendMessageW(hwnd as _, MENU_UPDATE_THEME, 0, theme as _);
+
+        Ok(())
+    }
+}
+
+type ParentMenu = (HMENU, Option<Rc<RefCell<HashMap<Hwnd, MenuTheme>>>>);
+
+/// A generic child in a menu
+#[derive(Debug)]
+pub(crate) struct MenuChild {
+    // shared fields between submenus and menu items
+    item_type: MenuItemType,
+    text: String,
+    enabled: bool,
+    parents_hemnu: Vec<ParentMenu>,
+    root_menu_haccel_stores: HashMap<u32, Rc<RefCell<AccelWrapper>>>,
+    root_states: RootStates,
+
+    // menu item fields
+    internal_id: u32,
+    id: MenuId,
+    accelerator: Option<Accelerator>,
+
+    // predefined menu item fields
+    predefined_item_type: Option<PredefinedMenuItemType>,
+
+    /// Position of the last [`sort::mark_used`] call for this item, `0` if never used.
+    last_used: u32,
+
+    // check menu item fields
+    checked: bool,
+    /// The radio group of this item, see [`radio`].
+    radio_group: Option<String>,
+    /// The side the menus of this submenu cascade to when it is shown as a context menu.
+    cascade_side: CascadeSide,
+    /// Called once the built-in action of this predefined item completed.
+    after_action: Option<Callback<AfterAction>>,
+    /// The payload of [`MenuItem::set_data_json`](crate::MenuItem::set_data_json), as JSON.
+    #[cfg(feature = "serde")]
+    pub data_json: Option<String>,
+
+    // icon menu item fields
+    icon: Option<Icon>,
+
+    // owner-drawn fields
+    owner_draw: OwnerDrawData,
+    opens_dialog: bool,
+    /// Parents that don't show the accelerator of this item.
+    accelerator_hidden_in: Vec<HMENU>,
+    visible: bool,
+    /// Parents this item is removed from while hidden, see [`visibility`].
+    hidden_in: Vec<visibility::HiddenItem>,
+    /// Parents that don't reserve the check and icon gutter, see [`MenuChild::is_compact`].
+    compact_in: Vec<HMENU>,
+
+    // submenu fields
+    hmenu: HMENU,
+    hpopupmenu: HMENU,
+    pub children: Option<Vec<Rc<RefCell<MenuChild>>>>,
+    show_accelerators: bool,
+    sort: Sort,
+    clear_on_close: bool,
+    /// The thread that created this item, the only one that can change the items of a submenu.
+    thread_id: u32,
+    compact: bool,
+    title_clickable: bool,
+    /// Installs the hook that watches for clicks on the title, see [`title_click`].
+    title_msg_filter: Option<MsgFilter>,
+    /// Fills this submenu right before it opens, see [`lazy`].
+    lazy_builder: Option<Callback<lazy::LazyBuilder>>,
+    /// Whether the lazy builder ran, it runs again once another builder is set.
+    lazy_built: bool,
+    rebuild_on_open: bool,
+}
+
+impl Drop for MenuChild {
+    fn drop(&mut self) {
+        if self.item_type == MenuItemType::Submenu {
+            unsafe {
+                DestroyMenu(self.hmenu);
+                DestroyMenu(self.hpopupmenu);
+            }
+        }
+
+        if self.accelerator.is_some() {
+            for store in self.root_menu_haccel_stores.values() {
+                AccelAction::remove(&mut store.borrow_mut(), self.internal_id)
+            }
+            self.notify_haccel_changed();
+        }
+
+        if let Some(group) = &self.radio_group {
+            radio::prune(group);
+        }
+    }
+}
+
+/// Constructors
+impl MenuChild {
+    pub fn new(
+        text: &str,
         enabled: bool,
         accelerator: Option<Accelerator>,
         id: Option<MenuId>,
@@ -512,12 +1304,35 @@ impl MenuChild {
             id: id.unwrap_or_else(|| MenuId::new(internal_id.to_string())),
             accelerator,
             root_menu_haccel_stores: HashMap::new(),
+            root_states: HashMap::new(),
             predefined_item_type: None,
+            last_used: 0,
             icon: None,
             checked: false,
             children: None,
             hmenu: std::ptr::null_mut(),
             hpopupmenu: std::ptr::null_mut(),
+            owner_draw: OwnerDrawData::default(),
+            opens_dialog: false,
+            accelerator_hidden_in: Vec::new(),
+            visible: true,
+            hidden_in: Vec::new(),
+            compact_in: Vec::new(),
+            show_accelerators: true,
+            sort: Sort::None,
+            clear_on_close: false,
+            thread_id: util::owner_thread_id(),
+            title_clickable: false,
+            title_msg_filter: None,
+            lazy_builder: None,
+            lazy_built: false,
+            rebuild_on_open: false,
+            cascade_side: CascadeSide::Auto,
+            after_action: None,
+            #[cfg(feature = "serde")]
+            data_json: None,
+            radio_group: None,
+            compact: false,
         }
     }
 
@@ -534,14 +1349,41 @@ impl MenuChild {
             id: id.unwrap_or_else(|| MenuId::new(internal_id.to_string())),
             hpopupmenu: unsafe { CreatePopupMenu() },
             root_menu_haccel_stores: HashMap::new(),
+            root_states: HashMap::new(),
             predefined_item_type: None,
+            last_used: 0,
             icon: None,
             checked: false,
             accelerator: None,
+            owner_draw: OwnerDrawData::default(),
+            opens_dialog: false,
+            accelerator_hidden_in: Vec::new(),
+            visible: true,
+            hidden_in: Vec::new(),
+            compact_in: Vec::new(),
+            show_accelerators: true,
+            sort: Sort::None,
+            clear_on_close: false,
+            thread_id: util::owner_thread_id(),
+            title_clickable: false,
+            title_msg_filter: None,
+            lazy_builder: None,
+            lazy_built: false,
+            rebuild_on_open: false,
+            cascade_side: CascadeSide::Auto,
+            after_action: None,
+            #[cfg(feature = "serde")]
+            data_json: None,
+            radio_group: None,
+            compact: false,
         }
     }
 
-    pub fn new_predefined(item_type: PredefinedMenuItemType, text: Option<String>) -> Self {
+    pub fn new_predefined(
+        item_type: PredefinedMenuItemType,
+        text: Option<String>,
+        id: Option<MenuId>,
+    ) -> Self {
         let internal_id = COUNTER.next();
         Self {
             item_type: MenuItemType::Predefined,
@@ -549,15 +1391,38 @@ impl MenuChild {
             enabled: true,
             parents_hemnu: Vec::new(),
             internal_id,
-            id: MenuId::new(internal_id.to_string()),
+            id: id.unwrap_or_else(|| MenuId::new(internal_id.to_string())),
             accelerator: item_type.accelerator(),
             predefined_item_type: Some(item_type),
+            last_used: 0,
             root_menu_haccel_stores: HashMap::new(),
+            root_states: HashMap::new(),
             icon: None,
             checked: false,
             children: None,
             hmenu: std::ptr::null_mut(),
             hpopupmenu: std::ptr::null_mut(),
+            owner_draw: OwnerDrawData::default(),
+            opens_dialog: false,
+            accelerator_hidden_in: Vec::new(),
+            visible: true,
+            hidden_in: Vec::new(),
+            compact_in: Vec::new(),
+            show_accelerators: true,
+            sort: Sort::None,
+            clear_on_close: false,
+            thread_id: util::owner_thread_id(),
+            title_clickable: false,
+            title_msg_filter: None,
+            lazy_builder: None,
+            lazy_built: false,
+            rebuild_on_open: false,
+            cascade_side: CascadeSide::Auto,
+            after_action: None,
+            #[cfg(feature = "serde")]
+            data_json: None,
+            radio_group: None,
+            compact: false,
         }
     }
 
@@ -579,11 +1444,34 @@ impl MenuChild {
             accelerator,
             checked,
             root_menu_haccel_stores: HashMap::new(),
+            root_states: HashMap::new(),
             predefined_item_type: None,
+            last_used: 0,
             icon: None,
             children: None,
             hmenu: std::ptr::null_mut(),
             hpopupmenu: std::ptr::null_mut(),
+            owner_draw: OwnerDrawData::default(),
+            opens_dialog: false,
+            accelerator_hidden_in: Vec::new(),
+            visible: true,
+            hidden_in: Vec::new(),
+            compact_in: Vec::new(),
+            show_accelerators: true,
+            sort: Sort::None,
+            clear_on_close: false,
+            thread_id: util::owner_thread_id(),
+            title_clickable: false,
+            title_msg_filter: None,
+            lazy_builder: None,
+            lazy_built: false,
+            rebuild_on_open: false,
+            cascade_side: CascadeSide::Auto,
+            after_action: None,
+            #[cfg(feature = "serde")]
+            data_json: None,
+            radio_group: None,
+            compact: false,
         }
     }
 
@@ -605,11 +1493,34 @@ impl MenuChild {
             accelerator,
             icon,
             root_menu_haccel_stores: HashMap::new(),
+            root_states: HashMap::new(),
             predefined_item_type: None,
+            last_used: 0,
             checked: false,
             children: None,
             hmenu: std::ptr::null_mut(),
             hpopupmenu: std::ptr::null_mut(),
+            owner_draw: OwnerDrawData::default(),
+            opens_dialog: false,
+            accelerator_hidden_in: Vec::new(),
+            visible: true,
+            hidden_in: Vec::new(),
+            compact_in: Vec::new(),
+            show_accelerators: true,
+            sort: Sort::None,
+            clear_on_close: false,
+            thread_id: util::owner_thread_id(),
+            title_clickable: false,
+            title_msg_filter: None,
+            lazy_builder: None,
+            lazy_built: false,
+            rebuild_on_open: false,
+            cascade_side: CascadeSide::Auto,
+            after_action: None,
+            #[cfg(feature = "serde")]
+            data_json: None,
+            radio_group: None,
+            compact: false,
         }
     }
 
@@ -630,14 +1541,68 @@ impl MenuChild {
             id: id.unwrap_or_else(|| MenuId::new(internal_id.to_string())),
             accelerator,
             root_menu_haccel_stores: HashMap::new(),
+            root_states: HashMap::new(),
             predefined_item_type: None,
+            last_used: 0,
             icon: None,
             checked: false,
             children: None,
             hmenu: std::ptr::null_mut(),
             hpopupmenu: std::ptr::null_mut(),
+            owner_draw: OwnerDrawData::default(),
+            opens_dialog: false,
+            accelerator_hidden_in: Vec::new(),
+            visible: true,
+            hidden_in: Vec::new(),
+            compact_in: Vec::new(),
+            show_accelerators: true,
+            sort: Sort::None,
+            clear_on_close: false,
+            thread_id: util::owner_thread_id(),
+            title_clickable: false,
+            title_msg_filter: None,
+            lazy_builder: None,
+            lazy_built: false,
+            rebuild_on_open: false,
+            cascade_side: CascadeSide::Auto,
+            after_action: None,
+            #[cfg(feature = "serde")]
+            data_json: None,
+            radio_group: None,
+            compact: false,
         }
     }
+
+    /// A new item with the same properties as this item, check or icon item,
+    /// but with a fresh id and not added to any menu yet.
+    pub fn clone_as_new(&self) -> Self {
+        let mut item = match self.item_type {
+            MenuItemType::Check => Self::new_check(
+                &self.text,
+                self.enabled,
+                self.checked,
+                self.accelerator,
+                None,
+            ),
+            MenuItemType::Icon => Self::new_icon(
+                &self.text,
+                self.enabled,
+                self.icon.clone(),
+                self.accelerator,
+                None,
+            ),
+            _ => Self::new(&self.text, self.enabled, self.accelerator, None),
+        };
+        item.opens_dialog = self.opens_dialog;
+        item.icon.clone_from(&self.icon);
+        item.owner_draw.badge.clone_from(&self.owner_draw.badge);
+        item.owner_draw.value.clone_from(&self.owner_draw.value);
+        item.owner_draw.indent_level = self.owner_draw.indent_level;
+        item.owner_draw.style = self.owner_draw.style;
+        #[cfg(feature = "serde")]
+        item.data_json.clone_from(&self.data_json);
+        item
+    }
 }
 
 /// Shared methods
@@ -646,6 +1611,10 @@ impl MenuChild {
         self.item_type
     }
 
+    pub fn predefined_item_type(&self) -> Option<&PredefinedMenuItemType> {
+        self.predefined_item_type.as_ref()
+    }
+
     pub fn id(&self) -> &MenuId {
         &self.id
     }
@@ -657,6 +1626,11 @@ impl MenuChild {
         }
     }
 
+    /// The id of `WM_COMMAND`, which only has a word for it.
+    pub fn command_id(&self) -> u16 {
+        self.internal_id as u16
+    }
+
     pub fn text(&self) -> String {
         self.parents_hemnu
             .first()
@@ -676,20 +1650,107 @@ impl MenuChild {
                 unsafe { GetMenuItemInfoW(*hmenu, self.internal_id(), false.into(), &mut info) };
 
                 let text = decode_wide(info.dwTypeData);
-                text.split('\t').next().unwrap().to_string()
+                let text = text.split('\t').next().unwrap();
+                match self.appends_ellipsis() {
+                    true => text.strip_suffix(ELLIPSIS).unwrap_or(text).to_string(),
+                    false => text.to_string(),
+                }
             })
             .unwrap_or_else(|| self.text.clone())
     }
 
-    pub fn set_text(&mut self, text: &str) {
+    /// The label shown in the menu, without the accelerator.
+    pub fn display_text(&self) -> String {
+        match self.appends_ellipsis() {
+            true => format!("{}{ELLIPSIS}", self.text),
+            false => self.text.clone(),
+        }
+    }
+
+    /// The label of this item in the first menu it was added to, see [`MenuItem::display_label`](crate::MenuItem::display_label).
+    pub fn display_label(&self) -> String {
+        let parent = self
+            .parents_hemnu
+            .first()
+            .map_or(std::ptr::null_mut(), |(hmenu, _)| *hmenu);
+        self.label(parent)
+    }
+
+    /// The label of this item in `parent`, including the accelerator if it is shown there.
+    fn label(&self, parent: HMENU) -> String {
+        match self.accelerator {
+            Some(accelerator) if !self.accelerator_hidden_in.contains(&parent) => {
+                format!("{}\t{accelerator}", self.display_text())
+            }
+            _ => self.display_text(),
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn set_visible(&mut self, visible: bool) -> StateChange {
+        if self.visible == visible {
+            return StateChange::default();
+        }
+        self.visible = visible;
+
+        let parents = self.parents_hemnu.clone();
+        for (parent, menu_bars) in parents {
+            if !visible {
+                self.hide_in(parent);
+            }
+            let Some(menu_bars) = menu_bars else {
+                continue;
+            };
+            for hwnd in menu_bars.borrow().keys() {
+                unsafe {
+                    if visible {
+                        // the menu inserts the item back, it knows where
+                        PostMessageW(*hwnd as _, MENU_SHOW_ITEMS, 0, 0);
+                    } else {
+                        DrawMenuBar(*hwnd as _);
+                    }
+                }
+            }
+        }
+
+        self.state_changed()
+    }
+
+    /// Removes this item from the native menu `parent`, unless it is already removed from it.
+    fn hide_in(&mut self, parent: HMENU) {
+        if self.is_hidden_in(parent) {
+            return;
+        }
+        if let Some(hidden) = unsafe { visibility::remove(parent, self.internal_id()) } {
+            self.hidden_in.push(hidden);
+        }
+    }
+
+    /// Whether this item is missing from the native menu `parent` because it was hidden,
+    /// it is still missing after being shown until `parent` is about to be displayed.
+    fn is_hidden_in(&self, parent: HMENU) -> bool {
+        self.hidden_in.iter().any(|h| h.parent == parent)
+    }
+
+    pub fn set_text(&mut self, text: &str) -> StateChange {
+        let changed = self.text != text;
         self.text = text.to_string();
-        let mut text = if let Some(accelerator) = self.accelerator {
-            encode_wide(format!("{text}\t{}", accelerator))
+        self.update_label();
+
+        if changed {
+            self.state_changed()
         } else {
-            encode_wide(text)
-        };
+            StateChange::default()
+        }
+    }
 
+    /// Updates the label shown in the native menus after the text or what is added to it changed.
+    fn update_label(&mut self) {
         for (parent, menu_bars) in &self.parents_hemnu {
+            let mut text = encode_wide(self.label(*parent));
             let mut info: MENUITEMINFOW = unsafe { std::mem::zeroed() };
             info.cbSize = std::mem::size_of::<MENUITEMINFOW>() as _;
             info.fMask = MIIM_STRING;
@@ -703,6 +1764,11 @@ impl MenuChild {
                 }
             }
         }
+
+        // the caption of a separator is only drawn when it is owner-drawn
+        if self.is_separator() {
+            self.update_owner_draw();
+        }
     }
 
     pub fn is_enabled(&self) -> bool {
@@ -720,7 +1786,8 @@ impl MenuChild {
             .unwrap_or(self.enabled)
     }
 
-    pub fn set_enabled(&mut self, enabled: bool) {
+    pub fn set_enabled(&mut self, enabled: bool) -> StateChange {
+        let changed = self.enabled != enabled;
         self.enabled = enabled;
         for (parent, menu_bars) in &self.parents_hemnu {
             let flag = if enabled { MF_ENABLED } else { MF_DISABLED };
@@ -732,11 +1799,88 @@ impl MenuChild {
                 }
             };
         }
+
+        if changed {
+            self.state_changed()
+        } else {
+            StateChange::default()
+        }
+    }
+
+    /// The state reported to the state subscribers of the root menus.
+    pub fn state(&self) -> ItemState {
+        ItemState {
+            enabled: self.enabled,
+            checked: (self.item_type == MenuItemType::Check).then_some(self.checked),
+            text: self.text.clone(),
+            visible: self.visible,
+        }
+    }
+
+    /// Bumps the generation of the root menus, the subscribers are notified by the returned change.
+    fn state_changed(&self) -> StateChange {
+        if self.root_states.is_empty() {
+            return StateChange::default();
+        }
+
+        for root in self.root_states.values() {
+            root.bump_generation();
+        }
+        StateChange::new(
+            self.id.clone(),
+            self.state(),
+            self.root_states.values().cloned().collect(),
+        )
+    }
+
+    /// Reports the accelerator tables of the root menus as rebuilt, after the accelerator
+    /// of this item was added to or removed from them.
+    fn notify_haccel_changed(&self) {
+        for (root_id, store) in &self.root_menu_haccel_stores {
+            // read the handle first, observers may change the accelerators again
+            let (haccel, deferred) = {
+                let store = store.borrow();
+                (store.0 as isize, store.2.is_some())
+            };
+            // the root menu reports it once its update ends
+            if deferred {
+                continue;
+            }
+            if let Some(root) = self.root_states.get(root_id) {
+                root.notify_haccel_changed(haccel);
+            }
+        }
+    }
+
+    /// Bumps the generation of the root menus for changes that aren't part of [`ItemState`].
+    fn mark_changed(&self) {
+        for root in self.root_states.values() {
+            root.bump_generation();
+        }
+    }
+
+    pub fn opens_dialog(&self) -> bool {
+        self.opens_dialog
+    }
+
+    pub fn set_opens_dialog(&mut self, opens_dialog: bool) {
+        self.opens_dialog = opens_dialog;
+        self.update_label();
+        self.mark_changed();
+    }
+
+    /// Whether an ellipsis is added to the label, labels that already end with one are left alone.
+    fn appends_ellipsis(&self) -> bool {
+        self.opens_dialog && !self.text.ends_with(ELLIPSIS) && !self.text.ends_with("...")
+    }
+
+    pub fn accelerator(&self) -> Option<Accelerator> {
+        self.accelerator
     }
 
     pub fn set_accelerator(&mut self, accelerator: Option<Accelerator>) -> crate::Result<()> {
         self.accelerator = accelerator;
-        self.set_text(&self.text.clone());
+        self.update_label();
 
         for store in self.root_menu_haccel_stores.values() {
             let mut store = store.borrow_mut();
@@ -746,6 +1890,9 @@ impl MenuChild {
                 AccelAction::remove(&mut store, self.internal_id)
             }
         }
+        self.notify_haccel_changed();
+
+        self.mark_changed();
 
         Ok(())
     }
@@ -753,6 +1900,10 @@ impl MenuChild {
 
 /// CheckMenuItem methods
 impl MenuChild {
+    pub fn radio_group(&self) -> Option<&str> {
+        self.radio_group.as_deref()
+    }
+
     pub fn is_checked(&self) -> bool {
         self.parents_hemnu
             .first()
@@ -768,9 +1919,10 @@ impl MenuChild {
             .unwrap_or(self.enabled)
     }
 
-    pub fn set_checked(&mut self, checked: bool) {
+    pub fn set_checked(&mut self, checked: bool) -> StateChange {
         use windows_sys::Win32::UI::WindowsAndMessaging;
 
+        let changed = self.checked != checked;
         self.checked = checked;
         for (parent, menu_bars) in &self.parents_hemnu {
             let flag = if checked { MF_CHECKED } else { MF_UNCHECKED };
@@ -782,11 +1934,21 @@ impl MenuChild {
                 }
             };
         }
+
+        if changed {
+            self.state_changed()
+        } else {
+            StateChange::default()
+        }
     }
 }
 
-/// IconMenuItem methods
+/// IconMenuItem and MenuItem methods
 impl MenuChild {
+    pub fn icon(&self) -> Option<Icon> {
+        self.icon.clone()
+    }
+
     pub fn set_icon(&mut self, icon: Option<Icon>) {
         self.icon.clone_from(&icon);
 
@@ -803,43 +1965,283 @@ impl MenuChild {
                 }
             };
         }
+        // icons need the gutter of compact submenus back
+        if !self.compact_in.is_empty() {
+            self.update_owner_draw();
+        }
+
+        self.mark_changed();
     }
 }
 
-/// Submenu methods
+/// Owner-drawn item methods
 impl MenuChild {
-    pub fn hpopupmenu(&self) -> isize {
-        self.hpopupmenu as _
+    /// Whether this item is owner-drawn in popup menus.
+    pub fn is_owner_drawn(&self) -> bool {
+        self.is_owner_drawn_in(false)
     }
 
-    pub fn add_menu_item(&mut self, item: &dyn IsMenuItem, op: AddOp) -> crate::Result<()> {
-        let (child, mut flags) = inner_menu_child_and_flags!(item);
+    /// Whether this item is owner-drawn in a menu bar or in a popup menu, menu-wide
+    /// settings like the item minimum height only apply to popup menus.
+    fn is_owner_drawn_in(&self, menu_bar: bool) -> bool {
+        self.owner_draw.is_needed()
+            || self.is_labeled_separator()
+            || (!menu_bar && !self.is_separator() && self.item_min_height() > 0)
+            || (!menu_bar && self.is_compact())
+    }
 
-        {
-            child
-                .borrow_mut()
-                .root_menu_haccel_stores
-                .extend(self.root_menu_haccel_stores.clone());
-        }
+    /// Whether this item is drawn without the gutter for check marks and icons, which requires
+    /// all of its parents to be compact and the item to have neither a check mark nor an icon.
+    pub fn is_compact(&self) -> bool {
+        !self.compact_in.is_empty()
+            && self
+                .parents_hemnu
+                .iter()
+                .all(|(h, _)| self.compact_in.contains(h))
+            && self.item_type != MenuItemType::Check
+            && self.icon.is_none()
+            && !self.is_separator()
+    }
 
-        {
-            let child_ = child.borrow();
-            if !child_.enabled {
-                flags |= MF_GRAYED;
+    pub fn is_separator(&self) -> bool {
+        matches!(
+            self.predefined_item_type,
+            Some(PredefinedMenuItemType::Separator)
+        )
+    }
+
+    /// Whether this item is a separator with a caption, which is drawn next to its line.
+    fn is_labeled_separator(&self) -> bool {
+        self.is_separator() && !self.text.is_empty()
+    }
+
+    /// The largest item minimum height of the menus this item belongs to.
+    pub fn item_min_height(&self) -> u32 {
+        self.root_states
+            .values()
+            .map(|root| root.item_min_height())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The smallest popup height limit of the menus this item belongs to, `0` if there is none.
+    pub fn max_popup_height(&self) -> u32 {
+        self.root_states
+            .values()
+            .map(|root| root.max_popup_height())
+            .filter(|height| *height > 0)
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Applies [`MenuChild::max_popup_height`] to the popups of this submenu.
+    pub fn update_max_popup_height(&self) {
+        if self.item_type == MenuItemType::Submenu {
+            let height = self.max_popup_height();
+            unsafe {
+                util::set_menu_max_height(self.hmenu, height);
+                util::set_menu_max_height(self.hpopupmenu, height);
             }
+        }
+    }
 
-            let mut text = child_.text.clone();
+    pub fn separator_style(&self) -> Option<SeparatorStyle> {
+        self.owner_draw.separator_style
+    }
 
-            if let Some(accelerator) = &child_.accelerator {
-                let accel_str = accelerator.to_string();
+    /// Sets the style of a separator, the default style is drawn natively.
+    pub fn set_separator_style(&mut self, style: SeparatorStyle) {
+        self.owner_draw.separator_style =
+            (self.is_separator() && style != SeparatorStyle::default()).then_some(style);
+        self.update_owner_draw();
+        self.mark_changed();
+    }
 
-                text.push('\t');
-                text.push_str(&accel_str);
+    pub fn value(&self) -> Option<(String, Option<[u8; 4]>)> {
+        self.owner_draw.value.clone()
+    }
+
+    pub fn set_value(&mut self, value: Option<(String, Option<[u8; 4]>)>) {
+        self.owner_draw.value = value;
+        self.update_owner_draw();
+        self.mark_changed();
+    }
+
+    pub fn indent_level(&self) -> u8 {
+        self.owner_draw.indent_level
+    }
+
+    pub fn set_indent_level(&mut self, level: u8) {
+        self.owner_draw.indent_level = level;
+        self.update_owner_draw();
+        self.mark_changed();
+    }
+
+    pub fn badge(&self) -> Option<String> {
+        self.owner_draw.badge.clone()
+    }
+
+    pub fn style(&self) -> Option<MenuItemStyle> {
+        self.owner_draw.style
+    }
+
+    pub fn set_style(&mut self, style: Option<MenuItemStyle>) {
+        self.owner_draw.style = style;
+        self.update_owner_draw();
+        self.mark_changed();
+    }
+
+    pub fn set_badge(&mut self, badge: Option<String>) {
+        self.owner_draw.badge = badge;
+        self.update_owner_draw();
+        self.mark_changed();
+    }
+
+    /// Switches this item to or from owner-drawn in all of its parents,
+    /// which also makes the system re-measure and repaint it.
+    fn update_owner_draw(&self) {
+        for (parent, menu_bars) in &self.parents_hemnu {
+            let owner_drawn = self.is_owner_drawn_in(menu_bars.is_some());
+            unsafe { owner_draw::set_owner_drawn(*parent, self.internal_id(), owner_drawn) };
+
+            if let Some(menu_bars) = menu_bars {
+                for hwnd in menu_bars.borrow().keys() {
+                    unsafe { DrawMenuBar(*hwnd as _) };
+                }
+            };
+        }
+    }
+}
+
+/// Submenu methods
+impl MenuChild {
+    pub fn hpopupmenu(&self) -> isize {
+        self.hpopupmenu as _
+    }
+
+    pub fn shows_accelerators(&self) -> bool {
+        self.show_accelerators
+    }
+
+    pub fn sort(&self) -> Sort {
+        self.sort
+    }
+
+    pub fn clears_on_close(&self) -> bool {
+        self.clear_on_close
+    }
+
+    pub fn set_clear_on_close(&mut self, clear: bool) {
+        self.clear_on_close = clear;
+    }
+
+    pub fn is_title_clickable(&self) -> bool {
+        self.title_clickable
+    }
+
+    pub fn set_title_clickable(&mut self, clickable: bool) {
+        self.title_clickable = clickable;
+        self.title_msg_filter = clickable.then(MsgFilter::new);
+    }
+
+    pub fn set_after_action(&mut self, f: Rc<AfterAction>) {
+        self.after_action = Some(Callback(f));
+    }
+
+    pub fn cascade_side(&self) -> CascadeSide {
+        self.cascade_side
+    }
+
+    pub fn set_cascade_side(&mut self, side: CascadeSide) {
+        self.cascade_side = side;
+    }
+
+    pub fn set_lazy_builder(&mut self, builder: Rc<lazy::LazyBuilder>) {
+        self.lazy_builder = Some(Callback(builder));
+        self.lazy_built = false;
+    }
+
+    pub fn rebuilds_on_open(&self) -> bool {
+        self.rebuild_on_open
+    }
+
+    pub fn set_rebuild_on_open(&mut self, rebuild: bool) {
+        self.rebuild_on_open = rebuild;
+    }
+
+    pub fn is_compact_submenu(&self) -> bool {
+        self.compact
+    }
+
+    pub fn set_compact(&mut self, compact: bool) {
+        self.compact = compact;
 
+        for child in self.children.as_ref().unwrap() {
+            let mut child = child.borrow_mut();
+            child
+                .compact_in
+                .retain(|h| *h != self.hmenu && *h != self.hpopupmenu);
+            if compact {
+                child.compact_in.extend([self.hmenu, self.hpopupmenu]);
+            }
+            child.update_owner_draw();
+        }
+
+        self.mark_changed();
+    }
+
+    pub fn set_show_accelerators(&mut self, show: bool) {
+        self.show_accelerators = show;
+
+        for child in self.children.as_ref().unwrap() {
+            let mut child = child.borrow_mut();
+            child
+                .accelerator_hidden_in
+                .retain(|h| *h != self.hmenu && *h != self.hpopupmenu);
+            if !show {
+                child
+                    .accelerator_hidden_in
+                    .extend([self.hmenu, self.hpopupmenu]);
+            }
+            child.update_label();
+        }
+
+        self.mark_changed();
+    }
+
+    pub fn add_menu_item(&mut self, item: &dyn IsMenuItem, op: AddOp) -> crate::Result<()> {
+        util::check_thread(self.thread_id)?;
+        let (child, mut flags) = inner_menu_child_and_flags!(item);
+
+        {
+            let mut child_ = child.borrow_mut();
+            child_
+                .root_menu_haccel_stores
+                .extend(self.root_menu_haccel_stores.clone());
+            for (root_id, root) in &self.root_states {
+                root_state::attach(&mut child_, *root_id, root);
+            }
+            if !self.show_accelerators {
+                child_
+                    .accelerator_hidden_in
+                    .extend([self.hmenu, self.hpopupmenu]);
+            }
+        }
+
+        {
+            let child_ = child.borrow();
+            if !child_.enabled {
+                flags |= MF_GRAYED;
+            }
+
+            let text = child_.label(self.hmenu);
+
+            if let Some(accelerator) = &child_.accelerator {
                 for root_menu in self.root_menu_haccel_stores.values() {
                     let mut haccel = root_menu.borrow_mut();
                     AccelAction::add(&mut haccel, child_.internal_id(), accelerator)?;
                 }
+                child_.notify_haccel_changed();
             }
 
             let id = child_.internal_id() as usize;
@@ -851,16 +2253,21 @@ impl MenuChild {
                         AppendMenuW(self.hpopupmenu, flags, id, text.as_ptr());
                     }
                     AddOp::Insert(position) => {
+                        let native_position = visibility::native_position(
+                            self.hmenu,
+                            self.children.as_ref().unwrap(),
+                            position,
+                        );
                         InsertMenuW(
                             self.hmenu,
-                            position as _,
+                            native_position as _,
                             flags | MF_BYPOSITION,
                             id,
                             text.as_ptr(),
                         );
                         InsertMenuW(
                             self.hpopupmenu,
-                            position as _,
+                            native_position as _,
                             flags | MF_BYPOSITION,
                             id,
                             text.as_ptr(),
@@ -873,7 +2280,7 @@ impl MenuChild {
         {
             let child_ = child.borrow();
 
-            if child_.item_type() == MenuItemType::Icon {
+            if child_.item_type() == MenuItemType::Icon || child_.icon.is_some() {
                 let hbitmap = child_
                     .icon
                     .as_ref()
@@ -886,12 +2293,36 @@ impl MenuChild {
                     SetMenuItemInfoW(self.hpopupmenu, child_.internal_id, false.into(), &info);
                 };
             }
+
+            if child_.radio_group.is_some() {
+                unsafe {
+                    radio::set_radio_check(self.hmenu, child_.internal_id);
+                    radio::set_radio_check(self.hpopupmenu, child_.internal_id);
+                };
+            }
+
+            if child_.is_owner_drawn() {
+                unsafe {
+                    owner_draw::set_owner_drawn(self.hmenu, child_.internal_id(), true);
+                    owner_draw::set_owner_drawn(self.hpopupmenu, child_.internal_id(), true);
+                };
+            }
         }
 
         {
             let mut child_ = child.borrow_mut();
             child_.parents_hemnu.push((self.hmenu, None));
             child_.parents_hemnu.push((self.hpopupmenu, None));
+            if !child_.visible {
+                child_.hide_in(self.hmenu);
+                child_.hide_in(self.hpopupmenu);
+            }
+            if self.compact || !child_.compact_in.is_empty() {
+                if self.compact {
+                    child_.compact_in.extend([self.hmenu, self.hpopupmenu]);
+                }
+                child_.update_owner_draw();
+            }
         }
 
         {
@@ -902,10 +2333,29 @@ impl MenuChild {
             }
         }
 
+        self.mark_changed();
+
         Ok(())
     }
 
     pub fn remove(&mut self, item: &dyn IsMenuItem) -> crate::Result<()> {
+        self.remove_child(item)?;
+        self.mark_changed();
+        Ok(())
+    }
+
+    pub fn clear(&mut self) -> crate::Result<Vec<MenuItemKind>> {
+        let items = self.items();
+        for item in &items {
+            self.remove_child(item.as_ref())?;
+        }
+        self.mark_changed();
+        Ok(items)
+    }
+
+    /// Removes `item` from the native menus and the children.
+    fn remove_child(&mut self, item: &dyn IsMenuItem) -> crate::Result<()> {
+        util::check_thread(self.thread_id)?;
         let id = item.child().borrow().internal_id();
         unsafe {
             RemoveMenu(self.hmenu, id, MF_BYCOMMAND);
@@ -928,6 +2378,21 @@ impl MenuChild {
                 .position(|&(h, _)| h == self.hpopupmenu)
                 .ok_or(crate::Error::NotAChildOfThisMenu)?;
             child.parents_hemnu.remove(index);
+            child
+                .hidden_in
+                .retain(|h| h.parent != self.hmenu && h.parent != self.hpopupmenu);
+            for root_id in self.root_states.keys() {
+                root_state::detach(&mut child, *root_id);
+            }
+            child
+                .accelerator_hidden_in
+                .retain(|h| *h != self.hmenu && *h != self.hpopupmenu);
+            if !child.compact_in.is_empty() {
+                child
+                    .compact_in
+                    .retain(|h| *h != self.hmenu && *h != self.hpopupmenu);
+                child.update_owner_draw();
+            }
         }
 
         let children = self.children.as_mut().unwrap();
@@ -940,6 +2405,59 @@ impl MenuChild {
         Ok(())
     }
 
+    pub fn set_items(&mut self, items: &[&dyn IsMenuItem]) -> crate::Result<()> {
+        // fail before touching the submenu if an accelerator can't be registered
+        for item in items {
+            if let Some(accelerator) = item.child().borrow().accelerator {
+                accelerator.to_accel(0)?;
+            }
+        }
+
+        self.batch_update(|submenu| {
+            submenu.clear()?;
+            for item in items {
+                submenu.add_menu_item(*item, AddOp::Append)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Runs `f` as one update of the root menus, like [`Menu::begin_update`]: their menu bars
+    /// aren't redrawn and their accelerator tables are rebuilt once after `f`, if they changed.
+    ///
+    /// Root menus that are already updating are left to their own update.
+    fn batch_update<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+        let mut started = Vec::new();
+        for (root_id, store) in &self.root_menu_haccel_stores {
+            let mut store_ = store.borrow_mut();
+            if store_.2.is_none() {
+                store_.2 = Some(false);
+                started.push((*root_id, store.clone()));
+            }
+        }
+        let hwnds = suspend_redraw(
+            self.parents_hemnu
+                .iter()
+                .filter_map(|(_, menu_bars)| menu_bars.as_ref())
+                .flat_map(|menu_bars| menu_bars.borrow().keys().copied().collect::<Vec<_>>()),
+        );
+
+        let result = f(self);
+
+        for (root_id, store) in started {
+            let rebuild = store.borrow_mut().2.take() == Some(true);
+            if rebuild {
+                AccelAction::update_store(&mut store.borrow_mut());
+                if let Some(root) = self.root_states.get(&root_id) {
+                    root.notify_haccel_changed(store.borrow().0 as isize);
+                }
+            }
+        }
+        resume_redraw(&hwnds);
+
+        result
+    }
+
     pub fn items(&self) -> Vec<MenuItemKind> {
         self.children
             .as_ref()
@@ -954,14 +2472,89 @@ impl MenuChild {
         hwnd: isize,
         position: Option<Position>,
     ) -> bool {
-        let rc = show_context_menu(hwnd as _, self.hpopupmenu, position);
-        if let Some(item) = rc.and_then(|rc| self.find_by_id(rc)) {
+        self.show_popup_for_hwnd(hwnd, Placement::At(position), None, false)
+            .is_some()
+    }
+
+    pub unsafe fn show_context_menu_at_screen_pos_for_hwnd(
+        &mut self,
+        hwnd: isize,
+        position: Position,
+    ) -> bool {
+        self.show_popup_for_hwnd(hwnd, Placement::AtScreen(position), None, false)
+            .is_some()
+    }
+
+    pub unsafe fn show_context_menu_for_hwnd_blocking(
+        &mut self,
+        hwnd: isize,
+        position: Option<Position>,
+    ) -> Option<MenuId> {
+        self.show_popup_for_hwnd(hwnd, Placement::At(position), None, false)
+    }
+
+    pub unsafe fn show_context_menu_with_options_for_hwnd(
+        &mut self,
+        hwnd: isize,
+        options: &ContextMenuOptions,
+    ) -> bool {
+        self.show_popup_for_hwnd(
+            hwnd,
+            Placement::At(options.position),
+            options.select,
+            options.keep_open_on_check,
+        )
+        .is_some()
+    }
+
+    pub unsafe fn show_below_control_for_hwnd(
+        &mut self,
+        hwnd: isize,
+        position: Position,
+        size: Size,
+    ) -> bool {
+        self.show_popup_for_hwnd(hwnd, Placement::BelowControl(position, size), None, false)
+            .is_some()
+    }
+
+    unsafe fn show_popup_for_hwnd(
+        &mut self,
+        hwnd: isize,
+        placement: Placement,
+        select: Option<usize>,
+        keep_open_on_check: bool,
+    ) -> Option<MenuId> {
+        let mut placement = placement;
+        if keep_open_on_check {
+            placement = pinned_placement(hwnd as _, placement);
+        }
+
+        visibility::insert_shown(
+            [self.hmenu, self.hpopupmenu],
+            self.children.as_ref().unwrap(),
+        );
+        let mut selected = None;
+        loop {
+            let rc = show_context_menu(
+                hwnd as _,
+                self.hpopupmenu,
+                placement.clone(),
+                select,
+                self.cascade_side,
+            );
+            let Some(item) = rc.and_then(|rc| self.find_by_id(rc)) else {
+                return selected;
+            };
+
             unsafe {
-                menu_selected(hwnd as _, &mut item.borrow_mut());
+                menu_selected(hwnd as _, &item);
+            }
+            selected = Some(item.borrow().id.clone());
+
+            if !reopens_after(keep_open_on_check, &item.borrow()) {
+                return selected;
             }
-            return true;
         }
-        false
     }
 
     pub unsafe fn attach_menu_subclass_for_hwnd(&self, hwnd: isize) {
@@ -986,6 +2579,49 @@ impl MenuChild {
         let children = self.children.as_ref().unwrap();
         find_by_id(id, children)
     }
+
+    pub fn find_item_by_id(&self, id: &MenuId) -> Option<MenuItemKind> {
+        find_by_menu_id(id, self.children.as_ref().unwrap())
+    }
+}
+
+/// The accelerators shared by several of `items`, in the order they first appear,
+/// with the ids of the items sharing them.
+fn accelerator_conflicts(
+    items: &[(MenuId, Option<Accelerator>)],
+) -> Vec<(Accelerator, Vec<MenuId>)> {
+    let mut conflicts: Vec<(Accelerator, Vec<MenuId>)> = Vec::new();
+    for (id, accelerator) in items {
+        let Some(accelerator) = accelerator else {
+            continue;
+        };
+        let same_keys = |a: &Accelerator| {
+            a.modifiers() == accelerator.modifiers() && a.key() == accelerator.key()
+        };
+        match conflicts.iter_mut().find(|(a, _)| same_keys(a)) {
+            Some((_, ids)) => ids.push(id.clone()),
+            None => conflicts.push((*accelerator, vec![id.clone()])),
+        }
+    }
+    conflicts.retain(|(_, ids)| ids.len() > 1);
+    conflicts
+}
+
+/// The ids and accelerators of `items` whose accelerator is one of `reserved`, in order.
+fn accelerators_shadowing(
+    items: &[(MenuId, Option<Accelerator>)],
+    reserved: &[Accelerator],
+) -> Vec<(MenuId, Accelerator)> {
+    items
+        .iter()
+        .filter_map(|(id, accelerator)| Some((id, (*accelerator)?)))
+        .filter(|(_, accelerator)| {
+            reserved
+                .iter()
+                .any(|r| r.modifiers() == accelerator.modifiers() && r.key() == accelerator.key())
+        })
+        .map(|(id, accelerator)| (id.clone(), accelerator))
+        .collect()
 }
 
 fn find_by_id(id: u32, children: &Vec<Rc<RefCell<MenuChild>>>) -> Option<Rc<RefCell<MenuChild>>> {
@@ -1004,33 +2640,124 @@ fn find_by_id(id: u32, children: &Vec<Rc<RefCell<MenuChild>>>) -> Option<Rc<RefC
     None
 }
 
+/// Depth-first search of `children` and their nested submenus for the first item with `id`.
+fn find_by_menu_id(id: &MenuId, children: &Vec<Rc<RefCell<MenuChild>>>) -> Option<MenuItemKind> {
+    for i in children {
+        let item = i.borrow();
+        if item.id() == id {
+            return Some(item.kind(i.clone()));
+        }
+
+        if item.item_type() == MenuItemType::Submenu {
+            if let Some(child) = item.find_item_by_id(id) {
+                return Some(child);
+            }
+        }
+    }
+    None
+}
+
+/// Where a popup menu is shown, positions and sizes are relative to the client area of the window.
+#[derive(Clone)]
+enum Placement {
+    /// At the given position, or at the cursor.
+    At(Option<Position>),
+    /// At the given position in screen coordinates.
+    AtScreen(Position),
+    /// Below the control with the given position and size, or above it if there is no room below.
+    BelowControl(Position, Size),
+}
+
+/// `placement` with the cursor position resolved, so a popup that is shown again
+/// after a toggle opens where it was, even if the cursor moved to the toggled item.
+unsafe fn pinned_placement(
+    hwnd: windows_sys::Win32::Foundation::HWND,
+    placement: Placement,
+) -> Placement {
+    match placement {
+        Placement::At(None) => {
+            let mut pt = POINT { x: 0, y: 0 };
+            GetCursorPos(&mut pt);
+            ScreenToClient(hwnd, &mut pt);
+            Placement::At(Some(PhysicalPosition::new(pt.x, pt.y).into()))
+        }
+        placement => placement,
+    }
+}
+
+/// Whether a popup shown with [`ContextMenuOptions::keep_open_on_check`] is shown again after `item` was selected.
+fn reopens_after(keep_open_on_check: bool, item: &MenuChild) -> bool {
+    keep_open_on_check && item.item_type() == MenuItemType::Check
+}
+
 // SAFETY:
 // HWND validity is upheld by caller
 unsafe fn show_context_menu(
     hwnd: windows_sys::Win32::Foundation::HWND,
     hmenu: HMENU,
-    position: Option<Position>,
+    placement: Placement,
+    select: Option<usize>,
+    side: CascadeSide,
+) -> Option<u32> {
+    TRACKED_POPUPS.with_borrow_mut(|popups| popups.push((hmenu, hwnd)));
+    let result = track_context_menu(hwnd, hmenu, placement, select, side);
+    TRACKED_POPUPS.with_borrow_mut(|popups| {
+        if let Some(i) = popups.iter().rposition(|p| *p == (hmenu, hwnd)) {
+            popups.remove(i);
+        }
+    });
+    result
+}
+
+thread_local! {
+    /// Popup menus being tracked by [`show_context_menu`] and the windows tracking them.
+    static TRACKED_POPUPS: RefCell<Vec<(HMENU, windows_sys::Win32::Foundation::HWND)>> =
+        const { RefCell::new(Vec::new()) };
+}
+
+/// Cancels the popup menu `hmenu` if it is being tracked, see [`ContextMenu::dismiss`](crate::ContextMenu::dismiss).
+pub(crate) fn dismiss_context_menu(hmenu: isize) {
+    let hwnd = TRACKED_POPUPS.with_borrow(|popups| {
+        popups
+            .iter()
+            .rev()
+            .find(|(h, _)| *h as isize == hmenu)
+            .map(|(_, hwnd)| *hwnd)
+    });
+    if let Some(hwnd) = hwnd {
+        unsafe { PostMessageW(hwnd, WM_CANCELMODE, 0, 0) };
+    }
+}
+
+unsafe fn track_context_menu(
+    hwnd: windows_sys::Win32::Foundation::HWND,
+    hmenu: HMENU,
+    placement: Placement,
+    select: Option<usize>,
+    side: CascadeSide,
 ) -> Option<u32> {
+    // the keys are handled by the menu loop of `TrackPopupMenu` once it starts
+    for _ in 0..select
+        .and_then(|i| select_key_presses(hmenu, i))
+        .unwrap_or(0)
+    {
+        PostMessageW(hwnd, WM_KEYDOWN, VK_DOWN as _, 0);
+    }
+
+    let (position, in_screen) = match placement {
+        Placement::At(position) => (position, false),
+        Placement::AtScreen(position) => (Some(position), true),
+        Placement::BelowControl(position, size) => {
+            return show_dropdown_menu(hwnd, hmenu, position, size)
+        }
+    };
+
     let result = unsafe {
-        let pt = if let Some(pos) = position {
-            let dpi = util::hwnd_dpi(hwnd);
-            let scale_factor = util::dpi_to_scale_factor(dpi);
-            let pos = pos.to_physical::<i32>(scale_factor);
-            let mut pt = POINT {
-                x: pos.x as _,
-                y: pos.y as _,
-            };
-            ClientToScreen(hwnd, &mut pt);
-            pt
-        } else {
-            let mut pt = POINT { x: 0, y: 0 };
-            GetCursorPos(&mut pt);
-            pt
-        };
+        let pt = tracking_point(hwnd, position, in_screen);
         SetForegroundWindow(hwnd);
         TrackPopupMenu(
             hmenu,
-            TPM_LEFTALIGN | TPM_RETURNCMD,
+            tracking_flags(side) | TPM_RETURNCMD,
             pt.x,
             pt.y,
             0,
@@ -1041,6 +2768,141 @@ unsafe fn show_context_menu(
     (result > 0).then_some(result.try_into().ok()).flatten()
 }
 
+/// The screen point a popup is tracked at, `position` is in screen coordinates if `in_screen`,
+/// otherwise relative to the client area of `hwnd`, and the cursor position if `None`.
+unsafe fn tracking_point(
+    hwnd: windows_sys::Win32::Foundation::HWND,
+    position: Option<Position>,
+    in_screen: bool,
+) -> POINT {
+    let Some(pos) = position else {
+        let mut pt = POINT { x: 0, y: 0 };
+        GetCursorPos(&mut pt);
+        return pt;
+    };
+
+    let dpi = util::hwnd_dpi(hwnd);
+    let scale_factor = util::dpi_to_scale_factor(dpi);
+    let pos = pos.to_physical::<i32>(scale_factor);
+    let mut pt = POINT {
+        x: pos.x as _,
+        y: pos.y as _,
+    };
+    if !in_screen {
+        ClientToScreen(hwnd, &mut pt);
+    }
+    pt
+}
+
+/// The alignment flags of `TrackPopupMenu` for a popup whose menus cascade to `side`,
+/// the system cascades submenus on the side the popup opened to.
+fn tracking_flags(side: CascadeSide) -> u32 {
+    match side {
+        // menus drop to the left when the handedness setting of Windows asks for it
+        CascadeSide::Auto if unsafe { GetSystemMetrics(SM_MENUDROPALIGNMENT) } != 0 => {
+            TPM_RIGHTALIGN
+        }
+        CascadeSide::Left => TPM_RIGHTALIGN,
+        CascadeSide::Auto | CascadeSide::Right => TPM_LEFTALIGN,
+    }
+}
+
+/// The number of down arrow presses that highlight the item at `index` of `hmenu`
+/// when it opens, separators are skipped by the keyboard navigation.
+///
+/// Returns `None` if there is no such item or if it is a separator.
+unsafe fn select_key_presses(hmenu: HMENU, index: usize) -> Option<usize> {
+    let count = GetMenuItemCount(hmenu);
+    if count < 0 || index >= count as usize {
+        return None;
+    }
+
+    let mut presses = 0;
+    for position in 0..=index {
+        let mut info: MENUITEMINFOW = std::mem::zeroed();
+        info.cbSize = std::mem::size_of::<MENUITEMINFOW>() as _;
+        info.fMask = MIIM_FTYPE;
+        GetMenuItemInfoW(hmenu, position as _, true.into(), &mut info);
+
+        if info.fType & MFT_SEPARATOR == 0 {
+            presses += 1;
+        } else if position == index {
+            return None;
+        }
+    }
+    Some(presses)
+}
+
+unsafe fn show_dropdown_menu(
+    hwnd: windows_sys::Win32::Foundation::HWND,
+    hmenu: HMENU,
+    position: Position,
+    size: Size,
+) -> Option<u32> {
+    let scale_factor = util::dpi_to_scale_factor(util::hwnd_dpi(hwnd));
+    let position = position.to_physical::<i32>(scale_factor);
+    let size = size.to_physical::<i32>(scale_factor);
+
+    let mut top_left = POINT {
+        x: position.x,
+        y: position.y,
+    };
+    ClientToScreen(hwnd, &mut top_left);
+    let control = RECT {
+        left: top_left.x,
+        top: top_left.y,
+        right: top_left.x + size.width,
+        bottom: top_left.y + size.height,
+    };
+
+    let mut monitor: MONITORINFO = std::mem::zeroed();
+    monitor.cbSize = std::mem::size_of::<MONITORINFO>() as _;
+    GetMonitorInfoW(
+        MonitorFromRect(&control, MONITOR_DEFAULTTONEAREST),
+        &mut monitor,
+    );
+
+    let menu = owner_draw::measure_menu(hwnd, hmenu).unwrap_or_default();
+    let pt = dropdown_position(&control, menu, &monitor.rcWork);
+
+    // the control is excluded so the system never covers it when adjusting the position
+    let params = TPMPARAMS {
+        cbSize: std::mem::size_of::<TPMPARAMS>() as _,
+        rcExclude: control,
+    };
+
+    SetForegroundWindow(hwnd);
+    let result = TrackPopupMenuEx(
+        hmenu,
+        TPM_LEFTALIGN | TPM_TOPALIGN | TPM_VERTICAL | TPM_RETURNCMD,
+        pt.x,
+        pt.y,
+        hwnd,
+        &params,
+    );
+    (result > 0).then_some(result.try_into().ok()).flatten()
+}
+
+/// The top-left corner of a dropdown menu of size `menu` for `control`: aligned to its left edge,
+/// below it when the menu fits in `work_area`, otherwise above it if it fits there, all in screen coordinates.
+fn dropdown_position(control: &RECT, menu: PhysicalSize<u32>, work_area: &RECT) -> POINT {
+    let (width, height) = (menu.width as i32, menu.height as i32);
+
+    let x = control
+        .left
+        .min(work_area.right - width)
+        .max(work_area.left);
+    let fits_below = control.bottom + height <= work_area.bottom;
+    let fits_above = control.top - height >= work_area.top;
+    let y = if fits_below || !fits_above {
+        control.bottom
+    } else {
+        control.top - height
+    };
+
+    POINT { x, y }
+}
+
 struct AccelAction;
 
 impl AccelAction {
@@ -1061,6 +2923,11 @@ impl AccelAction {
     }
 
     fn update_store(haccel_store: &mut RefMut<AccelWrapper>) {
+        // rebuilt once by `Menu::end_update`
+        if let Some(rebuild) = &mut haccel_store.2 {
+            *rebuild = true;
+            return;
+        }
         unsafe {
             DestroyAcceleratorTable(haccel_store.0);
             let len = haccel_store.1.len();
@@ -1078,106 +2945,488 @@ fn create_icon_item_info(hbitmap: HBITMAP) -> MENUITEMINFOW {
     info
 }
 
-fn dwrefdata_from_obj<T>(obj: &T) -> usize {
-    (obj as *const T) as usize
+fn dwrefdata_from_obj<T>(obj: &T) -> usize {
+    (obj as *const T) as usize
+}
+
+unsafe fn obj_from_dwrefdata<T>(dwrefdata: usize) -> &'static mut T {
+    unsafe { (dwrefdata as *mut T).as_mut().unwrap() }
+}
+
+const MENU_SUBCLASS_ID: usize = 200;
+const MENU_UPDATE_THEME: u32 = 201;
+const SUBMENU_SUBCLASS_ID: usize = 202;
+/// Posted to the owner window when the about dialog of the item with id `wparam` is dismissed.
+const MENU_ABOUT_CLOSED: u32 = 203;
+/// Posted to the windows of a menu bar when an item hidden from it is shown again.
+const MENU_SHOW_ITEMS: u32 = 204;
+/// Posted to the owner window when the popup `wparam` of a submenu that clears on close
+/// is closed, the command of an item clicked in it is posted before and handled first.
+const MENU_CLEAR_CLOSED: u32 = 205;
+
+unsafe extern "system" fn menu_subclass_proc(
+    hwnd: windows_sys::Win32::Foundation::HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+    uidsubclass: usize,
+    dwrefdata: usize,
+) -> LRESULT {
+    let default = || Some(DefSubclassProc(hwnd as _, msg, wparam, lparam));
+    handle_message(hwnd, msg, wparam, lparam, uidsubclass, dwrefdata, default).unwrap_or(0)
+}
+
+/// Handles the menu related messages of the subclass `uidsubclass`.
+///
+/// `default` runs the default processing of the message, its result is returned for messages
+/// that aren't fully handled here, `None` means the message still needs default processing.
+unsafe fn handle_message<F: Fn() -> Option<LRESULT>>(
+    hwnd: windows_sys::Win32::Foundation::HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+    uidsubclass: usize,
+    dwrefdata: usize,
+    default: F,
+) -> Option<LRESULT> {
+    if uidsubclass == MENU_SUBCLASS_ID {
+        let menu = obj_from_dwrefdata::<Menu>(dwrefdata);
+        if let Some(result) = menu.call_raw_message_hook(hwnd as _, msg, wparam, lparam) {
+            return Some(result);
+        }
+    }
+
+    match msg {
+        MENU_UPDATE_THEME if uidsubclass == MENU_SUBCLASS_ID => {
+            let menu = obj_from_dwrefdata::<Menu>(dwrefdata);
+            let theme: MenuTheme = std::mem::transmute(lparam);
+            menu.hwnds.borrow_mut().insert(hwnd as _, theme);
+            if GetActiveWindow() == hwnd {
+                PostMessageW(hwnd, WM_NCACTIVATE, 0, 0);
+                PostMessageW(hwnd, WM_NCACTIVATE, true.into(), 0);
+            } else {
+                PostMessageW(hwnd, WM_NCACTIVATE, true.into(), 0);
+                PostMessageW(hwnd, WM_NCACTIVATE, 0, 0);
+            }
+            Some(0)
+        }
+
+        WM_COMMAND => {
+            let id = util::LOWORD(wparam as _) as u32;
+            // the high-order word is 1 when the command originates from an accelerator
+            let from_accelerator = util::HIWORD(wparam as _) == 1;
+
+            if let Some(item) = find_by_id_in_subclass(uidsubclass, dwrefdata, id) {
+                // hidden items keep their accelerator but can't be activated
+                if from_accelerator && !item.borrow().is_visible() {
+                    return Some(0);
+                }
+                if uidsubclass == MENU_SUBCLASS_ID {
+                    let menu = obj_from_dwrefdata::<Menu>(dwrefdata);
+                    let accelerator = item.borrow().accelerator;
+                    if menu.read_only
+                        || (from_accelerator && menu.intercepts_accelerator(accelerator))
+                    {
+                        return Some(0);
+                    }
+                    // accelerators of disabled items only give feedback
+                    if from_accelerator && !item.borrow().is_enabled() {
+                        if menu.beep_on_disabled {
+                            MessageBeep(MB_OK);
+                        }
+                        return Some(0);
+                    }
+                }
+
+                menu_selected(hwnd, &item);
+                Some(0)
+            } else {
+                default()
+            }
+        }
+
+        WM_MEASUREITEM => {
+            let mis = &mut *(lparam as *mut MEASUREITEMSTRUCT);
+            let item = (mis.CtlType == ODT_MENU)
+                .then(|| find_by_id_in_subclass(uidsubclass, dwrefdata, mis.itemID))
+                .flatten();
+
+            if let Some(item) = item {
+                owner_draw::measure_item(hwnd, &item.borrow(), mis);
+                Some(1)
+            } else {
+                default()
+            }
+        }
+
+        WM_DRAWITEM => {
+            let dis = &*(lparam as *const DRAWITEMSTRUCT);
+            let item = (dis.CtlType == ODT_MENU)
+                .then(|| find_by_id_in_subclass(uidsubclass, dwrefdata, dis.itemID))
+                .flatten();
+
+            if let Some(item) = item {
+                let palette = if popups_are_dark(hwnd, uidsubclass, dwrefdata) {
+                    owner_draw::Palette::dark()
+                } else {
+                    owner_draw::Palette::system()
+                };
+                owner_draw::draw_item(&item.borrow(), dis, &palette);
+                Some(1)
+            } else {
+                default()
+            }
+        }
+
+        WM_UAHDRAWMENUITEM | WM_UAHDRAWMENU if uidsubclass == MENU_SUBCLASS_ID => {
+            let menu = obj_from_dwrefdata::<Menu>(dwrefdata);
+            let theme = menu.theme_for_hwnd(hwnd as _).unwrap_or(MenuTheme::Auto);
+            if theme.should_use_dark(hwnd as _) {
+                dark_menu_bar::draw(hwnd as _, msg, wparam, lparam);
+                Some(0)
+            } else {
+                default()
+            }
+        }
+        WM_MENUSELECT => {
+            let flags = util::HIWORD(wparam as _) as u32;
+            let position = util::LOWORD(wparam as _) as u32;
+
+            if uidsubclass == MENU_SUBCLASS_ID {
+                let menu = obj_from_dwrefdata::<Menu>(dwrefdata);
+                menu.update_highlighted_item(hwnd as _, wparam, lparam);
+                // Up and Down open the menus of the bar instead of navigating it
+                let wrap = menu.nav_wrap || lparam as HMENU == menu.hmenu;
+                nav_wrap::set_highlighted(lparam as _, flags, position, wrap);
+            }
+            let clickable_submenu = if flags & MF_POPUP != 0 {
+                let submenu = GetSubMenu(lparam as _, position as _);
+                find_by_id_in_subclass(uidsubclass, dwrefdata, submenu as u32)
+                    .filter(|item| item.borrow().title_clickable)
+                    .map(|_| submenu)
+            } else {
+                None
+            };
+            title_click::set_highlighted(hwnd, lparam as _, position, clickable_submenu);
+            default()
+        }
+
+        MENU_SHOW_ITEMS if uidsubclass == MENU_SUBCLASS_ID => {
+            obj_from_dwrefdata::<Menu>(dwrefdata).insert_shown_items();
+            DrawMenuBar(hwnd);
+            Some(0)
+        }
+
+        MENU_ABOUT_CLOSED => {
+            if let Some(item) = find_by_id_in_subclass(uidsubclass, dwrefdata, wparam as u32) {
+                run_after_action(&item);
+            }
+            Some(0)
+        }
+
+        title_click::MENU_TITLE_CLICKED => {
+            let item =
+                find_by_id_in_subclass(uidsubclass, dwrefdata, wparam as u32).filter(|item| {
+                    let item = item.borrow();
+                    item.title_clickable && item.is_enabled()
+                });
+            if let Some(item) = item {
+                if uidsubclass == MENU_SUBCLASS_ID
+                    && obj_from_dwrefdata::<Menu>(dwrefdata).read_only
+                {
+                    return Some(0);
+                }
+                menu_selected(hwnd, &item);
+            }
+            Some(0)
+        }
+
+        // reveal a hidden menu bar while it is activated with the keyboard (Alt or F10)
+        WM_SYSCOMMAND
+            if uidsubclass == MENU_SUBCLASS_ID && wparam as u32 & 0xFFF0 == SC_KEYMENU =>
+        {
+            let menu = obj_from_dwrefdata::<Menu>(dwrefdata);
+            // `lparam` is the character pressed with Alt, Space opens the window menu instead
+            if !menu.alt_activates_bar && lparam != ' ' as isize {
+                return Some(0);
+            }
+            if menu.hidden_bars.contains(&(hwnd as _)) && GetMenu(hwnd).is_null() {
+                SetMenu(hwnd, menu.hmenu);
+                DrawMenuBar(hwnd);
+            }
+            default()
+        }
+
+        // context menus are counted while they are shown, the window may not be subclassed
+        WM_ENTERMENULOOP if uidsubclass == MENU_SUBCLASS_ID && wparam == 0 => {
+            let menu = obj_from_dwrefdata::<Menu>(dwrefdata);
+            menu.tracking.set(menu.tracking.get() + 1);
+            default()
+        }
+
+        WM_EXITMENULOOP if uidsubclass == MENU_SUBCLASS_ID => {
+            let menu = obj_from_dwrefdata::<Menu>(dwrefdata);
+            if wparam == 0 {
+                menu.tracking.set(menu.tracking.get().saturating_sub(1));
+            }
+            menu.highlighted_items.remove(&(hwnd as _));
+            // `wparam` is `TRUE` for context menus, which don't reveal the bar
+            if wparam == 0 && menu.hidden_bars.contains(&(hwnd as _)) {
+                SetMenu(hwnd, std::ptr::null_mut());
+                DrawMenuBar(hwnd);
+            }
+            default()
+        }
+
+        WM_INITMENUPOPUP => {
+            build_lazy_popup(uidsubclass, dwrefdata, wparam as _);
+            insert_shown_items(uidsubclass, dwrefdata);
+            // before the items are themed, so items added by the handler are too
+            submenu_event(
+                uidsubclass,
+                dwrefdata,
+                wparam as _,
+                SubmenuEventKind::Opened,
+            );
+            let dark = popups_are_dark(hwnd, uidsubclass, dwrefdata);
+            theme_check_glyphs(uidsubclass, dwrefdata, wparam as _, dark);
+            default()
+        }
+
+        WM_UNINITMENUPOPUP => {
+            submenu_event(
+                uidsubclass,
+                dwrefdata,
+                wparam as _,
+                SubmenuEventKind::Closed,
+            );
+            if popup_clears_on_close(uidsubclass, dwrefdata, wparam as _) {
+                PostMessageW(hwnd, MENU_CLEAR_CLOSED, wparam, 0);
+            }
+            default()
+        }
+
+        MENU_CLEAR_CLOSED => {
+            clear_closed_popup(uidsubclass, dwrefdata, wparam as _);
+            default()
+        }
+
+        WM_NCACTIVATE | WM_NCPAINT => {
+            // the default processing needs to run before calling the
+            // custom dark menu redraw
+            let res = default()?;
+
+            let menu = obj_from_dwrefdata::<Menu>(dwrefdata);
+            let theme = menu.theme_for_hwnd(hwnd as _).unwrap_or(MenuTheme::Auto);
+            if theme.should_use_dark(hwnd as _) {
+                dark_menu_bar::draw(hwnd as _, msg, wparam, lparam);
+            }
+
+            Some(res)
+        }
+
+        _ => default(),
+    }
+}
+
+/// Stops painting the visible windows among `hwnds`, returns them for [`resume_redraw`].
+fn suspend_redraw(hwnds: impl IntoIterator<Item = Hwnd>) -> Vec<Hwnd> {
+    let hwnds = hwnds
+        .into_iter()
+        .filter(|hwnd| unsafe { IsWindowVisible(*hwnd as _) } != 0)
+        .collect::<Vec<_>>();
+    for hwnd in &hwnds {
+        unsafe { SendMessageW(*hwnd as _, WM_SETREDRAW, false.into(), 0) };
+    }
+    hwnds
 }
 
-unsafe fn obj_from_dwrefdata<T>(dwrefdata: usize) -> &'static mut T {
-    unsafe { (dwrefdata as *mut T).as_mut().unwrap() }
+/// Paints the windows stopped by [`suspend_redraw`] again and redraws their menu bars.
+fn resume_redraw(hwnds: &[Hwnd]) {
+    for hwnd in hwnds {
+        unsafe {
+            SendMessageW(*hwnd as _, WM_SETREDRAW, true.into(), 0);
+            DrawMenuBar(*hwnd as _);
+        }
+    }
 }
 
-const MENU_SUBCLASS_ID: usize = 200;
-const MENU_UPDATE_THEME: u32 = 201;
-const SUBMENU_SUBCLASS_ID: usize = 202;
+/// Depth-first search of `children` and their nested submenus for the submenu with the popup `hmenu`.
+fn find_by_hmenu(
+    hmenu: HMENU,
+    children: &[Rc<RefCell<MenuChild>>],
+) -> Option<Rc<RefCell<MenuChild>>> {
+    for child in children {
+        let child_ = child.borrow();
+        if child_.item_type != MenuItemType::Submenu {
+            continue;
+        }
+        if child_.hmenu == hmenu || child_.hpopupmenu == hmenu {
+            return Some(child.clone());
+        }
+        if let Some(found) = find_by_hmenu(hmenu, child_.children.as_ref().unwrap()) {
+            return Some(found);
+        }
+    }
+    None
+}
 
-unsafe extern "system" fn menu_subclass_proc(
-    hwnd: windows_sys::Win32::Foundation::HWND,
-    msg: u32,
-    wparam: WPARAM,
-    lparam: LPARAM,
+/// Sends a [`SubmenuEvent`] for the submenu with the popup `hmenu`, if it belongs to the subclass.
+unsafe fn submenu_event(
     uidsubclass: usize,
     dwrefdata: usize,
-) -> LRESULT {
-    match msg {
-        MENU_UPDATE_THEME if uidsubclass == MENU_SUBCLASS_ID => {
-            let menu = obj_from_dwrefdata::<Menu>(dwrefdata);
-            let theme: MenuTheme = std::mem::transmute(lparam);
-            menu.hwnds.borrow_mut().insert(hwnd as _, theme);
-            if GetActiveWindow() == hwnd {
-                PostMessageW(hwnd, WM_NCACTIVATE, 0, 0);
-                PostMessageW(hwnd, WM_NCACTIVATE, true.into(), 0);
+    hmenu: HMENU,
+    kind: SubmenuEventKind,
+) {
+    let id = match uidsubclass {
+        MENU_SUBCLASS_ID => find_by_hmenu(hmenu, &obj_from_dwrefdata::<Menu>(dwrefdata).children)
+            .map(|submenu| submenu.borrow().id.clone()),
+        SUBMENU_SUBCLASS_ID => {
+            let submenu = obj_from_dwrefdata::<MenuChild>(dwrefdata);
+            if submenu.hpopupmenu == hmenu {
+                Some(submenu.id.clone())
             } else {
-                PostMessageW(hwnd, WM_NCACTIVATE, true.into(), 0);
-                PostMessageW(hwnd, WM_NCACTIVATE, 0, 0);
+                find_by_hmenu(hmenu, submenu.children.as_ref().unwrap())
+                    .map(|submenu| submenu.borrow().id.clone())
             }
-            0
         }
+        _ => unreachable!(),
+    };
 
-        WM_COMMAND => {
-            let id = util::LOWORD(wparam as _) as u32;
-
-            let item = match uidsubclass {
-                MENU_SUBCLASS_ID => {
-                    let menu = obj_from_dwrefdata::<Menu>(dwrefdata);
-                    menu.find_by_id(id)
-                }
-                SUBMENU_SUBCLASS_ID => {
-                    let menu = obj_from_dwrefdata::<MenuChild>(dwrefdata);
-                    menu.find_by_id(id)
-                }
-                _ => unreachable!(),
-            };
+    // no item is borrowed here, so the handler can change the submenu
+    if let Some(id) = id {
+        SubmenuEvent::send(SubmenuEvent { id, kind });
+    }
+}
 
-            if let Some(item) = item {
-                menu_selected(hwnd, &mut item.borrow_mut());
-                0
-            } else {
-                DefSubclassProc(hwnd as _, msg, wparam, lparam)
+/// Builds the lazy submenu with the popup `hmenu` before it opens, if it belongs to the subclass.
+unsafe fn build_lazy_popup(uidsubclass: usize, dwrefdata: usize, hmenu: HMENU) {
+    let found = match uidsubclass {
+        MENU_SUBCLASS_ID => find_by_hmenu(hmenu, &obj_from_dwrefdata::<Menu>(dwrefdata).children),
+        SUBMENU_SUBCLASS_ID => {
+            let submenu = obj_from_dwrefdata::<MenuChild>(dwrefdata);
+            // a submenu shown as a context menu is borrowed, it was built before
+            if submenu.hpopupmenu == hmenu {
+                return;
             }
+            find_by_hmenu(hmenu, submenu.children.as_ref().unwrap())
         }
+        _ => unreachable!(),
+    };
+    if let Some(submenu) = found {
+        lazy::build(&submenu);
+    }
+}
 
-        WM_UAHDRAWMENUITEM | WM_UAHDRAWMENU if uidsubclass == MENU_SUBCLASS_ID => {
-            let menu = obj_from_dwrefdata::<Menu>(dwrefdata);
-            let theme = menu
-                .hwnds
-                .borrow()
-                .get(&(hwnd as _))
-                .copied()
-                .unwrap_or(MenuTheme::Auto);
-            if theme.should_use_dark(hwnd as _) {
-                dark_menu_bar::draw(hwnd as _, msg, wparam, lparam);
-                0
-            } else {
-                DefSubclassProc(hwnd as _, msg, wparam, lparam)
+/// Whether the submenu with the popup `hmenu` clears on close, if it belongs to the subclass.
+unsafe fn popup_clears_on_close(uidsubclass: usize, dwrefdata: usize, hmenu: HMENU) -> bool {
+    let found = match uidsubclass {
+        MENU_SUBCLASS_ID => find_by_hmenu(hmenu, &obj_from_dwrefdata::<Menu>(dwrefdata).children),
+        SUBMENU_SUBCLASS_ID => {
+            let submenu = obj_from_dwrefdata::<MenuChild>(dwrefdata);
+            // the submenu may be borrowed while it is shown as a context menu
+            if submenu.hpopupmenu == hmenu {
+                return submenu.clear_on_close;
             }
+            find_by_hmenu(hmenu, submenu.children.as_ref().unwrap())
         }
-        WM_NCACTIVATE | WM_NCPAINT => {
-            // DefSubclassProc needs to be called before calling the
-            // custom dark menu redraw
-            let res = DefSubclassProc(hwnd as _, msg, wparam, lparam);
+        _ => unreachable!(),
+    };
+    found.is_some_and(|s| s.borrow().clear_on_close)
+}
 
-            let menu = obj_from_dwrefdata::<Menu>(dwrefdata);
-            let theme = menu
-                .hwnds
-                .borrow()
-                .get(&(hwnd as _))
-                .copied()
-                .unwrap_or(MenuTheme::Auto);
-            if theme.should_use_dark(hwnd as _) {
-                dark_menu_bar::draw(hwnd as _, msg, wparam, lparam);
+/// Removes the items of the submenu `hmenu` if it was closed and clears on close.
+unsafe fn clear_closed_popup(uidsubclass: usize, dwrefdata: usize, hmenu: HMENU) {
+    let found = match uidsubclass {
+        MENU_SUBCLASS_ID => find_by_hmenu(hmenu, &obj_from_dwrefdata::<Menu>(dwrefdata).children),
+        SUBMENU_SUBCLASS_ID => {
+            let submenu = obj_from_dwrefdata::<MenuChild>(dwrefdata);
+            // the submenu may be borrowed while it is shown as a context menu
+            if submenu.hpopupmenu == hmenu {
+                if submenu.clear_on_close {
+                    let _ = submenu.set_items(&[]);
+                }
+                return;
             }
+            find_by_hmenu(hmenu, submenu.children.as_ref().unwrap())
+        }
+        _ => unreachable!(),
+    };
+
+    if let Some(submenu) = found.filter(|s| s.borrow().clear_on_close) {
+        let _ = submenu.borrow_mut().set_items(&[]);
+    }
+}
+
+/// Whether the popups shown by `hwnd` use the dark theme.
+unsafe fn popups_are_dark(
+    hwnd: windows_sys::Win32::Foundation::HWND,
+    uidsubclass: usize,
+    dwrefdata: usize,
+) -> bool {
+    let theme = match uidsubclass {
+        MENU_SUBCLASS_ID => obj_from_dwrefdata::<Menu>(dwrefdata).theme_for_hwnd(hwnd as _),
+        _ => None,
+    };
+    theme.unwrap_or(MenuTheme::Auto).should_use_dark(hwnd as _)
+}
+
+/// Makes the check items of the popup `hmenu` owner-drawn while it is `dark`, the system
+/// draws their check marks and radio bullets dark on the dark background otherwise.
+unsafe fn theme_check_glyphs(uidsubclass: usize, dwrefdata: usize, hmenu: HMENU, dark: bool) {
+    for position in 0..GetMenuItemCount(hmenu).max(0) {
+        let id = GetMenuItemID(hmenu, position);
+        let Some(item) = find_by_id_in_subclass(uidsubclass, dwrefdata, id) else {
+            continue;
+        };
+        let item = item.borrow();
+        if item.item_type == MenuItemType::Check {
+            let owner_drawn = dark || item.is_owner_drawn_in(false);
+            owner_draw::set_owner_drawn(hmenu, id, owner_drawn);
+        }
+    }
+}
 
-            res
+/// Inserts the items of the menu of the subclass that were shown again since they were hidden.
+unsafe fn insert_shown_items(uidsubclass: usize, dwrefdata: usize) {
+    match uidsubclass {
+        MENU_SUBCLASS_ID => obj_from_dwrefdata::<Menu>(dwrefdata).insert_shown_items(),
+        SUBMENU_SUBCLASS_ID => {
+            let submenu = obj_from_dwrefdata::<MenuChild>(dwrefdata);
+            visibility::insert_shown(
+                [submenu.hmenu, submenu.hpopupmenu],
+                submenu.children.as_ref().unwrap(),
+            );
         }
+        _ => unreachable!(),
+    }
+}
 
-        _ => DefSubclassProc(hwnd as _, msg, wparam, lparam),
+unsafe fn find_by_id_in_subclass(
+    uidsubclass: usize,
+    dwrefdata: usize,
+    id: u32,
+) -> Option<Rc<RefCell<MenuChild>>> {
+    match uidsubclass {
+        MENU_SUBCLASS_ID => {
+            let menu = obj_from_dwrefdata::<Menu>(dwrefdata);
+            menu.find_by_id(id)
+        }
+        SUBMENU_SUBCLASS_ID => {
+            let menu = obj_from_dwrefdata::<MenuChild>(dwrefdata);
+            menu.find_by_id(id)
+        }
+        _ => unreachable!(),
     }
 }
 
-unsafe fn menu_selected(hwnd: windows_sys::Win32::Foundation::HWND, item: &mut MenuChild) {
+unsafe fn menu_selected(hwnd: windows_sys::Win32::Foundation::HWND, item: &Rc<RefCell<MenuChild>>) {
     let (mut dispatch, mut menu_id) = (true, None);
+    let mut after_action = false;
+    let mut change = StateChange::default();
 
-    {
+    let roots = {
+        let item = &mut *item.borrow_mut();
         if item.item_type() == MenuItemType::Predefined {
             dispatch = false;
         } else {
@@ -1186,11 +3435,15 @@ unsafe fn menu_selected(hwnd: windows_sys::Win32::Foundation::HWND, item: &mut M
 
         match item.item_type() {
             MenuItemType::Check => {
-                let checked = !item.checked;
-                item.set_checked(checked);
+                // a selected radio item stays checked
+                let checked = !item.checked || item.radio_group.is_some();
+                change = item.set_checked(checked);
             }
             MenuItemType::Predefined => {
                 if let Some(predefined_item_type) = &item.predefined_item_type {
+                    // the about dialog runs the action once it is dismissed
+                    after_action =
+                        !matches!(predefined_item_type, PredefinedMenuItemType::About(Some(_)));
                     match predefined_item_type {
                         PredefinedMenuItemType::Copy => execute_edit_command(EditCommand::Copy),
                         PredefinedMenuItemType::Cut => execute_edit_command(EditCommand::Cut),
@@ -1215,7 +3468,7 @@ unsafe fn menu_selected(hwnd: windows_sys::Win32::Foundation::HWND, item: &mut M
                             PostQuitMessage(0);
                         }
                         PredefinedMenuItemType::About(Some(ref metadata)) => {
-                            show_about_dialog(hwnd as _, metadata)
+                            show_about_dialog(hwnd as _, metadata, item.internal_id)
                         }
 
                         _ => {}
@@ -1224,12 +3477,24 @@ unsafe fn menu_selected(hwnd: windows_sys::Win32::Foundation::HWND, item: &mut M
             }
             _ => {}
         }
-    }
 
+        item.root_states.values().cloned().collect::<Vec<_>>()
+    };
+
+    // the item isn't borrowed anymore, so handlers can use it
+    change.notify();
+    if after_action {
+        run_after_action(item);
+    }
+    radio::uncheck_others(item);
     if dispatch {
-        MenuEvent::send(MenuEvent {
+        let event = MenuEvent {
             id: menu_id.unwrap(),
-        });
+        };
+        MenuEvent::send(event.clone());
+        for root in roots {
+            root.notify_item(&event);
+        }
     }
 }
 
@@ -1280,15 +3545,31 @@ fn execute_edit_command(command: EditCommand) {
     }
 }
 
-fn show_about_dialog(hwnd: Hwnd, metadata: &AboutMetadata) {
+/// Calls the [`MenuChild::set_after_action`] callback of `item`, if any.
+fn run_after_action(item: &Rc<RefCell<MenuChild>>) {
+    let after_action = item.borrow().after_action.clone();
+    if let Some(after_action) = after_action {
+        (after_action.0)();
+    }
+}
+
+/// Shows the about dialog on another thread, then posts [`MENU_ABOUT_CLOSED`]
+/// for the item `id` to `hwnd` once it is dismissed.
+fn show_about_dialog(hwnd: Hwnd, metadata: &AboutMetadata, id: u32) {
     use std::fmt::Write;
 
     let mut message = String::new();
     if let Some(name) = &metadata.name {
         let _ = writeln!(&mut message, "Name: {}", name);
     }
-    if let Some(version) = &metadata.full_version() {
-        let _ = writeln!(&mut message, "Version: {}", version);
+    if let Some(version) = &metadata.detailed_version() {
+        // the build info has its own prefix
+        let prefix = if metadata.version.is_some() {
+            "Version: "
+        } else {
+            ""
+        };
+        let _ = writeln!(&mut message, "{}{}", prefix, version);
     }
     if let Some(authors) = &metadata.authors {
         let _ = writeln!(&mut message, "Authors: {}", authors.join(", "));
@@ -1330,6 +3611,7 @@ fn show_about_dialog(hwnd: Hwnd, metadata: &AboutMetadata) {
             title.as_ptr(),
             MB_ICONINFORMATION,
         );
+        PostMessageW(hwnd as _, MENU_ABOUT_CLOSED, id as _, 0);
     });
 
     #[cfg(feature = "common-controls-v6")]
@@ -1380,7 +3662,8 @@ fn show_about_dialog(hwnd: Hwnd, metadata: &AboutMetadata) {
                 &mut pn_button,
                 &mut pn_radio_button,
                 &mut pf_verification_flag_checked,
-            )
+            );
+            PostMessageW(hwnd as _, MENU_ABOUT_CLOSED, id as _, 0);
         });
     }
 }
@@ -1472,3 +3755,698 @@ impl MenuItemKind {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{
+        rc::Rc,
+        sync::{Arc, Mutex},
+    };
+
+    use windows_sys::Win32::UI::WindowsAndMessaging::WM_COMMAND;
+
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        GetMenuItemInfoW, MENUITEMINFOW, MIIM_STRING,
+    };
+
+    use super::{decode_wide, dwrefdata_from_obj, menu_subclass_proc, Menu, MENU_SUBCLASS_ID};
+    use crate::{util::AddOp, IsMenuItem, MenuEvent, MenuItem, PredefinedMenuItem, SeparatorStyle};
+
+    /// The width and height `menu` measures for `item` when `hwnd` sends `WM_MEASUREITEM`,
+    /// `0` measures with the screen DPI.
+    fn measure(menu: &mut Menu, hwnd: isize, item: &dyn IsMenuItem) -> (u32, u32) {
+        use windows_sys::Win32::UI::{
+            Controls::{MEASUREITEMSTRUCT, ODT_MENU},
+            WindowsAndMessaging::WM_MEASUREITEM,
+        };
+
+        let mut mis: MEASUREITEMSTRUCT = unsafe { std::mem::zeroed() };
+        mis.CtlType = ODT_MENU;
+        mis.itemID = item.child().borrow().internal_id;
+        let handled = unsafe {
+            Menu::handle_message(menu, hwnd, WM_MEASUREITEM, 0, &mut mis as *mut _ as isize)
+        };
+        assert_eq!(handled, Some(1), "the item isn't in the menu");
+        (mis.itemWidth, mis.itemHeight)
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn raw_message_hook_suppresses_commands() {
+        let item = MenuItem::with_id("raw-hooked", "Item", true, None);
+        let mut menu = Menu::new(None);
+        menu.add_menu_item(&item, AddOp::Append).unwrap();
+
+        let seen = Arc::new(Mutex::new(0));
+        let seen_c = seen.clone();
+        let observer = MenuEvent::add_observer(move |e| {
+            if e.id == "raw-hooked" {
+                *seen_c.lock().unwrap() += 1;
+            }
+        });
+
+        let hwnd = 1;
+        let wparam = item.inner.borrow().internal_id as usize;
+        let dwrefdata = dwrefdata_from_obj(&menu);
+        let send_command = || unsafe {
+            menu_subclass_proc(
+                hwnd as _,
+                WM_COMMAND,
+                wparam,
+                0,
+                MENU_SUBCLASS_ID,
+                dwrefdata,
+            )
+        };
+
+        menu.set_raw_message_hook_for_hwnd(
+            hwnd,
+            Some(Rc::new(|msg, _, _| (msg == WM_COMMAND).then_some(42))),
+        );
+        assert_eq!(send_command(), 42);
+        assert_eq!(*seen.lock().unwrap(), 0);
+
+        menu.set_raw_message_hook_for_hwnd(hwnd, None);
+        assert_eq!(send_command(), 0);
+        assert_eq!(*seen.lock().unwrap(), 1);
+
+        MenuEvent::remove_observer(observer);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn dark_popups_draw_light_check_glyphs() {
+        use crate::{CheckMenuItem, MenuTheme};
+        use windows_sys::Win32::UI::WindowsAndMessaging::{
+            MFT_OWNERDRAW, MIIM_FTYPE, WM_INITMENUPOPUP,
+        };
+
+        let wrap = CheckMenuItem::new("Wrap", true, true, None);
+        let mut menu = Menu::new(None);
+        menu.add_menu_item(&wrap, AddOp::Append).unwrap();
+
+        let hwnd = 1;
+        let hpopupmenu = menu.hpopupmenu;
+        let dwrefdata = dwrefdata_from_obj(&menu);
+        let owner_drawn = |theme| unsafe {
+            menu.hwnds.borrow_mut().insert(hwnd, theme);
+            menu_subclass_proc(
+                hwnd as _,
+                WM_INITMENUPOPUP,
+                hpopupmenu as _,
+                0,
+                MENU_SUBCLASS_ID,
+                dwrefdata,
+            );
+
+            let mut info: MENUITEMINFOW = std::mem::zeroed();
+            info.cbSize = std::mem::size_of::<MENUITEMINFOW>() as _;
+            info.fMask = MIIM_FTYPE;
+            let id = wrap.inner.borrow().internal_id;
+            GetMenuItemInfoW(hpopupmenu, id, false.into(), &mut info);
+            info.fType & MFT_OWNERDRAW != 0
+        };
+        assert!(owner_drawn(MenuTheme::Dark));
+        assert!(!owner_drawn(MenuTheme::Light));
+
+        // the check mark is drawn with the light text color of the dark palette
+        let (rgba, width, height) = menu.render_to_bitmap(MenuTheme::Dark, 0).unwrap();
+        let gutter = unsafe { super::owner_draw::label_offset(&wrap.inner.borrow()) } as u32;
+        let light_gutter_pixels = (0..height)
+            .flat_map(|y| (0..gutter.min(width)).map(move |x| ((y * width + x) * 4) as usize))
+            .filter(|&i| rgba[i..i + 3].iter().all(|&c| c > 0xC0))
+            .count();
+        assert!(light_gutter_pixels > 0);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn nav_wrap_is_consulted_on_menu_select() {
+        use super::nav_wrap;
+        use windows_sys::Win32::UI::{
+            Input::KeyboardAndMouse::{VK_DOWN, VK_UP},
+            WindowsAndMessaging::{MF_HILITE, WM_MENUSELECT},
+        };
+
+        let first = MenuItem::new("First", true, None);
+        let last = MenuItem::new("Last", true, None);
+        let mut menu = Menu::new(None);
+        menu.add_menu_item(&first, AddOp::Append).unwrap();
+        menu.add_menu_item(&PredefinedMenuItem::separator(), AddOp::Append)
+            .unwrap();
+        menu.add_menu_item(&last, AddOp::Append).unwrap();
+        assert!(menu.nav_wraps());
+
+        let hpopupmenu = menu.hpopupmenu;
+        let dwrefdata = dwrefdata_from_obj(&menu);
+        let select = |item: &MenuItem| unsafe {
+            let id = item.inner.borrow().internal_id as usize & 0xFFFF;
+            menu_subclass_proc(
+                1 as _,
+                WM_MENUSELECT,
+                id | (MF_HILITE as usize) << 16,
+                hpopupmenu as _,
+                MENU_SUBCLASS_ID,
+                dwrefdata,
+            );
+        };
+
+        select(&last);
+        assert!(!unsafe { nav_wrap::blocks(VK_DOWN) });
+
+        menu.set_nav_wrap(false);
+        select(&last);
+        assert!(unsafe { nav_wrap::blocks(VK_DOWN) });
+        assert!(!unsafe { nav_wrap::blocks(VK_UP) });
+
+        select(&first);
+        assert!(unsafe { nav_wrap::blocks(VK_UP) });
+        assert!(!unsafe { nav_wrap::blocks(VK_DOWN) });
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn clicking_clickable_submenu_title_emits_event() {
+        use super::title_click::MENU_TITLE_CLICKED;
+        use crate::Submenu;
+
+        let submenu = Submenu::with_id("recent-title", "Recent", true);
+        let mut menu = Menu::new(None);
+        menu.add_menu_item(&submenu, AddOp::Append).unwrap();
+
+        let seen = Arc::new(Mutex::new(0));
+        let seen_c = seen.clone();
+        let observer = MenuEvent::add_observer(move |e| {
+            if e.id == "recent-title" {
+                *seen_c.lock().unwrap() += 1;
+            }
+        });
+
+        let wparam = submenu.inner.borrow().internal_id() as usize;
+        let dwrefdata = dwrefdata_from_obj(&menu);
+        let click_title = || unsafe {
+            menu_subclass_proc(
+                1 as _,
+                MENU_TITLE_CLICKED,
+                wparam,
+                0,
+                MENU_SUBCLASS_ID,
+                dwrefdata,
+            )
+        };
+
+        click_title();
+        assert_eq!(*seen.lock().unwrap(), 0);
+
+        submenu.set_title_clickable(true);
+        click_title();
+        assert_eq!(*seen.lock().unwrap(), 1);
+
+        MenuEvent::remove_observer(observer);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn msg_filter_hook_is_removed_with_its_last_user() {
+        use super::msg_filter::is_installed;
+        use crate::Submenu;
+
+        assert!(!is_installed());
+        let mut menu = Menu::new(None);
+        menu.set_nav_wrap(false);
+        let submenu = Submenu::new("File", true);
+        submenu.set_title_clickable(true);
+        // one hook for both features
+        assert!(is_installed());
+
+        drop(menu);
+        assert!(is_installed());
+        submenu.set_title_clickable(false);
+        assert!(!is_installed());
+
+        submenu.set_title_clickable(true);
+        drop(submenu);
+        assert!(!is_installed());
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn item_min_height_applies_to_measured_height() {
+        let item = MenuItem::new("Item", true, None);
+        let mut menu = Menu::new(None);
+        menu.add_menu_item(&item, AddOp::Append).unwrap();
+        assert!(!item.inner.borrow().is_owner_drawn());
+
+        menu.set_item_min_height(64);
+        assert!(item.inner.borrow().is_owner_drawn());
+
+        assert!(measure(&mut menu, 0, &item).1 >= 64);
+
+        menu.set_item_min_height(0);
+        assert!(!item.inner.borrow().is_owner_drawn());
+    }
+
+    #[test]
+    fn dropdown_is_placed_below_control_when_it_fits() {
+        use crate::dpi::PhysicalSize;
+        use windows_sys::Win32::Foundation::RECT;
+
+        let work_area = RECT {
+            left: 0,
+            top: 0,
+            right: 1920,
+            bottom: 1080,
+        };
+        let control = RECT {
+            left: 100,
+            top: 200,
+            right: 180,
+            bottom: 230,
+        };
+        let menu = PhysicalSize::new(150, 300);
+
+        let pt = super::dropdown_position(&control, menu, &work_area);
+        assert_eq!((pt.x, pt.y), (100, 230));
+
+        // no room below, flipped above the control
+        let low = RECT {
+            top: 900,
+            bottom: 930,
+            ..control
+        };
+        let pt = super::dropdown_position(&low, menu, &work_area);
+        assert_eq!((pt.x, pt.y), (100, 600));
+
+        // kept inside the work area horizontally
+        let right = RECT {
+            left: 1850,
+            right: 1900,
+            ..control
+        };
+        let pt = super::dropdown_position(&right, menu, &work_area);
+        assert_eq!((pt.x, pt.y), (1770, 230));
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn value_switches_item_to_owner_drawn() {
+        use windows_sys::Win32::UI::WindowsAndMessaging::{MFT_OWNERDRAW, MIIM_FTYPE};
+
+        let item = MenuItem::new("Status", true, None);
+        let mut menu = Menu::new(None);
+        menu.add_menu_item(&item, AddOp::Append).unwrap();
+
+        let is_owner_drawn_in_menu = || {
+            let mut info: MENUITEMINFOW = unsafe { std::mem::zeroed() };
+            info.cbSize = std::mem::size_of::<MENUITEMINFOW>() as _;
+            info.fMask = MIIM_FTYPE;
+            let id = item.inner.borrow().internal_id;
+            unsafe { GetMenuItemInfoW(menu.hpopupmenu, id, false.into(), &mut info) };
+            info.fType & MFT_OWNERDRAW != 0
+        };
+        assert!(!is_owner_drawn_in_menu());
+
+        let green = Some([0, 160, 0, 255]);
+        item.set_value(Some(("Connected", green)));
+        assert_eq!(item.value(), Some(("Connected".to_string(), green)));
+        assert!(is_owner_drawn_in_menu());
+
+        item.set_value(None::<(&str, _)>);
+        assert_eq!(item.value(), None);
+        assert!(!is_owner_drawn_in_menu());
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn indent_level_offsets_label() {
+        let item = MenuItem::new("Bookmark", true, None);
+        let mut menu = Menu::new(None);
+        menu.add_menu_item(&item, AddOp::Append).unwrap();
+
+        let mut measure = || {
+            let offset = unsafe { super::owner_draw::label_offset(&item.inner.borrow()) };
+            (offset, measure(&mut menu, 0, &item).0)
+        };
+        let (offset, width) = measure();
+
+        item.set_indent_level(2);
+        assert_eq!(item.indent_level(), 2);
+        assert!(item.inner.borrow().is_owner_drawn());
+        let (indented_offset, indented_width) = measure();
+        assert!(indented_offset > offset);
+        assert_eq!(indented_width - width, (indented_offset - offset) as u32);
+
+        item.set_indent_level(0);
+        assert!(!item.inner.borrow().is_owner_drawn());
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn compact_submenu_removes_gutter() {
+        use crate::{CheckMenuItem, Submenu};
+
+        let item = MenuItem::new("Arial", true, None);
+        let check = CheckMenuItem::new("Bold", true, true, None);
+        let fonts = Submenu::with_items("Fonts", true, &[&item, &check]).unwrap();
+        let mut menu = Menu::new(None);
+        menu.add_menu_item(&fonts, AddOp::Append).unwrap();
+
+        let mut measure = || {
+            let offset = unsafe { super::owner_draw::label_offset(&item.inner.borrow()) };
+            (offset, measure(&mut menu, 0, &item).0)
+        };
+        let (offset, width) = measure();
+
+        fonts.set_compact(true);
+        assert!(item.inner.borrow().is_owner_drawn());
+        // check items keep their gutter
+        assert!(!check.inner.borrow().is_compact());
+        let (compact_offset, compact_width) = measure();
+        assert!(compact_offset < offset);
+        assert!(compact_width < width);
+
+        fonts.set_compact(false);
+        assert!(!item.inner.borrow().is_owner_drawn());
+        assert_eq!(measure().0, offset);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn initial_selection_skips_separators_and_ignores_out_of_range() {
+        let mut menu = Menu::new(None);
+        for item in [
+            &MenuItem::new("Cut", true, None) as &dyn crate::IsMenuItem,
+            &PredefinedMenuItem::separator(),
+            &MenuItem::new("Paste", true, None),
+        ] {
+            menu.add_menu_item(item, AddOp::Append).unwrap();
+        }
+
+        let presses = |index| unsafe { super::select_key_presses(menu.hpopupmenu, index) };
+        assert_eq!(presses(0), Some(1));
+        assert_eq!(presses(1), None);
+        assert_eq!(presses(2), Some(2));
+        assert_eq!(presses(3), None);
+        assert_eq!(presses(usize::MAX), None);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn menu_item_icon_sets_and_clears_bitmap() {
+        use crate::icon::Icon;
+        use windows_sys::Win32::UI::WindowsAndMessaging::MIIM_BITMAP;
+
+        let item = MenuItem::new("Open", true, None);
+        let mut menu = Menu::new(None);
+        menu.add_menu_item(&item, AddOp::Append).unwrap();
+
+        let bitmap = || {
+            let mut info: MENUITEMINFOW = unsafe { std::mem::zeroed() };
+            info.cbSize = std::mem::size_of::<MENUITEMINFOW>() as _;
+            info.fMask = MIIM_BITMAP;
+            let id = item.inner.borrow().internal_id;
+            unsafe { GetMenuItemInfoW(menu.hpopupmenu, id, false.into(), &mut info) };
+            info.hbmpItem
+        };
+        assert!(bitmap().is_null());
+
+        let icon = Icon::from_rgba(vec![255; 16 * 16 * 4], 16, 16).unwrap();
+        item.set_icon(Some(icon));
+        assert!(!bitmap().is_null());
+
+        item.set_icon(None);
+        assert!(bitmap().is_null());
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn keep_open_on_check_reopens_only_after_check_items() {
+        use crate::{CheckMenuItem, ContextMenuOptions};
+
+        assert!(!ContextMenuOptions::new().keep_open_on_check);
+        let options = ContextMenuOptions::new()
+            .with_select(0)
+            .with_keep_open_on_check(true);
+        assert!(options.keep_open_on_check);
+
+        let check = CheckMenuItem::new("Bold", true, false, None);
+        let item = MenuItem::new("Close", true, None);
+        assert!(super::reopens_after(
+            options.keep_open_on_check,
+            &check.inner.borrow()
+        ));
+        assert!(!super::reopens_after(
+            options.keep_open_on_check,
+            &item.inner.borrow()
+        ));
+        assert!(!super::reopens_after(false, &check.inner.borrow()));
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn styled_separator_is_owner_drawn() {
+        let native = PredefinedMenuItem::separator_styled(SeparatorStyle::default());
+        assert_eq!(native.separator_style(), None);
+        assert!(!native.inner.borrow().is_owner_drawn());
+
+        let style = SeparatorStyle {
+            thickness: 3,
+            inset: 8,
+            color: Some([255, 0, 0, 255]),
+        };
+        let separator = PredefinedMenuItem::separator_styled(style);
+        assert_eq!(separator.separator_style(), Some(style));
+        assert!(separator.inner.borrow().is_owner_drawn());
+
+        let mut menu = Menu::new(None);
+        menu.add_menu_item(&separator, AddOp::Append).unwrap();
+        assert!(menu
+            .find_by_id(separator.inner.borrow().internal_id)
+            .is_some());
+
+        let (width, height) = measure(&mut menu, 0, &separator);
+        assert!(height >= 3);
+        assert_eq!(width, 0);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn cascade_side_sets_tracking_alignment() {
+        use super::tracking_flags;
+        use crate::{CascadeSide, Submenu};
+        use windows_sys::Win32::UI::WindowsAndMessaging::{
+            GetSystemMetrics, SM_MENUDROPALIGNMENT, TPM_LEFTALIGN, TPM_RIGHTALIGN,
+        };
+
+        let submenu = Submenu::new("Submenu", true);
+        assert_eq!(submenu.cascade_side(), CascadeSide::Auto);
+        submenu.set_cascade_side(CascadeSide::Left);
+        assert_eq!(submenu.cascade_side(), CascadeSide::Left);
+        assert_eq!(submenu.inner.borrow().cascade_side, CascadeSide::Left);
+
+        let system = match unsafe { GetSystemMetrics(SM_MENUDROPALIGNMENT) } {
+            0 => TPM_LEFTALIGN,
+            _ => TPM_RIGHTALIGN,
+        };
+        assert_eq!(tracking_flags(CascadeSide::Auto), system);
+        assert_eq!(tracking_flags(CascadeSide::Right), TPM_LEFTALIGN);
+        assert_eq!(tracking_flags(CascadeSide::Left), TPM_RIGHTALIGN);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn labeled_separator_is_owner_drawn_and_disabled() {
+        use windows_sys::Win32::UI::WindowsAndMessaging::{
+            MFT_OWNERDRAW, MFT_SEPARATOR, MIIM_FTYPE,
+        };
+
+        let separator = PredefinedMenuItem::labeled_separator("Recent");
+        assert_eq!(separator.text(), "Recent");
+        assert!(separator.inner.borrow().is_owner_drawn());
+
+        let mut menu = Menu::new(None);
+        menu.add_menu_item(&separator, AddOp::Append).unwrap();
+        assert!(!separator.inner.borrow().is_enabled());
+
+        // separators can't be selected, so they never send `WM_COMMAND`
+        let (id, hpopupmenu) = (separator.inner.borrow().internal_id, menu.hpopupmenu);
+        let item_type = || unsafe {
+            let mut info: MENUITEMINFOW = std::mem::zeroed();
+            info.cbSize = std::mem::size_of::<MENUITEMINFOW>() as _;
+            info.fMask = MIIM_FTYPE;
+            GetMenuItemInfoW(hpopupmenu, id, false.into(), &mut info);
+            info.fType
+        };
+        assert_eq!(item_type() & MFT_SEPARATOR, MFT_SEPARATOR);
+        assert_eq!(item_type() & MFT_OWNERDRAW, MFT_OWNERDRAW);
+
+        assert!(measure(&mut menu, 0, &separator).0 > 0);
+
+        separator.set_text("");
+        assert!(!separator.inner.borrow().is_owner_drawn());
+        assert_eq!(item_type() & MFT_OWNERDRAW, 0);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn submenu_hides_accelerator_column() {
+        use crate::{
+            accelerator::{Accelerator, Code, Modifiers},
+            Submenu,
+        };
+
+        let accelerator = Accelerator::new(Some(Modifiers::CONTROL), Code::KeyS);
+        let save = MenuItem::new("Save", true, Some(accelerator));
+        let file = Submenu::with_items("File", true, &[&save]).unwrap();
+        let mut menu = Menu::new(None);
+        menu.add_menu_item(&file, AddOp::Append).unwrap();
+
+        let native_label = || {
+            let save = save.inner.borrow();
+            let file = file.inner.borrow();
+            let mut label = vec![0u16; 64];
+            let mut info: MENUITEMINFOW = unsafe { std::mem::zeroed() };
+            info.cbSize = std::mem::size_of::<MENUITEMINFOW>() as _;
+            info.fMask = MIIM_STRING;
+            info.dwTypeData = label.as_mut_ptr();
+            info.cch = label.len() as _;
+            unsafe { GetMenuItemInfoW(file.hmenu, save.internal_id(), false.into(), &mut info) };
+            decode_wide(label.as_mut_ptr())
+        };
+        assert_eq!(native_label(), "Save\tCtrl+S");
+
+        file.set_show_accelerators(false);
+        assert!(!file.shows_accelerators());
+        assert_eq!(native_label(), "Save");
+        save.set_text("Save All");
+        assert_eq!(native_label(), "Save All");
+
+        // the accelerator still resolves
+        let id = save.inner.borrow().internal_id();
+        assert!(menu.haccel_store.borrow().1.contains_key(&id));
+
+        file.set_show_accelerators(true);
+        assert_eq!(native_label(), "Save All\tCtrl+S");
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn predefined_after_action_runs_after_the_action() {
+        use std::cell::Cell;
+
+        use super::MENU_ABOUT_CLOSED;
+        use crate::AboutMetadata;
+
+        let hide = PredefinedMenuItem::hide(None);
+        let about = PredefinedMenuItem::about(None, Some(AboutMetadata::default()));
+        let mut menu = Menu::new(None);
+        menu.add_menu_item(&hide, AddOp::Append).unwrap();
+        menu.add_menu_item(&about, AddOp::Append).unwrap();
+
+        let hidden = Rc::new(Cell::new(0));
+        let hidden_c = hidden.clone();
+        hide.set_after_action(move || hidden_c.set(hidden_c.get() + 1));
+        let dismissed = Rc::new(Cell::new(0));
+        let dismissed_c = dismissed.clone();
+        about.set_after_action(move || dismissed_c.set(dismissed_c.get() + 1));
+
+        let hwnd = 1;
+        let dwrefdata = dwrefdata_from_obj(&menu);
+        let send = |msg, item: &PredefinedMenuItem| unsafe {
+            let wparam = item.inner.borrow().internal_id as usize;
+            menu_subclass_proc(hwnd as _, msg, wparam, 0, MENU_SUBCLASS_ID, dwrefdata);
+        };
+
+        // not a window, so hiding it does nothing, but the action still completes
+        send(WM_COMMAND, &hide);
+        assert_eq!(hidden.get(), 1);
+
+        // the about dialog reports when it is dismissed
+        assert_eq!(dismissed.get(), 0);
+        send(MENU_ABOUT_CLOSED, &about);
+        assert_eq!(dismissed.get(), 1);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn read_only_menu_emits_no_events() {
+        let item = MenuItem::with_id("read-only", "Status", true, None);
+        let mut menu = Menu::new(None);
+        menu.add_menu_item(&item, AddOp::Append).unwrap();
+
+        let seen = Arc::new(Mutex::new(0));
+        let seen_c = seen.clone();
+        let observer = MenuEvent::add_observer(move |e| {
+            if e.id == "read-only" {
+                *seen_c.lock().unwrap() += 1;
+            }
+        });
+
+        let hwnd = 1;
+        let wparam = item.inner.borrow().internal_id as usize;
+        let dwrefdata = dwrefdata_from_obj(&menu);
+        let send_command = || unsafe {
+            menu_subclass_proc(
+                hwnd as _,
+                WM_COMMAND,
+                wparam,
+                0,
+                MENU_SUBCLASS_ID,
+                dwrefdata,
+            )
+        };
+
+        menu.set_read_only(true);
+        assert_eq!(send_command(), 0);
+        assert_eq!(*seen.lock().unwrap(), 0);
+        assert!(item.is_enabled());
+
+        menu.set_read_only(false);
+        send_command();
+        assert_eq!(*seen.lock().unwrap(), 1);
+
+        MenuEvent::remove_observer(observer);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(target_os = "linux")), ignore)]
+    fn disabled_item_accelerator_emits_no_event() {
+        let item = MenuItem::with_id("disabled-accel", "Save", false, None);
+        let mut menu = Menu::new(None);
+        menu.add_menu_item(&item, AddOp::Append).unwrap();
+
+        let seen = Arc::new(Mutex::new(0));
+        let seen_c = seen.clone();
+        let observer = MenuEvent::add_observer(move |e| {
+            if e.id == "disabled-accel" {
+                *seen_c.lock().unwrap() += 1;
+            }
+        });
+
+        let hwnd = 1;
+        let id = item.inner.borrow().internal_id as usize;
+        let dwrefdata = dwrefdata_from_obj(&menu);
+        let send_command = |wparam| unsafe {
+            menu_subclass_proc(
+                hwnd as _,
+                WM_COMMAND,
+                wparam,
+                0,
+                MENU_SUBCLASS_ID,
+                dwrefdata,
+            )
+        };
+
+        assert!(menu.beeps_on_disabled());
+        menu.set_beep_on_disabled(false);
+        // the high-order word is 1 for accelerators, the command is handled without beeping
+        assert_eq!(send_command((1 << 16) | id), 0);
+        assert_eq!(*seen.lock().unwrap(), 0);
+
+        item.set_enabled(true);
+        send_command((1 << 16) | id);
+        assert_eq!(*seen.lock().unwrap(), 1);
+
+        MenuEvent::remove_observer(observer);
+    }
+}
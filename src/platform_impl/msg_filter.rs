@@ -0,0 +1,79 @@
+//! The thread-local `WH_MSGFILTER` hook shared by the features that need the messages of a menu
+//! loop before it handles them: non-wrapping navigation ([`nav_wrap`](super::nav_wrap)) and
+//! clickable submenu titles ([`title_click`](super::title_click)).
+//!
+//! The hook is installed by the first [`MsgFilter`] of a thread and removed once the last one
+//! is dropped, along with the menu or submenu that uses it.
+
+use std::cell::Cell;
+
+use windows_sys::Win32::{
+    Foundation::{LPARAM, LRESULT, WPARAM},
+    System::Threading::GetCurrentThreadId,
+    UI::WindowsAndMessaging::{
+        CallNextHookEx, SetWindowsHookExW, UnhookWindowsHookEx, HHOOK, MSG, MSGF_MENU,
+        WH_MSGFILTER, WM_KEYDOWN, WM_LBUTTONUP,
+    },
+};
+
+use super::{nav_wrap, title_click};
+
+thread_local! {
+    static MSG_FILTER_HOOK: Cell<HHOOK> = const { Cell::new(std::ptr::null_mut()) };
+    static USERS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Keeps the hook of this thread installed while it is alive.
+#[derive(Debug)]
+pub(crate) struct MsgFilter(());
+
+impl MsgFilter {
+    pub fn new() -> Self {
+        if MSG_FILTER_HOOK.get().is_null() {
+            let hook = unsafe {
+                SetWindowsHookExW(
+                    WH_MSGFILTER,
+                    Some(msg_filter_proc),
+                    std::ptr::null_mut(),
+                    GetCurrentThreadId(),
+                )
+            };
+            MSG_FILTER_HOOK.set(hook);
+        }
+        USERS.set(USERS.get() + 1);
+        Self(())
+    }
+}
+
+impl Drop for MsgFilter {
+    fn drop(&mut self) {
+        USERS.set(USERS.get() - 1);
+        if USERS.get() == 0 {
+            let hook = MSG_FILTER_HOOK.replace(std::ptr::null_mut());
+            if !hook.is_null() {
+                unsafe { UnhookWindowsHookEx(hook) };
+            }
+        }
+    }
+}
+
+/// Whether the hook of this thread is installed.
+#[cfg(test)]
+pub(crate) fn is_installed() -> bool {
+    !MSG_FILTER_HOOK.get().is_null()
+}
+
+unsafe extern "system" fn msg_filter_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    if code == MSGF_MENU as i32 {
+        let msg = &*(lparam as *const MSG);
+        let handled = match msg.message {
+            WM_KEYDOWN => nav_wrap::blocks(msg.wParam as u16),
+            WM_LBUTTONUP => title_click::clicked(msg),
+            _ => false,
+        };
+        if handled {
+            return 1;
+        }
+    }
+    CallNextHookEx(MSG_FILTER_HOOK.get(), code, wparam, lparam)
+}
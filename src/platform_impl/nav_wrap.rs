@@ -0,0 +1,58 @@
+//! Non-wrapping keyboard navigation, see [`Menu::set_nav_wrap`](crate::Menu::set_nav_wrap).
+//!
+//! The keys of a shown menu are handled by the menu loop and never reach the window subclass,
+//! so the [`msg_filter`](super::msg_filter) hook drops Up at the first item and Down at the last
+//! item of the popups of menus that don't wrap. The highlighted item is recorded from `WM_MENUSELECT`.
+
+use std::cell::Cell;
+
+use windows_sys::Win32::UI::{
+    Input::KeyboardAndMouse::{VK_DOWN, VK_UP},
+    WindowsAndMessaging::{
+        GetMenuItemCount, GetMenuItemID, GetMenuState, HMENU, MF_BYPOSITION, MF_POPUP, MF_SEPARATOR,
+    },
+};
+
+/// The highlighted item of a popup of a menu that doesn't wrap.
+#[derive(Clone, Copy)]
+struct Highlighted {
+    hmenu: HMENU,
+    position: u32,
+}
+
+thread_local! {
+    static HIGHLIGHTED: Cell<Option<Highlighted>> = const { Cell::new(None) };
+}
+
+/// Records the item highlighted in `hmenu` from the parameters of `WM_MENUSELECT`,
+/// if the menu it belongs to doesn't `wrap`.
+pub(crate) unsafe fn set_highlighted(hmenu: HMENU, flags: u32, item: u32, wrap: bool) {
+    let position = if flags & MF_POPUP != 0 {
+        Some(item)
+    } else {
+        // the low-order word of the id of a command item
+        (0..GetMenuItemCount(hmenu).max(0) as u32)
+            .find(|&i| GetMenuItemID(hmenu, i as _) & 0xFFFF == item)
+    };
+
+    HIGHLIGHTED.set(
+        position
+            .filter(|_| !wrap && !hmenu.is_null())
+            .map(|position| Highlighted { hmenu, position }),
+    );
+}
+
+/// Whether `key` is dropped because it would wrap around the highlighted popup.
+pub(crate) unsafe fn blocks(key: u16) -> bool {
+    let Some(Highlighted { hmenu, position }) = HIGHLIGHTED.get() else {
+        return false;
+    };
+
+    let mut selectable = (0..GetMenuItemCount(hmenu).max(0) as u32)
+        .filter(|&i| GetMenuState(hmenu, i, MF_BYPOSITION) & MF_SEPARATOR == 0);
+    match key {
+        VK_UP => selectable.next() == Some(position),
+        VK_DOWN => selectable.next_back() == Some(position),
+        _ => false,
+    }
+}
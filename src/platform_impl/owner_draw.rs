@@ -0,0 +1,629 @@
+//! Owner-drawn menu items.
+//!
+//! Native menu items can only show a label and an accelerator, so items that need
+//! anything more (a badge for example) are switched to `MFT_OWNERDRAW` and are measured
+//! and painted here in response to `WM_MEASUREITEM` and `WM_DRAWITEM`.
+
+use windows_sys::Win32::{
+    Foundation::{COLORREF, HWND, RECT, SIZE},
+    Graphics::Gdi::{
+        CreateFontIndirectW, CreateFontW, CreateSolidBrush, DeleteObject, DrawTextW, FillRect,
+        GetDC, GetStockObject, GetSysColor, GetTextColor, ReleaseDC, RoundRect, SelectObject,
+        SetBkMode, SetTextColor, COLOR_GRAYTEXT, COLOR_HIGHLIGHT, COLOR_HIGHLIGHTTEXT, COLOR_MENU,
+        COLOR_MENUTEXT, DT_CALCRECT, DT_CENTER, DT_HIDEPREFIX, DT_LEFT, DT_NOPREFIX, DT_RIGHT,
+        DT_SINGLELINE, DT_VCENTER, HDC, HFONT, NULL_PEN, SYMBOL_CHARSET, TRANSPARENT,
+    },
+    UI::{
+        Controls::{
+            DRAWITEMSTRUCT, MEASUREITEMSTRUCT, ODS_DISABLED, ODS_GRAYED, ODS_NOACCEL, ODS_SELECTED,
+            ODT_MENU,
+        },
+        WindowsAndMessaging::{
+            DrawIconEx, GetMenuItemCount, GetMenuItemInfoW, GetSystemMetrics, SendMessageW,
+            SetMenuItemInfoW, SystemParametersInfoW, DI_NORMAL, HMENU, MENUITEMINFOW,
+            MFT_OWNERDRAW, MFT_SEPARATOR, MIIM_FTYPE, MIIM_ID, MIIM_STRING, NONCLIENTMETRICSW,
+            SM_CXEDGE, SM_CXMENUCHECK, SM_CYEDGE, SM_CYMENU, SPI_GETNONCLIENTMETRICS,
+            WM_MEASUREITEM,
+        },
+    },
+};
+
+use super::{
+    dark_menu_bar,
+    util::{decode_wide, encode_wide, menu_max_height},
+    MenuChild,
+};
+use crate::dpi::PhysicalSize;
+use crate::{MenuItemStyle, MenuItemType, SeparatorStyle};
+
+/// Horizontal padding around the item content.
+const ITEM_PADDING: i32 = 4;
+/// Space between the label and the columns on the right side.
+const COLUMN_GAP: i32 = 16;
+/// Background color of badges, `#E81123`.
+const BADGE_COLOR: COLORREF = 0x002311E8;
+const BADGE_TEXT_COLOR: COLORREF = 0x00FFFFFF;
+/// Diameter of a badge without text.
+const BADGE_DOT_SIZE: i32 = 8;
+/// Shortest line drawn after the caption of a labeled separator, including the gap before it.
+const MIN_RULE_WIDTH: i32 = 32;
+
+/// Properties of a menu item that are only rendered when it is owner-drawn.
+#[derive(Debug, Default)]
+pub(crate) struct OwnerDrawData {
+    pub badge: Option<String>,
+    /// A right-aligned value with an optional `[r, g, b, a]` color.
+    pub value: Option<(String, Option<[u8; 4]>)>,
+    pub separator_style: Option<SeparatorStyle>,
+    /// Depth of the item in a flattened hierarchy, each level is indented by the width of a check mark.
+    pub indent_level: u8,
+    pub style: Option<MenuItemStyle>,
+}
+
+impl OwnerDrawData {
+    /// Whether any of the properties requires the item to be owner-drawn.
+    pub fn is_needed(&self) -> bool {
+        self.badge.is_some()
+            || self.value.is_some()
+            || self.separator_style.is_some()
+            || self.indent_level > 0
+            || self.style.is_some()
+    }
+}
+
+/// Switches the item with `id` in `hmenu` between a native and an owner-drawn item,
+/// keeping the rest of its type flags (separator, radio check... etc) intact.
+pub(crate) unsafe fn set_owner_drawn(hmenu: HMENU, id: u32, owner_drawn: bool) {
+    let mut info: MENUITEMINFOW = std::mem::zeroed();
+    info.cbSize = std::mem::size_of::<MENUITEMINFOW>() as _;
+    info.fMask = MIIM_FTYPE;
+    GetMenuItemInfoW(hmenu, id, false.into(), &mut info);
+
+    if owner_drawn {
+        info.fType |= MFT_OWNERDRAW;
+    } else {
+        info.fType &= !MFT_OWNERDRAW;
+    }
+
+    SetMenuItemInfoW(hmenu, id, false.into(), &info);
+}
+
+/// Fills `mis` with the size of `item`, measured using the menu font.
+pub(crate) unsafe fn measure_item(hwnd: HWND, item: &MenuChild, mis: &mut MEASUREITEMSTRUCT) {
+    if item.is_separator() {
+        let style = item.owner_draw.separator_style.unwrap_or_default();
+        // the width of a menu is the width of its widest item
+        mis.itemWidth = 0;
+        mis.itemHeight = style.thickness + ITEM_PADDING as u32 * 2;
+        if item.text.is_empty() {
+            return;
+        }
+    }
+
+    let hdc = GetDC(hwnd);
+    let font = menu_font();
+    let old_font = SelectObject(hdc, font);
+
+    if item.is_separator() {
+        let caption = text_size(hdc, &item.text, DT_NOPREFIX);
+        SelectObject(hdc, old_font);
+        DeleteObject(font);
+        ReleaseDC(hwnd, hdc);
+
+        mis.itemWidth = (label_offset(item) + caption.cx + MIN_RULE_WIDTH) as _;
+        mis.itemHeight = mis.itemHeight.max((caption.cy + ITEM_PADDING) as _);
+        return;
+    }
+
+    let label = text_size(hdc, &item.display_text(), 0);
+    let mut width = label_offset(item) + label.cx + ITEM_PADDING;
+
+    if let Some(accelerator) = &item.accelerator {
+        width += COLUMN_GAP + text_size(hdc, &accelerator.to_string(), 0).cx;
+    }
+
+    if let Some((value, _)) = &item.owner_draw.value {
+        width += COLUMN_GAP + text_size(hdc, value, 0).cx;
+    }
+
+    if let Some(badge) = &item.owner_draw.badge {
+        width += COLUMN_GAP + badge_size(hdc, badge).cx;
+    }
+
+    SelectObject(hdc, old_font);
+    DeleteObject(font);
+    ReleaseDC(hwnd, hdc);
+
+    mis.itemWidth = width as _;
+    mis.itemHeight = (label.cy + ITEM_PADDING * 2)
+        .max(GetSystemMetrics(SM_CYMENU))
+        .max(item.item_min_height() as i32) as _;
+}
+
+/// Horizontal space before the gutter of `item` for its indent level.
+unsafe fn indent_width(item: &MenuChild) -> i32 {
+    item.owner_draw.indent_level as i32 * GetSystemMetrics(SM_CXMENUCHECK)
+}
+
+/// Distance from the left edge of `item` to its label.
+pub(crate) unsafe fn label_offset(item: &MenuChild) -> i32 {
+    let gutter = if item.is_compact() {
+        ITEM_PADDING * 2
+    } else {
+        gutter_width()
+    };
+    indent_width(item) + gutter
+}
+
+/// Estimates the size of the popup menu `hmenu` without showing it, measuring native items
+/// with the menu font and asking `hwnd` to measure owner-drawn ones.
+///
+/// Returns `None` if `hmenu` is not a valid menu.
+pub(crate) unsafe fn measure_menu(hwnd: HWND, hmenu: HMENU) -> Option<PhysicalSize<u32>> {
+    let items = measure_items(hmenu, &|id| {
+        let mut mis: MEASUREITEMSTRUCT = std::mem::zeroed();
+        mis.CtlType = ODT_MENU;
+        mis.itemID = id;
+        (SendMessageW(hwnd, WM_MEASUREITEM, 0, &mut mis as *mut _ as _) != 0).then_some(mis)
+    })?;
+
+    let mut size = popup_size(&items);
+    // taller popups scroll
+    let max_height = menu_max_height(hmenu);
+    if max_height > 0 {
+        size.height = size.height.min(max_height);
+    }
+    Some(size)
+}
+
+/// The size of an item of a popup menu, see [`measure_items`].
+pub(crate) struct MeasuredItem {
+    pub id: u32,
+    pub separator: bool,
+    pub width: i32,
+    pub height: i32,
+}
+
+/// Measures the items of the popup menu `hmenu` in order, native items are measured
+/// with the menu font and owner-drawn ones with `measure_owner_drawn`, falling back
+/// to the native measurement if it returns `None`.
+///
+/// Returns `None` if `hmenu` is not a valid menu.
+pub(crate) unsafe fn measure_items(
+    hmenu: HMENU,
+    measure_owner_drawn: &dyn Fn(u32) -> Option<MEASUREITEMSTRUCT>,
+) -> Option<Vec<MeasuredItem>> {
+    let count = GetMenuItemCount(hmenu);
+    if count < 0 {
+        return None;
+    }
+
+    let hdc = GetDC(std::ptr::null_mut());
+    let font = menu_font();
+    let old_font = SelectObject(hdc, font);
+
+    let mut items = Vec::with_capacity(count as usize);
+    for position in 0..count as u32 {
+        let mut info: MENUITEMINFOW = std::mem::zeroed();
+        info.cbSize = std::mem::size_of::<MENUITEMINFOW>() as _;
+        info.fMask = MIIM_FTYPE | MIIM_ID | MIIM_STRING;
+        GetMenuItemInfoW(hmenu, position, true.into(), &mut info);
+
+        let mut text = vec![0u16; info.cch as usize + 1];
+        info.dwTypeData = text.as_mut_ptr();
+        info.cch += 1;
+        GetMenuItemInfoW(hmenu, position, true.into(), &mut info);
+
+        let separator = info.fType & MFT_SEPARATOR != 0;
+        let mut item = MeasuredItem {
+            id: info.wID,
+            separator,
+            width: 0,
+            height: 0,
+        };
+
+        if let Some(mis) = (info.fType & MFT_OWNERDRAW != 0)
+            .then(|| measure_owner_drawn(info.wID))
+            .flatten()
+        {
+            // the system adds the width of a check mark to owner-drawn items
+            item.width = mis.itemWidth as i32 + GetSystemMetrics(SM_CXMENUCHECK);
+            item.height = mis.itemHeight as i32;
+        } else if separator {
+            item.height = ITEM_PADDING * 2 + 1;
+        } else {
+            let text = decode_wide(text.as_mut_ptr());
+            let mut columns = text.split('\t');
+            let label = text_size(hdc, columns.next().unwrap_or_default(), 0);
+            item.width = gutter_width() + label.cx + ITEM_PADDING;
+            if let Some(accelerator) = columns.next() {
+                item.width += COLUMN_GAP + text_size(hdc, accelerator, 0).cx;
+            }
+            // room for the submenu arrow
+            item.width += GetSystemMetrics(SM_CXMENUCHECK);
+            item.height = (label.cy + ITEM_PADDING * 2).max(GetSystemMetrics(SM_CYMENU));
+        }
+        items.push(item);
+    }
+
+    SelectObject(hdc, old_font);
+    DeleteObject(font);
+    ReleaseDC(std::ptr::null_mut(), hdc);
+
+    Some(items)
+}
+
+/// The size of a popup menu showing all of `items`, including its border.
+pub(crate) unsafe fn popup_size(items: &[MeasuredItem]) -> PhysicalSize<u32> {
+    let width = items.iter().map(|i| i.width).max().unwrap_or_default();
+    let height = items.iter().map(|i| i.height).sum::<i32>();
+    // the border on both sides of the menu
+    PhysicalSize::new(
+        (width + GetSystemMetrics(SM_CXEDGE) * 2) as u32,
+        (height + GetSystemMetrics(SM_CYEDGE) * 2) as u32,
+    )
+}
+
+/// The colors items are painted with.
+pub(crate) struct Palette {
+    pub background: COLORREF,
+    pub selected_background: COLORREF,
+    pub text: COLORREF,
+    pub selected_text: COLORREF,
+    pub disabled_text: COLORREF,
+}
+
+impl Palette {
+    /// The system colors used by native menus.
+    pub unsafe fn system() -> Self {
+        Self {
+            background: GetSysColor(COLOR_MENU),
+            selected_background: GetSysColor(COLOR_HIGHLIGHT),
+            text: GetSysColor(COLOR_MENUTEXT),
+            selected_text: GetSysColor(COLOR_HIGHLIGHTTEXT),
+            disabled_text: GetSysColor(COLOR_GRAYTEXT),
+        }
+    }
+
+    /// The colors of the dark menu bar.
+    pub fn dark() -> Self {
+        Self {
+            background: dark_menu_bar::BACKGROUND_COLOR,
+            selected_background: dark_menu_bar::SELECTED_BACKGROUND_COLOR,
+            text: dark_menu_bar::TEXT_COLOR,
+            selected_text: dark_menu_bar::TEXT_COLOR,
+            disabled_text: dark_menu_bar::DISABLED_TEXT_COLOR,
+        }
+    }
+
+    /// This palette with the colors of `style` blended over it,
+    /// disabled items use the text color of the style at half opacity.
+    pub fn styled(&self, style: &MenuItemStyle) -> Self {
+        let background = style
+            .background_color
+            .map_or(self.background, |color| blend_rgba(color, self.background));
+        let text = style.text_color.map(|color| blend_rgba(color, background));
+        Self {
+            background,
+            selected_background: style
+                .highlight_color
+                .map_or(self.selected_background, |color| {
+                    blend_rgba(color, background)
+                }),
+            text: text.unwrap_or(self.text),
+            selected_text: text.unwrap_or(self.selected_text),
+            disabled_text: text.map_or(self.disabled_text, |text| blend(text, background, 0x80)),
+        }
+    }
+}
+
+/// Paints `item` as described by `dis` with the colors of `palette`.
+pub(crate) unsafe fn draw_item(item: &MenuChild, dis: &DRAWITEMSTRUCT, palette: &Palette) {
+    let styled;
+    let palette = match &item.owner_draw.style {
+        Some(style) => {
+            styled = palette.styled(style);
+            &styled
+        }
+        None => palette,
+    };
+
+    let hdc = dis.hDC;
+    let selected = dis.itemState & ODS_SELECTED != 0;
+    let disabled = dis.itemState & (ODS_GRAYED | ODS_DISABLED) != 0;
+
+    let background = if selected {
+        palette.selected_background
+    } else {
+        palette.background
+    };
+    let text_color = if disabled {
+        palette.disabled_text
+    } else if selected {
+        palette.selected_text
+    } else {
+        palette.text
+    };
+
+    fill_rect(hdc, &dis.rcItem, background);
+
+    if item.is_separator() {
+        let style = item.owner_draw.separator_style.unwrap_or_default();
+        let mut rule = dis.rcItem;
+        if !item.text.is_empty() {
+            rule.left = draw_caption(hdc, item, &dis.rcItem, palette.disabled_text) + ITEM_PADDING;
+        }
+        draw_separator(hdc, &style, &rule, palette);
+        return;
+    }
+
+    let font = menu_font();
+    let old_font = SelectObject(hdc, font);
+    SetBkMode(hdc, TRANSPARENT as _);
+    SetTextColor(hdc, text_color);
+
+    let mut flags = DT_SINGLELINE | DT_VCENTER;
+    if dis.itemState & ODS_NOACCEL != 0 {
+        flags |= DT_HIDEPREFIX;
+    }
+
+    let mut gutter = dis.rcItem;
+    gutter.left += indent_width(item);
+    draw_gutter(hdc, item, &gutter);
+
+    let mut rc = dis.rcItem;
+    rc.left += label_offset(item);
+    rc.right -= ITEM_PADDING;
+
+    // for menus, `hwndItem` is the menu containing the item
+    let parent = dis.hwndItem as HMENU;
+    if let Some(accelerator) = item
+        .accelerator
+        .filter(|_| !item.accelerator_hidden_in.contains(&parent))
+    {
+        let accelerator = accelerator.to_string();
+        let size = text_size(hdc, &accelerator, 0);
+        draw_text(hdc, &accelerator, &mut rc.clone(), flags | DT_RIGHT);
+        rc.right -= size.cx + COLUMN_GAP;
+    }
+
+    if let Some((value, color)) = &item.owner_draw.value {
+        let size = text_size(hdc, value, 0);
+        let old_color = match color {
+            Some([r, g, b, a]) if !disabled => {
+                SetTextColor(hdc, blend(rgb(*r, *g, *b), background, *a))
+            }
+            _ => GetTextColor(hdc),
+        };
+        draw_text(hdc, value, &mut rc.clone(), flags | DT_RIGHT);
+        SetTextColor(hdc, old_color);
+        rc.right -= size.cx + COLUMN_GAP;
+    }
+
+    if let Some(badge) = &item.owner_draw.badge {
+        let size = badge_size(hdc, badge);
+        let top = rc.top + (rc.bottom - rc.top - size.cy) / 2;
+        let badge_rc = RECT {
+            left: rc.right - size.cx,
+            top,
+            right: rc.right,
+            bottom: top + size.cy,
+        };
+        draw_badge(hdc, badge, badge_rc);
+        rc.right -= size.cx + COLUMN_GAP;
+    }
+
+    draw_text(hdc, &item.display_text(), &mut rc, flags | DT_LEFT);
+
+    SelectObject(hdc, old_font);
+    DeleteObject(font);
+}
+
+/// Draws the icon of `item`, or its check mark or radio bullet if it is a checked item, on the left side of `rc`.
+unsafe fn draw_gutter(hdc: HDC, item: &MenuChild, rc: &RECT) {
+    let size = GetSystemMetrics(SM_CXMENUCHECK);
+    let left = rc.left + ITEM_PADDING;
+    let top = rc.top + (rc.bottom - rc.top - size) / 2;
+
+    if let Some(icon) = &item.icon {
+        DrawIconEx(
+            hdc,
+            left,
+            top,
+            icon.inner.handle(),
+            size,
+            size,
+            0,
+            std::ptr::null_mut(),
+            DI_NORMAL,
+        );
+    } else if item.item_type == MenuItemType::Check && item.checked {
+        // the check mark or bullet glyph, drawn with the current text color
+        let rc = RECT {
+            left,
+            top,
+            right: left + size,
+            bottom: top + size,
+        };
+        let glyph = if item.radio_group.is_some() { "h" } else { "a" };
+        draw_glyph(hdc, glyph, rc);
+    }
+}
+
+/// Draws the arrow of a submenu item on the right side of `rc` with the current text color.
+pub(crate) unsafe fn draw_submenu_arrow(hdc: HDC, rc: &RECT) {
+    let size = GetSystemMetrics(SM_CXMENUCHECK);
+    let top = rc.top + (rc.bottom - rc.top - size) / 2;
+    let rc = RECT {
+        left: rc.right - size,
+        top,
+        right: rc.right,
+        bottom: top + size,
+    };
+    draw_glyph(hdc, "8", rc);
+}
+
+/// Draws `glyph` of the Marlett font centered in the square `rc`.
+unsafe fn draw_glyph(hdc: HDC, glyph: &str, mut rc: RECT) {
+    let font = CreateFontW(
+        rc.bottom - rc.top,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+        SYMBOL_CHARSET as _,
+        0,
+        0,
+        0,
+        0,
+        encode_wide("Marlett").as_ptr(),
+    );
+    let old_font = SelectObject(hdc, font);
+    draw_text(hdc, glyph, &mut rc, DT_SINGLELINE | DT_VCENTER | DT_CENTER);
+    SelectObject(hdc, old_font);
+    DeleteObject(font);
+}
+
+/// Draws the caption of a labeled separator aligned with the labels of the other items,
+/// returns the right edge of the caption.
+unsafe fn draw_caption(hdc: HDC, item: &MenuChild, rc: &RECT, color: COLORREF) -> i32 {
+    let font = menu_font();
+    let old_font = SelectObject(hdc, font);
+    SetBkMode(hdc, TRANSPARENT as _);
+    let old_color = SetTextColor(hdc, color);
+
+    let mut caption = *rc;
+    caption.left += label_offset(item);
+    let size = text_size(hdc, &item.text, DT_NOPREFIX);
+    draw_text(
+        hdc,
+        &item.text,
+        &mut caption,
+        DT_SINGLELINE | DT_VCENTER | DT_LEFT | DT_NOPREFIX,
+    );
+
+    SetTextColor(hdc, old_color);
+    SelectObject(hdc, old_font);
+    DeleteObject(font);
+    (caption.left + size.cx).min(rc.right)
+}
+
+/// Draws a horizontal line centered vertically in `rc`.
+pub(crate) unsafe fn draw_separator(
+    hdc: HDC,
+    style: &SeparatorStyle,
+    rc: &RECT,
+    palette: &Palette,
+) {
+    let thickness = style.thickness as i32;
+    let inset = style.inset as i32;
+    let top = rc.top + (rc.bottom - rc.top - thickness) / 2;
+    let line = RECT {
+        left: rc.left + inset,
+        top,
+        right: (rc.right - inset).max(rc.left + inset),
+        bottom: top + thickness,
+    };
+
+    let color = match style.color {
+        Some(color) => blend_rgba(color, palette.background),
+        None => palette.disabled_text,
+    };
+    fill_rect(hdc, &line, color);
+}
+
+pub(crate) unsafe fn fill_rect(hdc: HDC, rc: &RECT, color: COLORREF) {
+    let brush = CreateSolidBrush(color);
+    FillRect(hdc, rc, brush);
+    DeleteObject(brush);
+}
+
+fn rgb(r: u8, g: u8, b: u8) -> COLORREF {
+    r as COLORREF | (g as COLORREF) << 8 | (b as COLORREF) << 16
+}
+
+/// Blends `color` over `background`, GDI has no notion of transparency.
+fn blend(color: COLORREF, background: COLORREF, alpha: u8) -> COLORREF {
+    let channel = |shift: u32| {
+        let fg = (color >> shift) & 0xFF;
+        let bg = (background >> shift) & 0xFF;
+        ((fg * alpha as u32 + bg * (255 - alpha as u32)) / 255) << shift
+    };
+    channel(0) | channel(8) | channel(16)
+}
+
+/// Blends an `[r, g, b, a]` color over `background` using its alpha.
+fn blend_rgba([r, g, b, a]: [u8; 4], background: COLORREF) -> COLORREF {
+    blend(rgb(r, g, b), background, a)
+}
+
+unsafe fn draw_badge(hdc: HDC, badge: &str, rc: RECT) {
+    let brush = CreateSolidBrush(BADGE_COLOR);
+    let old_brush = SelectObject(hdc, brush);
+    let old_pen = SelectObject(hdc, GetStockObject(NULL_PEN));
+
+    let radius = rc.bottom - rc.top;
+    RoundRect(hdc, rc.left, rc.top, rc.right, rc.bottom, radius, radius);
+
+    SelectObject(hdc, old_pen);
+    SelectObject(hdc, old_brush);
+    DeleteObject(brush);
+
+    if !badge.is_empty() {
+        let old_color = SetTextColor(hdc, BADGE_TEXT_COLOR);
+        let mut rc = rc;
+        draw_text(hdc, badge, &mut rc, DT_SINGLELINE | DT_VCENTER | DT_CENTER);
+        SetTextColor(hdc, old_color);
+    }
+}
+
+/// Size of a badge, an empty badge is drawn as a dot.
+unsafe fn badge_size(hdc: HDC, badge: &str) -> SIZE {
+    if badge.is_empty() {
+        SIZE {
+            cx: BADGE_DOT_SIZE,
+            cy: BADGE_DOT_SIZE,
+        }
+    } else {
+        let size = text_size(hdc, badge, 0);
+        SIZE {
+            cx: (size.cx + ITEM_PADDING * 2).max(size.cy),
+            cy: size.cy,
+        }
+    }
+}
+
+/// Width reserved on the left side of the item for check marks and icons.
+unsafe fn gutter_width() -> i32 {
+    GetSystemMetrics(SM_CXMENUCHECK) + ITEM_PADDING * 2
+}
+
+unsafe fn text_size(hdc: HDC, text: &str, flags: u32) -> SIZE {
+    let mut rc: RECT = std::mem::zeroed();
+    draw_text(hdc, text, &mut rc, flags | DT_SINGLELINE | DT_CALCRECT);
+    SIZE {
+        cx: rc.right - rc.left,
+        cy: rc.bottom - rc.top,
+    }
+}
+
+unsafe fn draw_text(hdc: HDC, text: &str, rc: &mut RECT, flags: u32) {
+    let text = encode_wide(text);
+    DrawTextW(hdc, text.as_ptr(), -1, rc, flags);
+}
+
+/// Creates the font used by the system for menus, the caller is responsible for deleting it.
+unsafe fn menu_font() -> HFONT {
+    let mut metrics: NONCLIENTMETRICSW = std::mem::zeroed();
+    metrics.cbSize = std::mem::size_of::<NONCLIENTMETRICSW>() as _;
+    SystemParametersInfoW(
+        SPI_GETNONCLIENTMETRICS,
+        metrics.cbSize,
+        &mut metrics as *mut _ as _,
+        0,
+    );
+    CreateFontIndirectW(&metrics.lfMenuFont)
+}
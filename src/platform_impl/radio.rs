@@ -0,0 +1,100 @@
+//! Radio groups of check items, see [`CheckMenuItem::new_radio`](crate::CheckMenuItem::new_radio).
+//!
+//! The members of a group can be spread over several submenus, so they are kept in a
+//! thread-local list by group id instead of in the submenus they were added to.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    rc::{Rc, Weak},
+};
+
+use windows_sys::Win32::UI::WindowsAndMessaging::{
+    GetMenuItemInfoW, SetMenuItemInfoW, HMENU, MENUITEMINFOW, MFT_RADIOCHECK, MIIM_FTYPE,
+};
+
+use super::MenuChild;
+
+thread_local! {
+    static RADIO_GROUPS: RefCell<HashMap<String, Vec<Weak<RefCell<MenuChild>>>>> =
+        RefCell::new(HashMap::new());
+}
+
+/// Adds `item` to `group`, unchecking the other members if `item` is checked.
+pub(crate) fn join(item: &Rc<RefCell<MenuChild>>, group: &str) {
+    item.borrow_mut().radio_group = Some(group.to_string());
+
+    RADIO_GROUPS.with_borrow_mut(|groups| {
+        let members = groups.entry(group.to_string()).or_default();
+        members.retain(|m| m.strong_count() > 0);
+        members.push(Rc::downgrade(item));
+    });
+
+    if item.borrow().checked {
+        uncheck_others(item);
+    }
+}
+
+/// Number of groups with members, dropped or not.
+#[cfg(test)]
+pub(crate) fn group_count() -> usize {
+    RADIO_GROUPS.with_borrow(HashMap::len)
+}
+
+/// Removes the members of `group` that were dropped, and `group` itself once it is empty.
+pub(crate) fn prune(group: &str) {
+    // the list may already be gone when the thread exits
+    let _ = RADIO_GROUPS.try_with(|groups| {
+        let Ok(mut groups) = groups.try_borrow_mut() else {
+            return;
+        };
+        if let Some(members) = groups.get_mut(group) {
+            members.retain(|m| m.strong_count() > 0);
+            if members.is_empty() {
+                groups.remove(group);
+            }
+        }
+    });
+}
+
+/// Checks or unchecks `item`, checking a member of a radio group unchecks the other members.
+pub(crate) fn set_checked(item: &Rc<RefCell<MenuChild>>, checked: bool) {
+    let change = item.borrow_mut().set_checked(checked);
+    change.notify();
+    if checked {
+        uncheck_others(item);
+    }
+}
+
+/// Unchecks the members of the radio group of `item` other than `item`, if it has one.
+pub(crate) fn uncheck_others(item: &Rc<RefCell<MenuChild>>) {
+    let Some(group) = item.borrow().radio_group.clone() else {
+        return;
+    };
+
+    let members = RADIO_GROUPS.with_borrow(|groups| {
+        groups
+            .get(&group)
+            .into_iter()
+            .flatten()
+            .filter_map(Weak::upgrade)
+            .collect::<Vec<_>>()
+    });
+    for member in members {
+        if !Rc::ptr_eq(&member, item) && member.borrow().checked {
+            let change = member.borrow_mut().set_checked(false);
+            change.notify();
+        }
+    }
+}
+
+/// Makes the item with `id` in `hmenu` show a round bullet instead of a check mark.
+pub(crate) unsafe fn set_radio_check(hmenu: HMENU, id: u32) {
+    let mut info: MENUITEMINFOW = std::mem::zeroed();
+    info.cbSize = std::mem::size_of::<MENUITEMINFOW>() as _;
+    info.fMask = MIIM_FTYPE;
+    GetMenuItemInfoW(hmenu, id, false.into(), &mut info);
+
+    info.fType |= MFT_RADIOCHECK;
+    SetMenuItemInfoW(hmenu, id, false.into(), &info);
+}
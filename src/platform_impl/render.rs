@@ -0,0 +1,203 @@
+//! Off-screen rendering of popup menus, see [`Menu::render_to_bitmap`](crate::Menu::render_to_bitmap).
+//!
+//! Native menus can only be painted by the system while they are shown, so every item
+//! is painted here the way owner-drawn items are, laid out like [`owner_draw::measure_menu`].
+
+use std::{cell::RefCell, rc::Rc};
+
+use windows_sys::Win32::{
+    Foundation::RECT,
+    Graphics::Gdi::{
+        CreateCompatibleDC, CreateDIBSection, DeleteDC, DeleteObject, GdiFlush, GetDC,
+        GetDeviceCaps, ReleaseDC, SelectObject, SetStretchBltMode, StretchBlt, BITMAPINFO,
+        BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS, HALFTONE, HBITMAP, HDC, LOGPIXELSX, SRCCOPY,
+    },
+    UI::{
+        Controls::{DRAWITEMSTRUCT, MEASUREITEMSTRUCT, ODA_DRAWENTIRE, ODS_GRAYED, ODT_MENU},
+        WindowsAndMessaging::{GetSystemMetrics, HMENU, SM_CXEDGE, SM_CYEDGE},
+    },
+};
+
+use super::{
+    owner_draw::{self, Palette},
+    MenuChild,
+};
+use crate::{MenuItemType, SeparatorStyle};
+
+/// Renders the popup menu `hmenu` at `dpi`, `find` looks up the item of a menu item id.
+///
+/// Returns the pixels as RGBA rows from top to bottom, the width and the height.
+pub(crate) unsafe fn render_popup(
+    hmenu: HMENU,
+    find: &dyn Fn(u32) -> Option<Rc<RefCell<MenuChild>>>,
+    palette: &Palette,
+    dpi: u32,
+) -> crate::Result<(Vec<u8>, u32, u32)> {
+    let items = owner_draw::measure_items(hmenu, &|id| {
+        let item = find(id)?;
+        let mut mis: MEASUREITEMSTRUCT = std::mem::zeroed();
+        mis.CtlType = ODT_MENU;
+        mis.itemID = id;
+        owner_draw::measure_item(std::ptr::null_mut(), &item.borrow(), &mut mis);
+        Some(mis)
+    })
+    .ok_or_else(os_error)?;
+    let size = owner_draw::popup_size(&items);
+    let (width, height) = (size.width as i32, size.height as i32);
+
+    let screen = GetDC(std::ptr::null_mut());
+    let system_dpi = GetDeviceCaps(screen, LOGPIXELSX as _) as u32;
+    ReleaseDC(std::ptr::null_mut(), screen);
+
+    let canvas = Canvas::new(width, height)?;
+    owner_draw::fill_rect(
+        canvas.hdc,
+        &RECT {
+            left: 0,
+            top: 0,
+            right: width,
+            bottom: height,
+        },
+        palette.background,
+    );
+
+    let (edge_x, edge_y) = (GetSystemMetrics(SM_CXEDGE), GetSystemMetrics(SM_CYEDGE));
+    let mut top = edge_y;
+    for measured in &items {
+        let rc = RECT {
+            left: edge_x,
+            top,
+            right: width - edge_x,
+            bottom: top + measured.height,
+        };
+        top = rc.bottom;
+
+        let item = find(measured.id);
+        // styled and labeled separators are owner-drawn
+        if measured.separator && !item.as_ref().is_some_and(|i| i.borrow().is_owner_drawn()) {
+            owner_draw::draw_separator(canvas.hdc, &SeparatorStyle::default(), &rc, palette);
+            continue;
+        }
+        let Some(item) = item else {
+            continue;
+        };
+        let item = item.borrow();
+
+        let mut dis: DRAWITEMSTRUCT = std::mem::zeroed();
+        dis.CtlType = ODT_MENU;
+        dis.itemID = measured.id;
+        dis.itemAction = ODA_DRAWENTIRE;
+        dis.itemState = if item.is_enabled() { 0 } else { ODS_GRAYED };
+        // for menus, `hwndItem` is the menu containing the item
+        dis.hwndItem = hmenu as _;
+        dis.hDC = canvas.hdc;
+        dis.rcItem = rc;
+        owner_draw::draw_item(&item, &dis, palette);
+
+        if item.item_type == MenuItemType::Submenu {
+            owner_draw::draw_submenu_arrow(canvas.hdc, &rc);
+        }
+    }
+
+    let dpi = if dpi == 0 { system_dpi } else { dpi };
+    if dpi == system_dpi {
+        return Ok((canvas.rgba(), width as u32, height as u32));
+    }
+
+    // layout uses the system metrics, so other DPIs are scaled from the system one
+    let scaled_width = (width as u64 * dpi as u64 / system_dpi as u64).max(1) as i32;
+    let scaled_height = (height as u64 * dpi as u64 / system_dpi as u64).max(1) as i32;
+    let scaled = Canvas::new(scaled_width, scaled_height)?;
+    SetStretchBltMode(scaled.hdc, HALFTONE);
+    StretchBlt(
+        scaled.hdc,
+        0,
+        0,
+        scaled_width,
+        scaled_height,
+        canvas.hdc,
+        0,
+        0,
+        width,
+        height,
+        SRCCOPY,
+    );
+    Ok((scaled.rgba(), scaled_width as u32, scaled_height as u32))
+}
+
+fn os_error() -> crate::Error {
+    crate::Error::OsError(std::io::Error::last_os_error())
+}
+
+/// A memory DC drawing into a top-down 32-bit DIB.
+struct Canvas {
+    hdc: HDC,
+    bitmap: HBITMAP,
+    old_bitmap: *mut std::ffi::c_void,
+    bits: *const u8,
+    width: i32,
+    height: i32,
+}
+
+impl Canvas {
+    unsafe fn new(width: i32, height: i32) -> crate::Result<Self> {
+        let mut info: BITMAPINFO = std::mem::zeroed();
+        info.bmiHeader.biSize = std::mem::size_of::<BITMAPINFOHEADER>() as _;
+        info.bmiHeader.biWidth = width;
+        // negative for a top-down bitmap
+        info.bmiHeader.biHeight = -height;
+        info.bmiHeader.biPlanes = 1;
+        info.bmiHeader.biBitCount = 32;
+        info.bmiHeader.biCompression = BI_RGB;
+
+        let hdc = CreateCompatibleDC(std::ptr::null_mut());
+        if hdc.is_null() {
+            return Err(os_error());
+        }
+        let mut bits = std::ptr::null_mut();
+        let bitmap = CreateDIBSection(
+            hdc,
+            &info,
+            DIB_RGB_COLORS,
+            &mut bits,
+            std::ptr::null_mut(),
+            0,
+        );
+        if bitmap.is_null() {
+            let err = os_error();
+            DeleteDC(hdc);
+            return Err(err);
+        }
+        let old_bitmap = SelectObject(hdc, bitmap);
+
+        Ok(Self {
+            hdc,
+            bitmap,
+            old_bitmap,
+            bits: bits as _,
+            width,
+            height,
+        })
+    }
+
+    /// The pixels as opaque RGBA.
+    unsafe fn rgba(&self) -> Vec<u8> {
+        // drawing may be batched
+        GdiFlush();
+        let len = self.width as usize * self.height as usize * 4;
+        let bgra = std::slice::from_raw_parts(self.bits, len);
+        bgra.chunks_exact(4)
+            .flat_map(|p| [p[2], p[1], p[0], 255])
+            .collect()
+    }
+}
+
+impl Drop for Canvas {
+    fn drop(&mut self) {
+        unsafe {
+            SelectObject(self.hdc, self.old_bitmap);
+            DeleteObject(self.bitmap);
+            DeleteDC(self.hdc);
+        }
+    }
+}
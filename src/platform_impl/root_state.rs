@@ -0,0 +1,213 @@
+//! State shared by a root [`Menu`](super::Menu) with every item in its tree,
+//! so changes deep in the tree can be reported to the menu they belong to.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    rc::Rc,
+};
+
+use super::{util::Callback, MenuChild, MenuItemType};
+use crate::{util::Counter, ItemState, MenuEvent, MenuId, SubscriptionId};
+
+static SUBSCRIPTION_COUNTER: Counter = Counter::new();
+
+pub(crate) type StateSubscriber = dyn Fn(&MenuId, ItemState);
+pub(crate) type HaccelObserver = dyn Fn(isize);
+pub(crate) type ItemHandler = dyn Fn(&MenuEvent);
+/// Root states of the menus an item belongs to, keyed by their internal id.
+pub(crate) type RootStates = HashMap<u32, Rc<RootState>>;
+
+#[derive(Debug, Default)]
+pub(crate) struct RootState {
+    state_subscribers: RefCell<Vec<(SubscriptionId, Callback<StateSubscriber>)>>,
+    haccel_observers: RefCell<Vec<Callback<HaccelObserver>>>,
+    item_handlers: RefCell<Vec<(MenuId, Callback<ItemHandler>)>>,
+    item_min_height: Cell<u32>,
+    max_popup_height: Cell<u32>,
+    generation: Cell<u64>,
+    /// Tasks waiting for the next change, see [`Changes`].
+    #[cfg(feature = "async")]
+    change_wakers: RefCell<Vec<std::task::Waker>>,
+}
+
+impl RootState {
+    /// Incremented by any change to the structure of the tree or to the state of its items.
+    pub fn generation(&self) -> u64 {
+        self.generation.get()
+    }
+
+    pub fn bump_generation(&self) {
+        self.generation.set(self.generation.get().wrapping_add(1));
+        #[cfg(feature = "async")]
+        for waker in self.change_wakers.take() {
+            waker.wake();
+        }
+    }
+
+    pub fn item_min_height(&self) -> u32 {
+        self.item_min_height.get()
+    }
+
+    pub fn set_item_min_height(&self, height: u32) {
+        self.item_min_height.set(height);
+    }
+
+    pub fn max_popup_height(&self) -> u32 {
+        self.max_popup_height.get()
+    }
+
+    pub fn set_max_popup_height(&self, height: u32) {
+        self.max_popup_height.set(height);
+    }
+
+    pub fn subscribe_state(&self, subscriber: Rc<StateSubscriber>) -> SubscriptionId {
+        let id = SubscriptionId(SUBSCRIPTION_COUNTER.next());
+        self.state_subscribers
+            .borrow_mut()
+            .push((id, Callback(subscriber)));
+        id
+    }
+
+    pub fn unsubscribe_state(&self, id: SubscriptionId) -> bool {
+        let mut subscribers = self.state_subscribers.borrow_mut();
+        let len = subscribers.len();
+        subscribers.retain(|(i, _)| *i != id);
+        subscribers.len() != len
+    }
+
+    pub fn on_haccel_changed(&self, observer: Rc<HaccelObserver>) {
+        self.haccel_observers.borrow_mut().push(Callback(observer));
+    }
+
+    pub fn notify_haccel_changed(&self, haccel: isize) {
+        // clone the observers so they can add more observers while being notified
+        let observers = self.haccel_observers.borrow().clone();
+        for observer in observers {
+            observer(haccel);
+        }
+    }
+
+    pub fn on_item(&self, id: MenuId, handler: Rc<ItemHandler>) {
+        self.item_handlers
+            .borrow_mut()
+            .push((id, Callback(handler)));
+    }
+
+    pub fn notify_item(&self, event: &MenuEvent) {
+        // clone the handlers so they can register more handlers while being called
+        let handlers = self
+            .item_handlers
+            .borrow()
+            .iter()
+            .filter(|(id, _)| *id == event.id)
+            .map(|(_, h)| h.clone())
+            .collect::<Vec<_>>();
+        for handler in handlers {
+            handler(event);
+        }
+    }
+
+    pub fn notify_state(&self, id: &MenuId, state: &ItemState) {
+        // clone the subscribers so they can subscribe or unsubscribe while being notified
+        let subscribers = self
+            .state_subscribers
+            .borrow()
+            .iter()
+            .map(|(_, s)| s.clone())
+            .collect::<Vec<_>>();
+        for subscriber in subscribers {
+            subscriber(id, state.clone());
+        }
+    }
+}
+
+/// Adds `root` to the root states of `child` and all of its descendants.
+pub(crate) fn attach(child: &mut MenuChild, root_id: u32, root: &Rc<RootState>) {
+    child.root_states.insert(root_id, root.clone());
+    if root.item_min_height() > 0 {
+        child.update_owner_draw();
+    }
+    if root.max_popup_height() > 0 {
+        child.update_max_popup_height();
+    }
+    if child.item_type == MenuItemType::Submenu {
+        for c in child.children.as_ref().unwrap() {
+            attach(&mut c.borrow_mut(), root_id, root);
+        }
+    }
+}
+
+/// Removes the root state of `root_id` from `child` and all of its descendants.
+pub(crate) fn detach(child: &mut MenuChild, root_id: u32) {
+    let root = child.root_states.remove(&root_id);
+    if root.as_ref().is_some_and(|root| root.item_min_height() > 0) {
+        child.update_owner_draw();
+    }
+    if root.is_some_and(|root| root.max_popup_height() > 0) {
+        child.update_max_popup_height();
+    }
+    if child.item_type == MenuItemType::Submenu {
+        for c in child.children.as_ref().unwrap() {
+            detach(&mut c.borrow_mut(), root_id);
+        }
+    }
+}
+
+/// A stream that yields once the generation of a root state changed since it last yielded,
+/// so any number of changes made before it is polled again are reported once.
+#[cfg(feature = "async")]
+pub(crate) struct Changes {
+    root: Rc<RootState>,
+    seen: u64,
+}
+
+#[cfg(feature = "async")]
+impl Changes {
+    pub fn new(root: Rc<RootState>) -> Self {
+        let seen = root.generation();
+        Self { root, seen }
+    }
+}
+
+#[cfg(feature = "async")]
+impl futures_core::Stream for Changes {
+    type Item = ();
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<()>> {
+        let generation = self.root.generation();
+        if generation != self.seen {
+            self.seen = generation;
+            return std::task::Poll::Ready(Some(()));
+        }
+
+        let mut wakers = self.root.change_wakers.borrow_mut();
+        if !wakers.iter().any(|w| w.will_wake(cx.waker())) {
+            wakers.push(cx.waker().clone());
+        }
+        std::task::Poll::Pending
+    }
+}
+
+/// A change of the state of an item, reported to the subscribers of its root menus by
+/// [`StateChange::notify`] once the item is no longer borrowed, so they can read it.
+#[must_use = "the change is only reported by `notify`"]
+#[derive(Default)]
+pub(crate) struct StateChange(Option<(MenuId, ItemState, Vec<Rc<RootState>>)>);
+
+impl StateChange {
+    pub fn new(id: MenuId, state: ItemState, roots: Vec<Rc<RootState>>) -> Self {
+        Self(Some((id, state, roots)))
+    }
+
+    pub fn notify(self) {
+        if let Some((id, state, roots)) = self.0 {
+            for root in roots {
+                root.notify_state(&id, &state);
+            }
+        }
+    }
+}
@@ -0,0 +1,103 @@
+//! Automatic ordering of submenus, see [`Submenu::set_sort`](crate::Submenu::set_sort).
+//!
+//! Items don't know the submenus they were added to, so sorted submenus are kept in a
+//! thread-local list that [`mark_used`] goes through to re-sort the ones containing the item.
+
+use std::{
+    cell::RefCell,
+    rc::{Rc, Weak},
+};
+
+use super::{strip_mnemonic, MenuChild};
+use crate::{items::PredefinedMenuItemType, util::Counter, MenuItemKind, Sort};
+
+static USAGE_COUNTER: Counter = Counter::new();
+
+thread_local! {
+    static SORTED_SUBMENUS: RefCell<Vec<Weak<RefCell<MenuChild>>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Sets the sort of `submenu` and re-orders its items right away.
+pub(crate) fn set_sort(submenu: &Rc<RefCell<MenuChild>>, sort: Sort) {
+    submenu.borrow_mut().sort = sort;
+
+    SORTED_SUBMENUS.with_borrow_mut(|submenus| {
+        submenus.retain(|s| s.strong_count() > 0 && !std::ptr::eq(s.as_ptr(), Rc::as_ptr(submenu)));
+        if sort != Sort::None {
+            submenus.push(Rc::downgrade(submenu));
+        }
+    });
+
+    apply(&mut submenu.borrow_mut());
+}
+
+/// Records that `item` was just used and re-sorts the most recent first submenus it is in.
+pub(crate) fn mark_used(item: &Rc<RefCell<MenuChild>>) {
+    item.borrow_mut().last_used = USAGE_COUNTER.next();
+
+    let submenus = SORTED_SUBMENUS.with_borrow(|submenus| {
+        submenus
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect::<Vec<_>>()
+    });
+    for submenu in submenus {
+        let contains_item = submenu.borrow().sort == Sort::MostRecentFirst
+            && submenu
+                .borrow()
+                .children
+                .as_ref()
+                .is_some_and(|children| children.iter().any(|c| Rc::ptr_eq(c, item)));
+        if contains_item {
+            apply(&mut submenu.borrow_mut());
+        }
+    }
+}
+
+/// Re-orders the items of `submenu` by its sort, separators stay in place
+/// and the items between them are sorted as separate groups.
+pub(crate) fn apply(submenu: &mut MenuChild) {
+    if submenu.sort == Sort::None {
+        return;
+    }
+
+    let items = submenu.items();
+    let mut sorted = Vec::with_capacity(items.len());
+    for group in items.split_inclusive(is_separator) {
+        let (separator, group) = match group.split_last() {
+            Some((last, rest)) if is_separator(last) => (Some(last), rest),
+            _ => (None, group),
+        };
+
+        let mut group = group.to_vec();
+        match submenu.sort {
+            // stable, so items that were never used keep their order after the used ones
+            Sort::MostRecentFirst => {
+                group.sort_by_key(|item| std::cmp::Reverse(item.child().last_used))
+            }
+            Sort::Alphabetical => {
+                group.sort_by_cached_key(|item| strip_mnemonic(&item.child().text()).to_lowercase())
+            }
+            Sort::None => {}
+        }
+        sorted.extend(group);
+        sorted.extend(separator.cloned());
+    }
+
+    let unchanged = items
+        .iter()
+        .zip(&sorted)
+        .all(|(a, b)| std::ptr::eq(&*a.child(), &*b.child()));
+    if !unchanged {
+        let sorted = sorted.iter().map(|i| i.as_ref()).collect::<Vec<_>>();
+        // the items are already in this submenu, so their accelerators are valid
+        let _ = submenu.set_items(&sorted);
+    }
+}
+
+fn is_separator(item: &MenuItemKind) -> bool {
+    matches!(
+        item.child().predefined_item_type,
+        Some(PredefinedMenuItemType::Separator)
+    )
+}
@@ -0,0 +1,68 @@
+//! Clickable submenu titles, see [`Submenu::set_title_clickable`](crate::Submenu::set_title_clickable).
+//!
+//! Win32 opens a submenu when its title is clicked instead of sending `WM_COMMAND`, so the
+//! [`msg_filter`](super::msg_filter) hook watches the menu loop for a click on the highlighted
+//! title of a clickable submenu, closes the menu and posts [`MENU_TITLE_CLICKED`] to the owner window.
+
+use std::cell::Cell;
+
+use windows_sys::Win32::{
+    Foundation::HWND,
+    UI::WindowsAndMessaging::{EndMenu, GetMenu, MenuItemFromPoint, PostMessageW, HMENU, MSG},
+};
+
+/// Posted to the owner window when the title of a clickable submenu is clicked,
+/// `wparam` is the id of the submenu.
+pub(crate) const MENU_TITLE_CLICKED: u32 = 202;
+
+/// A highlighted clickable submenu title.
+#[derive(Clone, Copy)]
+struct Title {
+    owner: HWND,
+    parent: HMENU,
+    position: u32,
+    submenu: HMENU,
+}
+
+thread_local! {
+    static HIGHLIGHTED: Cell<Option<Title>> = const { Cell::new(None) };
+}
+
+/// Records the highlighted item of the menus of `owner`, `clickable_submenu` is the submenu
+/// at `position` in `parent` if it is highlighted and its title is clickable.
+pub(crate) fn set_highlighted(
+    owner: HWND,
+    parent: HMENU,
+    position: u32,
+    clickable_submenu: Option<HMENU>,
+) {
+    HIGHLIGHTED.set(clickable_submenu.map(|submenu| Title {
+        owner,
+        parent,
+        position,
+        submenu,
+    }));
+}
+
+/// Whether the `WM_LBUTTONUP` `msg` of a menu loop clicked the highlighted clickable title,
+/// the menu is then closed and [`MENU_TITLE_CLICKED`] is posted.
+pub(crate) unsafe fn clicked(msg: &MSG) -> bool {
+    let Some(title) = HIGHLIGHTED.get() else {
+        return false;
+    };
+
+    // the menu bar isn't a popup, so it is found through its window
+    let hwnd = if GetMenu(title.owner) == title.parent {
+        title.owner
+    } else {
+        std::ptr::null_mut()
+    };
+    if MenuItemFromPoint(hwnd, title.parent, msg.pt) != title.position as i32 {
+        return false;
+    }
+
+    HIGHLIGHTED.set(None);
+    EndMenu();
+    PostMessageW(title.owner, MENU_TITLE_CLICKED, title.submenu as _, 0);
+    true
+}
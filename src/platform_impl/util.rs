@@ -1,4 +1,5 @@
 use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
 use std::sync::LazyLock;
 use windows_sys::{
     core::HRESULT,
@@ -7,10 +8,16 @@ use windows_sys::{
         Graphics::Gdi::{
             GetDC, GetDeviceCaps, MonitorFromWindow, HMONITOR, LOGPIXELSX, MONITOR_DEFAULTTONEAREST,
         },
-        System::LibraryLoader::{GetProcAddress, LoadLibraryW},
+        System::{
+            LibraryLoader::{GetProcAddress, LoadLibraryW},
+            Threading::GetCurrentThreadId,
+        },
         UI::{
             HiDpi::{MDT_EFFECTIVE_DPI, MONITOR_DPI_TYPE},
-            WindowsAndMessaging::{IsProcessDPIAware, ACCEL},
+            WindowsAndMessaging::{
+                GetMenuInfo, IsGUIThread, IsProcessDPIAware, SetMenuInfo, ACCEL, HMENU, MENUINFO,
+                MIM_MAXHEIGHT,
+            },
         },
     },
 };
@@ -26,12 +33,59 @@ pub fn LOWORD(dword: u32) -> u16 {
     (dword & 0xFFFF) as u16
 }
 
+#[allow(non_snake_case)]
+pub fn HIWORD(dword: u32) -> u16 {
+    ((dword & 0xFFFF_0000) >> 16) as u16
+}
+
 pub fn decode_wide(w_str: *mut u16) -> String {
     let len = unsafe { windows_sys::Win32::Globalization::lstrlenW(w_str) } as usize;
     let w_str_slice = unsafe { std::slice::from_raw_parts(w_str, len) };
     String::from_utf16_lossy(w_str_slice)
 }
 
+/// The maximum height of the popup `hmenu`, `0` if it isn't limited.
+pub unsafe fn menu_max_height(hmenu: HMENU) -> u32 {
+    let mut info: MENUINFO = std::mem::zeroed();
+    info.cbSize = std::mem::size_of::<MENUINFO>() as _;
+    info.fMask = MIM_MAXHEIGHT;
+    if GetMenuInfo(hmenu, &mut info) == 0 {
+        return 0;
+    }
+    info.cyMax
+}
+
+/// Limits the height of the popup `hmenu`, the system adds scroll arrows to popups that
+/// don't fit. `0` removes the limit.
+pub unsafe fn set_menu_max_height(hmenu: HMENU, height: u32) {
+    let mut info: MENUINFO = std::mem::zeroed();
+    info.cbSize = std::mem::size_of::<MENUINFO>() as _;
+    info.fMask = MIM_MAXHEIGHT;
+    info.cyMax = height;
+    SetMenuInfo(hmenu, &info);
+}
+
+/// Removes the mnemonic markers from a menu label, `&&` becomes a literal `&`.
+pub fn strip_mnemonic(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '&' {
+            if let Some(next) = chars.next() {
+                stripped.push(next);
+            }
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+/// Doubles the `&` of `text` so a menu label shows it verbatim, the reverse of [`strip_mnemonic`].
+pub fn escape_mnemonics(text: &str) -> String {
+    text.replace('&', "&&")
+}
+
 /// ACCEL wrapper to implement Debug
 #[derive(Clone)]
 #[repr(transparent)]
@@ -47,6 +101,29 @@ impl std::fmt::Debug for Accel {
     }
 }
 
+/// Callback wrapper to implement Debug
+pub struct Callback<F: ?Sized>(pub Rc<F>);
+
+impl<F: ?Sized> Clone for Callback<F> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<F: ?Sized> std::fmt::Debug for Callback<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Callback")
+    }
+}
+
+impl<F: ?Sized> Deref for Callback<F> {
+    type Target = F;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
 impl Deref for Accel {
     type Target = ACCEL;
 
@@ -61,6 +138,35 @@ impl DerefMut for Accel {
     }
 }
 
+/// Returns [`Error::WrongThread`](crate::Error::WrongThread) if the calling thread can't own menus,
+/// which happens when it can't be converted to a GUI thread with a message queue.
+pub fn check_ui_thread() -> crate::Result<()> {
+    if unsafe { IsGUIThread(1) } != 0 {
+        Ok(())
+    } else {
+        Err(crate::Error::WrongThread)
+    }
+}
+
+/// The id of the calling thread, which owns the menu or item being created.
+pub fn owner_thread_id() -> u32 {
+    debug_assert!(
+        check_ui_thread().is_ok(),
+        "menus and menu items can only be created on a thread that can have a message queue"
+    );
+    unsafe { GetCurrentThreadId() }
+}
+
+/// Returns [`Error::WrongThread`](crate::Error::WrongThread) unless called from `thread_id`,
+/// the thread that created a menu.
+pub fn check_thread(thread_id: u32) -> crate::Result<()> {
+    if unsafe { GetCurrentThreadId() } == thread_id {
+        Ok(())
+    } else {
+        Err(crate::Error::WrongThread)
+    }
+}
+
 // taken from winit's code base
 // https://github.com/rust-windowing/winit/blob/ee88e38f13fbc86a7aafae1d17ad3cd4a1e761df/src/platform_impl/windows/util.rs#L138
 pub fn get_instance_handle() -> windows_sys::Win32::Foundation::HMODULE {
@@ -0,0 +1,161 @@
+//! Hidden items, see [`MenuItem::set_visible`](crate::MenuItem::set_visible).
+//!
+//! Win32 menus have no hidden items, so a hidden item is removed from the native menus of its
+//! parents while staying in their children. Items don't know their siblings, so showing one
+//! only marks it visible: its parents insert it back at its position among the items that
+//! are in the native menu when a popup is about to open, or right away for menu bars through
+//! a posted message, see [`insert_shown`].
+
+use std::{cell::RefCell, rc::Rc};
+
+use windows_sys::Win32::{
+    Graphics::Gdi::HBITMAP,
+    UI::WindowsAndMessaging::{
+        GetMenuItemInfoW, InsertMenuItemW, RemoveMenu, HMENU, MENUITEMINFOW, MFS_CHECKED,
+        MFS_DISABLED, MFS_HILITE, MFT_OWNERDRAW, MFT_RADIOCHECK, MF_BYCOMMAND, MIIM_BITMAP,
+        MIIM_CHECKMARKS, MIIM_DATA, MIIM_FTYPE, MIIM_ID, MIIM_STATE, MIIM_STRING, MIIM_SUBMENU,
+    },
+};
+
+use super::{encode_wide, MenuChild};
+use crate::MenuItemType;
+
+/// The native item of a hidden item, removed from `parent`.
+///
+/// Only what the item doesn't know is kept, the rest may change while it is hidden.
+#[derive(Debug)]
+pub(crate) struct HiddenItem {
+    pub parent: HMENU,
+    id: u32,
+    f_type: u32,
+    state: u32,
+    submenu: HMENU,
+    checked_bitmap: HBITMAP,
+    unchecked_bitmap: HBITMAP,
+    data: usize,
+}
+
+/// Removes the item with the command `id` from `parent`, returns it unless it isn't there.
+pub(crate) unsafe fn remove(parent: HMENU, id: u32) -> Option<HiddenItem> {
+    let mut info: MENUITEMINFOW = std::mem::zeroed();
+    info.cbSize = std::mem::size_of::<MENUITEMINFOW>() as _;
+    info.fMask = MIIM_FTYPE
+        | MIIM_STATE
+        | MIIM_ID
+        | MIIM_SUBMENU
+        | MIIM_BITMAP
+        | MIIM_CHECKMARKS
+        | MIIM_DATA;
+    if GetMenuItemInfoW(parent, id, false.into(), &mut info) == 0 {
+        return None;
+    }
+    RemoveMenu(parent, id, MF_BYCOMMAND);
+
+    Some(HiddenItem {
+        parent,
+        id: info.wID,
+        f_type: info.fType,
+        state: info.fState,
+        submenu: info.hSubMenu,
+        checked_bitmap: info.hbmpChecked,
+        unchecked_bitmap: info.hbmpUnchecked,
+        data: info.dwItemData,
+    })
+}
+
+impl HiddenItem {
+    /// Inserts `item` back at `position` in the parent, with its current label, state,
+    /// icon and owner-drawn type.
+    unsafe fn insert(&self, item: &MenuChild, position: u32) {
+        let mut state = self.state & !(MFS_CHECKED | MFS_DISABLED | MFS_HILITE);
+        if !item.enabled {
+            state |= MFS_DISABLED;
+        }
+        if item.item_type == MenuItemType::Check && item.checked {
+            state |= MFS_CHECKED;
+        }
+        let mut f_type = self.f_type & !(MFT_OWNERDRAW | MFT_RADIOCHECK);
+        let menu_bar = item
+            .parents_hemnu
+            .iter()
+            .any(|(parent, menu_bars)| *parent == self.parent && menu_bars.is_some());
+        if item.is_owner_drawn_in(menu_bar) {
+            f_type |= MFT_OWNERDRAW;
+        }
+        if item.radio_group.is_some() {
+            f_type |= MFT_RADIOCHECK;
+        }
+        let bitmap = item
+            .icon
+            .as_ref()
+            .map_or(std::ptr::null_mut(), |icon| icon.inner.to_hbitmap());
+        let mut text = encode_wide(item.label(self.parent));
+
+        let mut info: MENUITEMINFOW = std::mem::zeroed();
+        info.cbSize = std::mem::size_of::<MENUITEMINFOW>() as _;
+        info.fMask = MIIM_FTYPE
+            | MIIM_STATE
+            | MIIM_ID
+            | MIIM_SUBMENU
+            | MIIM_BITMAP
+            | MIIM_CHECKMARKS
+            | MIIM_DATA
+            | MIIM_STRING;
+        info.fType = f_type;
+        info.fState = state;
+        info.wID = self.id;
+        info.hSubMenu = self.submenu;
+        info.hbmpItem = bitmap;
+        info.hbmpChecked = self.checked_bitmap;
+        info.hbmpUnchecked = self.unchecked_bitmap;
+        info.dwItemData = self.data;
+        info.dwTypeData = text.as_mut_ptr();
+        InsertMenuItemW(self.parent, position, true.into(), &info);
+    }
+}
+
+/// The position in `parent` of an item inserted at `index` in `children`,
+/// where `parent` is one of the native menus of their parent.
+pub(crate) fn native_position(
+    parent: HMENU,
+    children: &[Rc<RefCell<MenuChild>>],
+    index: usize,
+) -> usize {
+    children
+        .iter()
+        .take(index)
+        // a submenu shown as a context menu is borrowed, it is visible
+        .filter(|c| c.try_borrow().map_or(true, |c| !c.is_hidden_in(parent)))
+        .count()
+}
+
+/// Inserts the items of `children` that were shown again into `hmenus`, the native menus
+/// of their parent, and does the same in the submenus among them.
+pub(crate) unsafe fn insert_shown(hmenus: [HMENU; 2], children: &[Rc<RefCell<MenuChild>>]) {
+    let mut positions = [0; 2];
+    for child in children {
+        let Ok(mut child) = child.try_borrow_mut() else {
+            positions.iter_mut().for_each(|p| *p += 1);
+            continue;
+        };
+
+        for (hmenu, position) in hmenus.iter().zip(&mut positions) {
+            match child.hidden_in.iter().position(|h| h.parent == *hmenu) {
+                Some(index) if child.visible => {
+                    let hidden = child.hidden_in.remove(index);
+                    hidden.insert(&child, *position);
+                    *position += 1;
+                }
+                Some(_) => {}
+                None => *position += 1,
+            }
+        }
+
+        if child.item_type == MenuItemType::Submenu {
+            insert_shown(
+                [child.hmenu, child.hpopupmenu],
+                child.children.as_ref().unwrap(),
+            );
+        }
+    }
+}
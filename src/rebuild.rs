@@ -0,0 +1,190 @@
+//! Converging a menu to the structure of another one, see [`Menu::rebuild_from`](crate::Menu::rebuild_from).
+
+use std::mem::discriminant;
+
+use crate::{
+    platform_impl::StateChange, CheckMenuItem, IconMenuItem, IsMenuItem, Menu, MenuItem,
+    MenuItemKind, PredefinedMenuItem, Submenu,
+};
+
+/// The operations of [`Menu`] and [`Submenu`] needed to converge their items.
+pub(crate) trait ItemContainer {
+    fn items(&self) -> Vec<MenuItemKind>;
+    fn insert(&self, item: &dyn IsMenuItem, position: usize) -> crate::Result<()>;
+    fn remove(&self, item: &dyn IsMenuItem) -> crate::Result<()>;
+}
+
+impl ItemContainer for Menu {
+    fn items(&self) -> Vec<MenuItemKind> {
+        Menu::items(self)
+    }
+
+    fn insert(&self, item: &dyn IsMenuItem, position: usize) -> crate::Result<()> {
+        Menu::insert(self, item, position)
+    }
+
+    fn remove(&self, item: &dyn IsMenuItem) -> crate::Result<()> {
+        Menu::remove(self, item)
+    }
+}
+
+impl ItemContainer for Submenu {
+    fn items(&self) -> Vec<MenuItemKind> {
+        Submenu::items(self)
+    }
+
+    fn insert(&self, item: &dyn IsMenuItem, position: usize) -> crate::Result<()> {
+        Submenu::insert(self, item, position)
+    }
+
+    fn remove(&self, item: &dyn IsMenuItem) -> crate::Result<()> {
+        Submenu::remove(self, item)
+    }
+}
+
+/// Inserts, removes, moves and updates the items of `target` until they match `desired`,
+/// returns the number of operations that were applied.
+pub(crate) fn sync_items(
+    target: &dyn ItemContainer,
+    desired: &[MenuItemKind],
+) -> crate::Result<usize> {
+    let mut ops = 0;
+
+    for item in target.items() {
+        if !desired.iter().any(|d| is_same_item(&item, d)) {
+            target.remove(item.as_ref())?;
+            ops += 1;
+        }
+    }
+
+    for (i, desired_item) in desired.iter().enumerate() {
+        let current = target.items();
+        let found = current
+            .iter()
+            .skip(i)
+            .position(|c| is_same_item(c, desired_item))
+            .map(|p| p + i);
+
+        match found {
+            Some(position) => {
+                let item = &current[position];
+                if position != i {
+                    target.remove(item.as_ref())?;
+                    target.insert(item.as_ref(), i)?;
+                    ops += 2;
+                }
+                ops += sync_item(item, desired_item)?;
+            }
+            None => {
+                // a copy, `desired` keeps its items to itself
+                let item = copy_item(desired_item)?;
+                target.insert(item.as_ref(), i)?;
+                ops += 1;
+            }
+        }
+    }
+
+    // duplicates left behind after matching
+    for item in target.items().iter().skip(desired.len()) {
+        target.remove(item.as_ref())?;
+        ops += 1;
+    }
+
+    Ok(ops)
+}
+
+/// Predefined items are matched by their text since their ids are always generated,
+/// other items are matched by their kind and id.
+fn is_same_item(a: &MenuItemKind, b: &MenuItemKind) -> bool {
+    match (a, b) {
+        (MenuItemKind::Predefined(a), MenuItemKind::Predefined(b)) => a.text() == b.text(),
+        _ => discriminant(a) == discriminant(b) && a.id() == b.id(),
+    }
+}
+
+/// Copies the state of `desired` into `item`, returns the number of properties that changed.
+fn sync_item(item: &MenuItemKind, desired: &MenuItemKind) -> crate::Result<usize> {
+    if let MenuItemKind::Predefined(_) = item {
+        return Ok(0);
+    }
+
+    let mut ops = 0;
+    let mut changes = Vec::new();
+    let checked;
+
+    {
+        // read everything first, `item` may still be an item that was shared with `desired`
+        let (state, desired_state) = (item.child().state(), desired.child().state());
+        let (accelerator, desired_accelerator) =
+            (item.child().accelerator(), desired.child().accelerator());
+        let mut child = item.child_mut();
+        if state.text != desired_state.text {
+            changes.push(child.set_text(&desired_state.text));
+            ops += 1;
+        }
+        if state.enabled != desired_state.enabled {
+            changes.push(child.set_enabled(desired_state.enabled));
+            ops += 1;
+        }
+        if accelerator != desired_accelerator {
+            child.set_accelerator(desired_accelerator)?;
+            ops += 1;
+        }
+        checked = desired_state.checked.filter(|c| state.checked != Some(*c));
+    }
+    // reported once `item` isn't borrowed anymore, so subscribers can read it
+    changes.into_iter().for_each(StateChange::notify);
+    if let (Some(checked), MenuItemKind::Check(check)) = (checked, item) {
+        // unchecks the other items of its radio group
+        check.set_checked(checked);
+        ops += 1;
+    }
+
+    if let (MenuItemKind::Submenu(submenu), MenuItemKind::Submenu(desired)) = (item, desired) {
+        ops += sync_items(submenu, &desired.items())?;
+    }
+
+    Ok(ops)
+}
+
+/// A new item with the id and state of `item`, and copies of its items for submenus.
+fn copy_item(item: &MenuItemKind) -> crate::Result<MenuItemKind> {
+    let child = item.child();
+    let (id, text, enabled) = (child.id().clone(), child.text(), child.is_enabled());
+    let accelerator = child.accelerator();
+
+    Ok(match item {
+        MenuItemKind::MenuItem(_) => {
+            MenuItemKind::MenuItem(MenuItem::with_id(id, text, enabled, accelerator))
+        }
+        MenuItemKind::Check(_) => {
+            let (checked, group) = (child.is_checked(), child.radio_group().map(str::to_string));
+            // joining a group checked unchecks the other members, `item` may be one of them
+            drop(child);
+            let copy = CheckMenuItem::with_id(id, text, enabled, checked, accelerator);
+            if let Some(group) = group {
+                crate::platform_impl::radio::join(&copy.inner, &group);
+            }
+            MenuItemKind::Check(copy)
+        }
+        MenuItemKind::Icon(_) => MenuItemKind::Icon(IconMenuItem::with_id(
+            id,
+            text,
+            enabled,
+            child.icon(),
+            accelerator,
+        )),
+        MenuItemKind::Predefined(_) => {
+            let item_type = child.predefined_item_type().cloned().unwrap();
+            MenuItemKind::Predefined(PredefinedMenuItem::new(item_type, Some(text)))
+        }
+        MenuItemKind::Submenu(submenu) => {
+            drop(child);
+            let copy = Submenu::with_id(id, text, enabled);
+            for item in submenu.items() {
+                copy.append(copy_item(&item)?.as_ref())?;
+            }
+            MenuItemKind::Submenu(copy)
+        }
+    })
+}
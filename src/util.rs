@@ -23,3 +23,16 @@ impl Counter {
         self.0.fetch_add(1, Ordering::Relaxed)
     }
 }
+
+/// Returns the `HWND` of `window`, or an error if it isn't a Win32 window.
+#[cfg(feature = "raw-window-handle")]
+pub fn hwnd_of(window: &dyn raw_window_handle::HasWindowHandle) -> crate::Result<isize> {
+    match window
+        .window_handle()
+        .map_err(crate::Error::WindowHandle)?
+        .as_raw()
+    {
+        raw_window_handle::RawWindowHandle::Win32(handle) => Ok(handle.hwnd.get()),
+        _ => Err(crate::Error::NotAWin32Window),
+    }
+}